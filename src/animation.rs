@@ -0,0 +1,352 @@
+use nalgebra_glm as glm;
+
+use crate::scene::{NodeId, Scene};
+
+// Joints per skin supported by the lit programs
+pub const MAX_JOINTS: usize = 64;
+
+// GLSL vertex shader declarations for skinning with the SkinVertex attributes. getSkinMatrix()
+// returns the identity when uSkinned is false, set by Renderer::draw_scene from the node skin
+pub(crate) fn skinning_shader_src() -> String {
+    format!(
+        "layout (location = 4) in vec4 aJoints;
+layout (location = 5) in vec4 aWeights;
+
+uniform bool uSkinned;
+uniform mat4 uJointMatrices[{MAX_JOINTS}];
+
+mat4 getSkinMatrix() {{
+    if (!uSkinned) {{
+        return mat4(1.0);
+    }}
+    return aWeights.x * uJointMatrices[int(aJoints.x)]
+        + aWeights.y * uJointMatrices[int(aJoints.y)]
+        + aWeights.z * uJointMatrices[int(aJoints.z)]
+        + aWeights.w * uJointMatrices[int(aJoints.w)];
+}}
+"
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    // Each keyframe stores (in tangent, value, out tangent)
+    CubicSpline,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationProperty {
+    Translation,
+    // Quaternions stored as (x, y, z, w)
+    Rotation,
+    Scale,
+}
+
+// Keyframes of one property of one node. Values are Vec4 for all the properties, translations
+// and scales have w = 0
+pub struct AnimationChannel {
+    pub node: NodeId,
+    pub property: AnimationProperty,
+    pub interpolation: Interpolation,
+    // Keyframe times in seconds, increasing
+    pub times: Vec<f32>,
+    pub values: Vec<glm::Vec4>,
+}
+
+impl AnimationChannel {
+    // Times before the first keyframe or after the last one are clamped
+    pub fn sample(&self, time: f32) -> glm::Vec4 {
+        let value = |keyframe: usize| match self.interpolation {
+            Interpolation::CubicSpline => self.values[keyframe * 3 + 1],
+            _ => self.values[keyframe],
+        };
+
+        let last = self.times.len() - 1;
+        if time <= self.times[0] {
+            return value(0);
+        }
+        if time >= self.times[last] {
+            return value(last);
+        }
+
+        // Keyframe before the time
+        let k = self.times.partition_point(|&t| t <= time) - 1;
+        let delta = self.times[k + 1] - self.times[k];
+        let s = (time - self.times[k]) / delta;
+
+        match self.interpolation {
+            Interpolation::Step => value(k),
+            Interpolation::Linear => match self.property {
+                AnimationProperty::Rotation => {
+                    let a = glm::Quat::from_vector(value(k));
+                    let b = glm::Quat::from_vector(value(k + 1));
+                    glm::quat_slerp(&a, &b, s).coords
+                }
+                _ => glm::lerp(&value(k), &value(k + 1), s),
+            },
+            Interpolation::CubicSpline => {
+                // Hermite spline, the tangents are scaled by the keyframe duration
+                let (s2, s3) = (s * s, s * s * s);
+                let out_tangent = self.values[k * 3 + 2];
+                let in_tangent = self.values[(k + 1) * 3];
+                let result = value(k) * (2.0 * s3 - 3.0 * s2 + 1.0)
+                    + out_tangent * delta * (s3 - 2.0 * s2 + s)
+                    + value(k + 1) * (-2.0 * s3 + 3.0 * s2)
+                    + in_tangent * delta * (s3 - s2);
+                match self.property {
+                    AnimationProperty::Rotation => result.normalize(),
+                    _ => result,
+                }
+            }
+        }
+    }
+}
+
+pub struct Animation {
+    pub name: String,
+    pub channels: Vec<AnimationChannel>,
+}
+
+impl Animation {
+    // Time of the last keyframe
+    pub fn get_duration(&self) -> f32 {
+        self.channels
+            .iter()
+            .filter_map(|channel| channel.times.last().copied())
+            .fold(0.0, f32::max)
+    }
+
+    // Sets the animated transforms of the scene nodes. Use time % get_duration() to loop
+    pub fn apply(&self, scene: &mut Scene, time: f32) {
+        for channel in &self.channels {
+            let value = channel.sample(time);
+            let transform = scene.get_transform_mut(channel.node);
+            match channel.property {
+                AnimationProperty::Translation => transform.set_translation(value.xyz()),
+                AnimationProperty::Rotation => {
+                    transform.set_rotation(glm::Quat::from_vector(value))
+                }
+                AnimationProperty::Scale => transform.set_scale(value.xyz()),
+            }
+        }
+    }
+}
+
+// Joints of a skinned mesh. The vertices reference the joints by their index in this list, at
+// most MAX_JOINTS
+pub struct Skin {
+    pub name: String,
+    pub joints: Vec<NodeId>,
+    // Mesh space to joint space in the bind pose, one per joint
+    pub inverse_bind_matrices: Vec<glm::Mat4>,
+}
+
+impl Skin {
+    // Matrices to upload for skinning, from the mesh space of the bind pose to the current
+    // mesh space. mesh_node: node that draws the skinned mesh
+    pub fn get_joint_matrices(&self, scene: &Scene, mesh_node: NodeId) -> Vec<glm::Mat4> {
+        scene.update_transforms();
+
+        let world_to_mesh = glm::inverse(&scene.get_node(mesh_node).get_world_matrix());
+        self.joints
+            .iter()
+            .zip(&self.inverse_bind_matrices)
+            .map(|(&joint, inverse_bind)| {
+                world_to_mesh * scene.get_node(joint).get_world_matrix() * inverse_bind
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::SceneNode;
+    use crate::transform::Transform;
+
+    fn channel(
+        scene: &mut Scene,
+        property: AnimationProperty,
+        interpolation: Interpolation,
+        times: &[f32],
+        values: &[glm::Vec4],
+    ) -> AnimationChannel {
+        AnimationChannel {
+            node: scene.add_node(SceneNode::new("node", Transform::default())),
+            property,
+            interpolation,
+            times: times.to_vec(),
+            values: values.to_vec(),
+        }
+    }
+
+    fn assert_near(a: glm::Vec4, b: glm::Vec4) {
+        assert!((a - b).norm() < 1e-5, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn step() {
+        let mut scene = Scene::new();
+        let values = [glm::vec4(0.0, 0.0, 0.0, 0.0), glm::vec4(2.0, 4.0, 6.0, 0.0)];
+        let channel = channel(
+            &mut scene,
+            AnimationProperty::Translation,
+            Interpolation::Step,
+            &[0.0, 1.0],
+            &values,
+        );
+        assert_eq!(channel.sample(0.0), values[0]);
+        assert_eq!(channel.sample(0.99), values[0]);
+        assert_eq!(channel.sample(1.0), values[1]);
+    }
+
+    #[test]
+    fn linear_translation() {
+        let mut scene = Scene::new();
+        let channel = channel(
+            &mut scene,
+            AnimationProperty::Translation,
+            Interpolation::Linear,
+            &[1.0, 2.0, 4.0],
+            &[
+                glm::vec4(0.0, 0.0, 0.0, 0.0),
+                glm::vec4(2.0, 4.0, 6.0, 0.0),
+                glm::vec4(4.0, 4.0, 4.0, 0.0),
+            ],
+        );
+        assert_near(channel.sample(1.5), glm::vec4(1.0, 2.0, 3.0, 0.0));
+        assert_near(channel.sample(2.0), glm::vec4(2.0, 4.0, 6.0, 0.0));
+        assert_near(channel.sample(3.0), glm::vec4(3.0, 4.0, 5.0, 0.0));
+    }
+
+    #[test]
+    fn clamps_outside_the_keyframes() {
+        let mut scene = Scene::new();
+        let values = [glm::vec4(1.0, 1.0, 1.0, 0.0), glm::vec4(3.0, 3.0, 3.0, 0.0)];
+        let channel = channel(
+            &mut scene,
+            AnimationProperty::Scale,
+            Interpolation::Linear,
+            &[1.0, 2.0],
+            &values,
+        );
+        assert_eq!(channel.sample(-1.0), values[0]);
+        assert_eq!(channel.sample(0.5), values[0]);
+        assert_eq!(channel.sample(10.0), values[1]);
+    }
+
+    #[test]
+    fn single_keyframe() {
+        let mut scene = Scene::new();
+        let value = glm::vec4(1.0, 2.0, 3.0, 0.0);
+        let channel = channel(
+            &mut scene,
+            AnimationProperty::Translation,
+            Interpolation::Linear,
+            &[0.5],
+            &[value],
+        );
+        assert_eq!(channel.sample(0.0), value);
+        assert_eq!(channel.sample(1.0), value);
+    }
+
+    #[test]
+    fn linear_rotation_slerps() {
+        let mut scene = Scene::new();
+        let quarter_turn = glm::quat_angle_axis(std::f32::consts::FRAC_PI_2, &glm::Vec3::y());
+        let channel = channel(
+            &mut scene,
+            AnimationProperty::Rotation,
+            Interpolation::Linear,
+            &[0.0, 1.0],
+            &[glm::quat_identity().coords, quarter_turn.coords],
+        );
+
+        let eighth_turn = glm::quat_angle_axis(std::f32::consts::FRAC_PI_4, &glm::Vec3::y());
+        let sample = channel.sample(0.5);
+        assert_near(sample, eighth_turn.coords);
+        assert!((sample.norm() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn cubic_spline_with_zero_tangents() {
+        let mut scene = Scene::new();
+        let zero = glm::Vec4::zeros();
+        let channel = channel(
+            &mut scene,
+            AnimationProperty::Translation,
+            Interpolation::CubicSpline,
+            &[0.0, 2.0],
+            &[
+                zero,
+                glm::vec4(0.0, 0.0, 0.0, 0.0),
+                zero,
+                zero,
+                glm::vec4(4.0, 8.0, 0.0, 0.0),
+                zero,
+            ],
+        );
+        assert_near(channel.sample(0.0), glm::vec4(0.0, 0.0, 0.0, 0.0));
+        // Ease in and out: halfway in the middle, less than linear before it
+        assert_near(channel.sample(1.0), glm::vec4(2.0, 4.0, 0.0, 0.0));
+        assert!(channel.sample(0.5).x < 1.0);
+        assert_near(channel.sample(2.0), glm::vec4(4.0, 8.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn cubic_spline_tangents() {
+        let mut scene = Scene::new();
+        let zero = glm::Vec4::zeros();
+        // Constant velocity of 1 unit per second along x
+        let tangent = glm::vec4(1.0, 0.0, 0.0, 0.0);
+        let channel = channel(
+            &mut scene,
+            AnimationProperty::Translation,
+            Interpolation::CubicSpline,
+            &[0.0, 2.0],
+            &[
+                tangent,
+                zero,
+                tangent,
+                tangent,
+                glm::vec4(2.0, 0.0, 0.0, 0.0),
+                tangent,
+            ],
+        );
+        assert_near(channel.sample(0.5), glm::vec4(0.5, 0.0, 0.0, 0.0));
+        assert_near(channel.sample(1.5), glm::vec4(1.5, 0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn animation_duration_and_apply() {
+        let mut scene = Scene::new();
+        let translation = channel(
+            &mut scene,
+            AnimationProperty::Translation,
+            Interpolation::Linear,
+            &[0.0, 2.0],
+            &[glm::vec4(0.0, 0.0, 0.0, 0.0), glm::vec4(2.0, 0.0, 0.0, 0.0)],
+        );
+        let scale = channel(
+            &mut scene,
+            AnimationProperty::Scale,
+            Interpolation::Linear,
+            &[0.0, 3.0],
+            &[glm::vec4(1.0, 1.0, 1.0, 0.0), glm::vec4(4.0, 4.0, 4.0, 0.0)],
+        );
+        let (translated, scaled) = (translation.node, scale.node);
+        let animation = Animation {
+            name: String::from("animation"),
+            channels: vec![translation, scale],
+        };
+        assert_eq!(animation.get_duration(), 3.0);
+
+        animation.apply(&mut scene, 1.0);
+        let transform = &scene.get_node(translated).transform;
+        assert!((transform.get_translation() - glm::vec3(1.0, 0.0, 0.0)).norm() < 1e-5);
+        let transform = &scene.get_node(scaled).transform;
+        assert!((transform.get_scale() - glm::vec3(2.0, 2.0, 2.0)).norm() < 1e-5);
+    }
+}
@@ -0,0 +1,21 @@
+use crate::event::Event;
+use crate::window::Window;
+
+// Callbacks called by Window::run. Every method has an empty default implementation
+pub trait Application {
+    // Called at a fixed rate (FrameClock::get_fixed_timestep), zero or more times per frame.
+    // Deterministic simulation goes here, interpolate its state in render() with
+    // window.get_clock().get_alpha()
+    fn fixed_update(&mut self, _window: &mut Window, _dt: f32) {}
+
+    // dt: seconds since the previous update
+    fn update(&mut self, _window: &mut Window, _dt: f32) {}
+
+    fn render(&mut self, _window: &mut Window) {}
+
+    // Called for every event, after the input state has been updated
+    fn on_event(&mut self, _window: &mut Window, _event: &Event) {}
+
+    // New framebuffer size in pixels. The viewport has already been updated
+    fn on_resize(&mut self, _window: &mut Window, _width: u32, _height: u32) {}
+}
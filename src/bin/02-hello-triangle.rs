@@ -167,7 +167,7 @@ fn main() {
         }
 
         w.window.swap_buffers();
-        w.glfw.poll_events();
+        w.poll_events();
     }
 
     unsafe {
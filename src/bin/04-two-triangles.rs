@@ -99,7 +99,7 @@ fn main() {
         program.unbind();
 
         w.window.swap_buffers();
-        w.glfw.poll_events();
+        w.poll_events();
     }
 
     unsafe {
@@ -193,6 +193,6 @@ fn main() {
         }
 
         w.window.swap_buffers();
-        w.glfw.poll_events();
+        w.poll_events();
     }
 }
@@ -60,10 +60,10 @@ fn main() {
     ];
 
     let triangle_layouts = VertexBufferLayout::new(VertexBufferLayoutType::F32, 3, false);
-    let vao1 = VertexArray::new(&triangle, &triangle_indices, &triangle_layouts);
+    let vao1 = VertexArray::with_layout(&triangle, &triangle_indices, &triangle_layouts);
 
     let rectangle_layouts = VertexBufferLayout::new(VertexBufferLayoutType::F32, 3, false);
-    let vao2 = VertexArray::new(&rectangle, &rectangle_indices, &rectangle_layouts);
+    let vao2 = VertexArray::with_layout(&rectangle, &rectangle_indices, &rectangle_layouts);
 
     while !w.window.should_close() {
         renderer.clear();
@@ -71,6 +71,6 @@ fn main() {
         renderer.draw(&vao2, &program2);
 
         w.window.swap_buffers();
-        w.glfw.poll_events();
+        w.poll_events();
     }
 }
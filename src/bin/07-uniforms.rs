@@ -39,16 +39,16 @@ fn main() {
     let indices = [0u32, 1, 2];
 
     let layouts = VertexBufferLayout::new(VertexBufferLayoutType::F32, 3, false);
-    let vao = VertexArray::new(&vertices, &indices, &layouts);
+    let vao = VertexArray::with_layout(&vertices, &indices, &layouts);
 
     while !w.window.should_close() {
-        let green_color = (f32::sin(w.get_time()) / 2.0) + 0.5;
+        let green_color = (f32::sin(w.get_time() as f32) / 2.0) + 0.5;
         program.set_uniform_4f("u_GreenColor", 0.0, green_color, 0.0, 1.0);
 
         renderer.clear();
         renderer.draw(&vao, &program);
 
         w.window.swap_buffers();
-        w.glfw.poll_events();
+        w.poll_events();
     }
 }
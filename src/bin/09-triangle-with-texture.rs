@@ -57,13 +57,13 @@ fn main() {
     let mut layouts = VertexBufferLayout::new(VertexBufferLayoutType::F32, 3, false);
     layouts.add(VertexBufferLayoutType::F32, 3, false);
     layouts.add(VertexBufferLayoutType::F32, 2, false);
-    let vao = VertexArray::new(&vertices, &indices, &layouts);
+    let vao = VertexArray::with_layout(&vertices, &indices, &layouts);
 
     while !w.window.should_close() {
         renderer.clear();
         renderer.draw(&vao, &program);
 
         w.window.swap_buffers();
-        w.glfw.poll_events();
+        w.poll_events();
     }
 }
@@ -68,8 +68,8 @@ fn main() {
 
     let mut layouts = VertexBufferLayout::new(VertexBufferLayoutType::F32, 3, false);
     layouts.add(VertexBufferLayoutType::F32, 4, false);
-    let vao1 = VertexArray::new(&rectangle1_vertices, &rectangle1_indices, &layouts);
-    let vao2 = VertexArray::new(&rectangle2_vertices, &rectangle2_indices, &layouts);
+    let vao1 = VertexArray::with_layout(&rectangle1_vertices, &rectangle1_indices, &layouts);
+    let vao2 = VertexArray::with_layout(&rectangle2_vertices, &rectangle2_indices, &layouts);
 
     while !w.window.should_close() {
         renderer.clear();
@@ -77,6 +77,6 @@ fn main() {
         renderer.draw(&vao2, &program);
 
         w.window.swap_buffers();
-        w.glfw.poll_events();
+        w.poll_events();
     }
 }
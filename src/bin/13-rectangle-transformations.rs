@@ -61,7 +61,7 @@ fn main() {
 
     let mut layouts = VertexBufferLayout::new(VertexBufferLayoutType::F32, 3, false);
     layouts.add(VertexBufferLayoutType::F32, 2, false);
-    let vao = VertexArray::new(&vertices, &indices, &layouts);
+    let vao = VertexArray::with_layout(&vertices, &indices, &layouts);
 
     // Transformations are backwards. First we scale to half of the size and then rotate 90º around the Z axis
     let trans = &glm::Mat4::identity();
@@ -74,6 +74,6 @@ fn main() {
         renderer.draw(&vao, &program);
 
         w.window.swap_buffers();
-        w.glfw.poll_events();
+        w.poll_events();
     }
 }
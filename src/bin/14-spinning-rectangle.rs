@@ -61,19 +61,19 @@ fn main() {
 
     let mut layouts = VertexBufferLayout::new(VertexBufferLayoutType::F32, 3, false);
     layouts.add(VertexBufferLayoutType::F32, 2, false);
-    let vao = VertexArray::new(&vertices, &indices, &layouts);
+    let vao = VertexArray::with_layout(&vertices, &indices, &layouts);
 
     let trans = &glm::Mat4::identity();
     let trans = glm::translate(&trans, &glm::vec3(0.5, -0.5, 1.0));
 
     while !w.window.should_close() {
-        let trans = glm::rotate(&trans, w.get_time(), &glm::vec3(0.0, 0.0, 1.0));
+        let trans = glm::rotate(&trans, w.get_time() as f32, &glm::vec3(0.0, 0.0, 1.0));
         program.set_uniform_mat4("uTransform", &trans);
 
         renderer.clear();
         renderer.draw(&vao, &program);
 
         w.window.swap_buffers();
-        w.glfw.poll_events();
+        w.poll_events();
     }
 }
@@ -0,0 +1,93 @@
+use bytemuck::{Pod, Zeroable};
+use glfw::Context;
+use nalgebra_glm as glm;
+use opengl_sandbox::{
+    impl_vertex,
+    program::Program,
+    renderer::Renderer,
+    shader::{Shader, ShaderType},
+    texture::Texture,
+    vertex_array::VertexArray,
+    window::Window,
+};
+
+const VERTEX_SHADER_SRC: &str = "#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec2 aTexCoord;
+
+out vec2 vTexCoord;
+
+void main() {
+    gl_Position = vec4(aPos, 1.0);
+    vTexCoord = aTexCoord;
+}";
+
+const FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec2 vTexCoord;
+
+uniform sampler2D uTex1;
+
+void main() {
+    FragColor = texture(uTex1, vTexCoord);
+}";
+
+// The layout (offsets, counts, types and stride) is generated from the struct
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct TexturedVertex {
+    position: glm::Vec3,
+    tex_coords: glm::Vec2,
+}
+
+impl_vertex!(TexturedVertex {
+    position,
+    tex_coords
+});
+
+fn main() {
+    let mut w = Window::new(800, 600, "Vertex struct");
+    let renderer = Renderer::default();
+
+    let vertex_shader = Shader::new(ShaderType::VertexShader, VERTEX_SHADER_SRC);
+    let fragment_shader = Shader::new(ShaderType::FragmentShader, FRAGMENT_SHADER_SRC);
+    let mut program = Program::new(&vertex_shader, &fragment_shader);
+
+    let ferris_texture = Texture::new("uTex1", "res/textures/ferris.png", 0);
+    program.add_texture2d(ferris_texture);
+
+    let vertices = [
+        TexturedVertex {
+            position: glm::vec3(-0.5, 0.5, 0.0),
+            tex_coords: glm::vec2(0.0, 1.0),
+        },
+        TexturedVertex {
+            position: glm::vec3(0.5, 0.5, 0.0),
+            tex_coords: glm::vec2(1.0, 1.0),
+        },
+        TexturedVertex {
+            position: glm::vec3(0.5, -0.5, 0.0),
+            tex_coords: glm::vec2(1.0, 0.0),
+        },
+        TexturedVertex {
+            position: glm::vec3(-0.5, -0.5, 0.0),
+            tex_coords: glm::vec2(0.0, 0.0),
+        },
+    ];
+
+    let indices = [
+        0, 1, 3, // First triangle
+        1, 2, 3u32, // Second triangle
+    ];
+
+    let vao = VertexArray::new(&vertices, &indices);
+
+    while !w.window.should_close() {
+        renderer.clear();
+        renderer.draw(&vao, &program);
+
+        w.window.swap_buffers();
+        w.poll_events();
+    }
+}
@@ -0,0 +1,116 @@
+use glfw::Context;
+use opengl_sandbox::{
+    clear_values::ClearValues,
+    framebuffer::{ColorAttachment, DepthStencilAttachment, Framebuffer},
+    program::Program,
+    renderer::Renderer,
+    shader::{Shader, ShaderType},
+    texture::{Texture, TextureFormat},
+    vertex_array::VertexArray,
+    vertex_buffer_layout::{VertexBufferLayout, VertexBufferLayoutType},
+    window::Window,
+};
+
+const VERTEX_SHADER_SRC: &str = "#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec2 aTexCoord;
+
+out vec2 vTexCoord;
+
+void main() {
+    gl_Position = vec4(aPos, 1.0);
+    vTexCoord = aTexCoord;
+}";
+
+const FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec2 vTexCoord;
+
+uniform sampler2D uTex1;
+
+void main() {
+    FragColor = texture(uTex1, vTexCoord);
+}";
+
+// Samples the offscreen texture and turns it into grayscale
+const GRAYSCALE_FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec2 vTexCoord;
+
+uniform sampler2D uScene;
+
+void main() {
+    vec4 color = texture(uScene, vTexCoord);
+    float luminance = dot(color.rgb, vec3(0.2126, 0.7152, 0.0722));
+    FragColor = vec4(vec3(luminance), 1.0);
+}";
+
+fn main() {
+    let mut w = Window::new(800, 600, "Render to texture");
+    let renderer = Renderer::default();
+
+    let vertex_shader = Shader::new(ShaderType::VertexShader, VERTEX_SHADER_SRC);
+    let fragment_shader = Shader::new(ShaderType::FragmentShader, FRAGMENT_SHADER_SRC);
+    let mut program = Program::new(&vertex_shader, &fragment_shader);
+
+    let ferris_texture = Texture::new("uTex1", "res/textures/ferris.png", 0);
+    program.add_texture2d(ferris_texture);
+
+    let grayscale_shader = Shader::new(ShaderType::FragmentShader, GRAYSCALE_FRAGMENT_SHADER_SRC);
+    let mut grayscale_program = Program::new(&vertex_shader, &grayscale_shader);
+
+    let framebuffer = Framebuffer::new(
+        800,
+        600,
+        &[ColorAttachment::new("uScene", TextureFormat::Rgba8, 0)],
+        DepthStencilAttachment::Renderbuffer(TextureFormat::Depth24Stencil8),
+    );
+    grayscale_program.add_texture2d(framebuffer.get_color_attachment(0));
+
+    #[rustfmt::skip]
+    let vertices = [
+        // positions      // texture coords
+        -0.5,  0.5, 0.0,  0.0, 1.0,    // top left
+         0.5,  0.5, 0.0,  1.0, 1.0,    // top right
+         0.5, -0.5, 0.0,  1.0, 0.0,    // bottom right
+        -0.5, -0.5, 0.0,  0.0, 0.0f32, // bottom left
+    ];
+
+    #[rustfmt::skip]
+    let screen_vertices = [
+        // positions      // texture coords
+        -1.0,  1.0, 0.0,  0.0, 1.0,    // top left
+         1.0,  1.0, 0.0,  1.0, 1.0,    // top right
+         1.0, -1.0, 0.0,  1.0, 0.0,    // bottom right
+        -1.0, -1.0, 0.0,  0.0, 0.0f32, // bottom left
+    ];
+
+    let indices = [
+        0, 1, 3, // First triangle
+        1, 2, 3u32, // Second triangle
+    ];
+
+    let mut layouts = VertexBufferLayout::new(VertexBufferLayoutType::F32, 3, false);
+    layouts.add(VertexBufferLayoutType::F32, 2, false);
+    let vao = VertexArray::with_layout(&vertices, &indices, &layouts);
+    let screen_vao = VertexArray::with_layout(&screen_vertices, &indices, &layouts);
+
+    while !w.window.should_close() {
+        renderer.render_to(&framebuffer, || {
+            renderer.clear_with(&ClearValues {
+                color: Some([0.2, 0.3, 0.3, 1.0]),
+                depth: Some(1.0),
+                stencil: Some(0),
+            });
+            renderer.draw(&vao, &program);
+        });
+
+        renderer.clear();
+        renderer.draw(&screen_vao, &grayscale_program);
+
+        w.window.swap_buffers();
+        w.poll_events();
+    }
+}
@@ -0,0 +1,82 @@
+use glfw::Context;
+use nalgebra_glm as glm;
+use opengl_sandbox::{
+    event::Event,
+    multisample_framebuffer::MultisampleFramebuffer,
+    program::Program,
+    renderer::Renderer,
+    shader::{Shader, ShaderType},
+    texture::TextureFormat,
+    vertex_array::VertexArray,
+    vertex_buffer_layout::{VertexBufferLayout, VertexBufferLayoutType},
+    window::Window,
+};
+
+const VERTEX_SHADER_SRC: &str = "#version 330 core
+layout (location = 0) in vec3 aPos;
+
+uniform mat4 uTransform;
+
+void main() {
+    gl_Position = uTransform * vec4(aPos, 1.0);
+}";
+
+const FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+void main() {
+    FragColor = vec4(1.0, 0.5, 0.2, 1.0);
+}";
+
+fn main() {
+    // The triangle is rendered into a 4x multisampled offscreen target and resolved into the
+    // window. Window::new_multisampled() anti-aliases the window itself instead
+    let mut w = Window::new(800, 600, "Multisampling");
+    let renderer = Renderer::default();
+
+    let vertex_shader = Shader::new(ShaderType::VertexShader, VERTEX_SHADER_SRC);
+    let fragment_shader = Shader::new(ShaderType::FragmentShader, FRAGMENT_SHADER_SRC);
+    let program = Program::new(&vertex_shader, &fragment_shader);
+
+    let vertices = [
+        -0.5f32, -0.5, 0.0, //
+        0.5, -0.5, 0.0, //
+        0.0, 0.5, 0.0, //
+    ];
+    let indices = [0u32, 1, 2];
+
+    let layouts = VertexBufferLayout::new(VertexBufferLayoutType::F32, 3, false);
+    let vao = VertexArray::with_layout(&vertices, &indices, &layouts);
+
+    // The resolve copies the whole target, it must have the size of the window
+    let (width, height) = w.get_framebuffer_size();
+    let mut framebuffer = MultisampleFramebuffer::new(
+        width,
+        height,
+        4,
+        &[TextureFormat::Rgba8],
+        Some(TextureFormat::Depth24Stencil8),
+    );
+
+    while !w.window.should_close() {
+        let trans = glm::rotate(
+            &glm::Mat4::identity(),
+            w.get_time() as f32 * 0.1,
+            &glm::vec3(0.0, 0.0, 1.0),
+        );
+        program.set_uniform_mat4("uTransform", &trans);
+
+        renderer.render_to(&framebuffer, || {
+            renderer.clear();
+            renderer.draw(&vao, &program);
+        });
+        framebuffer.resolve_to_window();
+
+        w.window.swap_buffers();
+        for event in w.poll_events() {
+            if let Event::Resize { width, height } = event {
+                framebuffer.resize(width, height);
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use glfw::Context;
+use nalgebra_glm as glm;
+use opengl_sandbox::{
+    clear_values::ClearValues,
+    framebuffer::DefaultFramebuffer,
+    post_processing::{Bloom, PostProcessing, ShaderEffect},
+    program::Program,
+    renderer::Renderer,
+    shader::{Shader, ShaderType},
+    texture::Texture,
+    vertex_array::VertexArray,
+    vertex_buffer_layout::{VertexBufferLayout, VertexBufferLayoutType},
+    window::Window,
+};
+
+const VERTEX_SHADER_SRC: &str = "#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec2 aTexCoord;
+
+out vec2 vTexCoord;
+
+uniform mat4 uTransform;
+
+void main() {
+    gl_Position = uTransform * vec4(aPos, 1.0);
+    vTexCoord = aTexCoord;
+}";
+
+// Values above 1.0 are kept by the HDR scene target and feed the bloom
+const FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec2 vTexCoord;
+
+uniform sampler2D uTex1;
+
+void main() {
+    FragColor = texture(uTex1, vTexCoord) * 2.0;
+}";
+
+// User-defined effect: shifts the red and blue channels apart
+const CHROMATIC_ABERRATION_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec2 vTexCoord;
+
+uniform sampler2D uInput;
+
+void main() {
+    vec2 offset = (vTexCoord - vec2(0.5)) * 0.01;
+    float r = texture(uInput, vTexCoord + offset).r;
+    vec4 color = texture(uInput, vTexCoord);
+    float b = texture(uInput, vTexCoord - offset).b;
+    FragColor = vec4(r, color.g, b, color.a);
+}";
+
+fn main() {
+    let mut w = Window::new(800, 600, "Post-processing");
+    let renderer = Renderer::default();
+
+    let vertex_shader = Shader::new(ShaderType::VertexShader, VERTEX_SHADER_SRC);
+    let fragment_shader = Shader::new(ShaderType::FragmentShader, FRAGMENT_SHADER_SRC);
+    let mut program = Program::new(&vertex_shader, &fragment_shader);
+
+    let ferris_texture = Texture::new("uTex1", "res/textures/ferris.png", 0);
+    program.add_texture2d(ferris_texture);
+
+    #[rustfmt::skip]
+    let vertices = [
+        // positions      // texture coords
+        -0.5,  0.5, 0.0,  0.0, 1.0,    // top left
+         0.5,  0.5, 0.0,  1.0, 1.0,    // top right
+         0.5, -0.5, 0.0,  1.0, 0.0,    // bottom right
+        -0.5, -0.5, 0.0,  0.0, 0.0f32, // bottom left
+    ];
+
+    let indices = [
+        0, 1, 3, // First triangle
+        1, 2, 3u32, // Second triangle
+    ];
+
+    let mut layouts = VertexBufferLayout::new(VertexBufferLayoutType::F32, 3, false);
+    layouts.add(VertexBufferLayoutType::F32, 2, false);
+    let vao = VertexArray::with_layout(&vertices, &indices, &layouts);
+
+    // In pixels, bigger than the window size on HiDPI displays
+    let (mut width, mut height) = w.get_framebuffer_size();
+
+    let mut post_processing = PostProcessing::new(width, height);
+    post_processing.add_effect(Bloom::new(width, height, 1.0, 0.8));
+    post_processing.add_effect(ShaderEffect::tonemapping(1.0, 1.0));
+    post_processing.add_effect(ShaderEffect::fxaa());
+    post_processing.add_effect(ShaderEffect::new(CHROMATIC_ABERRATION_SRC));
+    post_processing.add_effect(ShaderEffect::vignette(0.75, 0.45));
+
+    let mut window_target = DefaultFramebuffer::new(width, height);
+
+    while !w.window.should_close() {
+        if w.get_framebuffer_size() != (width, height) {
+            (width, height) = w.get_framebuffer_size();
+            post_processing.resize(width, height);
+            window_target = DefaultFramebuffer::new(width, height);
+        }
+
+        let trans = glm::rotate(
+            &glm::Mat4::identity(),
+            w.get_time() as f32,
+            &glm::vec3(0.0, 0.0, 1.0),
+        );
+        program.set_uniform_mat4("uTransform", &trans);
+
+        renderer.render_to(post_processing.get_scene_target(), || {
+            renderer.clear_with(&ClearValues::color(0.2, 0.3, 0.3, 1.0));
+            renderer.draw(&vao, &program);
+        });
+        post_processing.render(&renderer, &window_target);
+
+        w.window.swap_buffers();
+        w.poll_events();
+    }
+}
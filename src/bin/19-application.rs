@@ -0,0 +1,131 @@
+use nalgebra_glm as glm;
+use opengl_sandbox::{
+    application::Application,
+    event::{Event, Key, KeyAction},
+    program::Program,
+    renderer::Renderer,
+    shader::{Shader, ShaderType},
+    vertex_array::VertexArray,
+    vertex_buffer_layout::{VertexBufferLayout, VertexBufferLayoutType},
+    window::Window,
+};
+
+const VERTEX_SHADER_SRC: &str = "#version 330 core
+layout (location = 0) in vec3 aPos;
+
+uniform mat4 uTransform;
+
+void main() {
+    gl_Position = uTransform * vec4(aPos, 1.0);
+}";
+
+const FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+uniform vec4 uColor;
+
+void main() {
+    FragColor = uColor;
+}";
+
+// Arrow keys: rotation speed, Space: pause, Escape: quit
+struct SpinningRectangle {
+    renderer: Renderer,
+    program: Program,
+    vao: VertexArray,
+    angle: f32,
+    speed: f32,
+    paused: bool,
+}
+
+impl Application for SpinningRectangle {
+    fn update(&mut self, window: &mut Window, dt: f32) {
+        if window.is_key_down(Key::Right) {
+            self.speed += 2.0 * dt;
+        }
+        if window.is_key_down(Key::Left) {
+            self.speed -= 2.0 * dt;
+        }
+        if !self.paused {
+            self.angle += self.speed * dt;
+        }
+
+        let clock = window.get_clock();
+        if clock.get_frame_count().is_multiple_of(60) {
+            let title = format!(
+                "Application - {:.0} FPS, 99th percentile {:.2} ms",
+                clock.get_fps(),
+                clock.get_frame_time_percentile(99.0) * 1000.0
+            );
+            window.window.set_title(&title);
+        }
+    }
+
+    fn render(&mut self, _window: &mut Window) {
+        let transform = glm::rotate(
+            &glm::Mat4::identity(),
+            self.angle,
+            &glm::vec3(0.0, 0.0, 1.0),
+        );
+        self.program.set_uniform_mat4("uTransform", &transform);
+        let color = match self.paused {
+            true => (0.5, 0.5, 0.5, 1.0),
+            false => (1.0, 0.5, 0.2, 1.0),
+        };
+        self.program
+            .set_uniform_4f("uColor", color.0, color.1, color.2, color.3);
+
+        self.renderer.clear();
+        self.renderer.draw(&self.vao, &self.program);
+    }
+
+    fn on_event(&mut self, window: &mut Window, event: &Event) {
+        match event {
+            Event::Key {
+                key: Key::Escape,
+                action: KeyAction::Press,
+                ..
+            } => window.close(),
+            Event::Key {
+                key: Key::Space,
+                action: KeyAction::Press,
+                ..
+            } => self.paused = !self.paused,
+            Event::FileDrop(paths) => println!("Dropped: {paths:?}"),
+            _ => {}
+        }
+    }
+
+    fn on_resize(&mut self, _window: &mut Window, width: u32, height: u32) {
+        println!("Resized to {width}x{height}");
+    }
+}
+
+fn main() {
+    let mut w = Window::new(800, 600, "Application");
+
+    let vertex_shader = Shader::new(ShaderType::VertexShader, VERTEX_SHADER_SRC);
+    let fragment_shader = Shader::new(ShaderType::FragmentShader, FRAGMENT_SHADER_SRC);
+    let program = Program::new(&vertex_shader, &fragment_shader);
+
+    #[rustfmt::skip]
+    let vertices = [
+        -0.5,  0.5, 0.0, // top left
+         0.5,  0.5, 0.0, // top right
+         0.5, -0.5, 0.0, // bottom right
+        -0.5, -0.5, 0.0f32, // bottom left
+    ];
+    let indices = [0, 1, 3, 1, 2, 3u32];
+    let layout = VertexBufferLayout::new(VertexBufferLayoutType::F32, 3, false);
+    let vao = VertexArray::with_layout(&vertices, &indices, &layout);
+
+    let mut app = SpinningRectangle {
+        renderer: Renderer::default(),
+        program,
+        vao,
+        angle: 0.0,
+        speed: 1.0,
+        paused: false,
+    };
+    w.run(&mut app);
+}
@@ -0,0 +1,155 @@
+use bytemuck::{Pod, Zeroable};
+use nalgebra_glm as glm;
+use opengl_sandbox::{
+    application::Application,
+    camera::{Camera, CameraController, FlyController, OrbitController, Projection},
+    clear_values::ClearValues,
+    event::{Event, Key, KeyAction},
+    impl_vertex,
+    program::Program,
+    render_state::RenderState,
+    renderer::Renderer,
+    shader::{Shader, ShaderType},
+    vertex_array::VertexArray,
+    window::Window,
+};
+
+const VERTEX_SHADER_SRC: &str = "#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aColor;
+
+out vec3 vColor;
+
+uniform mat4 uViewProjection;
+uniform mat4 uModel;
+
+void main() {
+    gl_Position = uViewProjection * uModel * vec4(aPos, 1.0);
+    vColor = aColor;
+}";
+
+const FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec3 vColor;
+
+void main() {
+    FragColor = vec4(vColor, 1.0);
+}";
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct ColoredVertex {
+    position: glm::Vec3,
+    color: glm::Vec3,
+}
+
+impl_vertex!(ColoredVertex { position, color });
+
+// Tab switches between the fly and the orbit controllers, Escape quits
+struct CameraSample {
+    renderer: Renderer,
+    program: Program,
+    cube: VertexArray,
+    camera: Camera,
+    fly: FlyController,
+    orbit: OrbitController,
+    flying: bool,
+}
+
+impl Application for CameraSample {
+    fn update(&mut self, window: &mut Window, dt: f32) {
+        let controller: &mut dyn CameraController = match self.flying {
+            true => &mut self.fly,
+            false => &mut self.orbit,
+        };
+        controller.update(&mut self.camera, window.get_input(), dt);
+    }
+
+    fn render(&mut self, _window: &mut Window) {
+        self.renderer.clear_with(&ClearValues::default());
+
+        self.program
+            .set_uniform_mat4("uViewProjection", &self.camera.view_projection());
+        for x in -2..=2 {
+            for z in -2..=2 {
+                let position = glm::vec3(x as f32 * 2.0, 0.0, z as f32 * 2.0);
+                let model = glm::translate(&glm::Mat4::identity(), &position);
+                self.program.set_uniform_mat4("uModel", &model);
+                self.renderer.draw(&self.cube, &self.program);
+            }
+        }
+    }
+
+    fn on_event(&mut self, window: &mut Window, event: &Event) {
+        if let Event::Key {
+            key,
+            action: KeyAction::Press,
+            ..
+        } = event
+        {
+            match key {
+                Key::Escape => window.close(),
+                Key::Tab => self.flying = !self.flying,
+                _ => {}
+            }
+        }
+    }
+
+    fn on_resize(&mut self, _window: &mut Window, width: u32, height: u32) {
+        self.camera.resize(width, height);
+    }
+}
+
+fn main() {
+    let mut w = Window::new(800, 600, "Camera");
+    let renderer = Renderer::default();
+    renderer.set_render_state(&RenderState::opaque_3d());
+
+    let vertex_shader = Shader::new(ShaderType::VertexShader, VERTEX_SHADER_SRC);
+    let fragment_shader = Shader::new(ShaderType::FragmentShader, FRAGMENT_SHADER_SRC);
+    let program = Program::new(&vertex_shader, &fragment_shader);
+
+    // Corner i is at (bit 0, bit 1, bit 2) of i, coloured by its position
+    let vertices: Vec<ColoredVertex> = (0..8)
+        .map(|i| {
+            let corner = glm::vec3((i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32);
+            ColoredVertex {
+                position: corner - glm::vec3(0.5, 0.5, 0.5),
+                color: corner,
+            }
+        })
+        .collect();
+
+    #[rustfmt::skip]
+    let indices = [
+        0, 6, 2, 0, 4, 6, // -X
+        1, 3, 7, 1, 7, 5, // +X
+        0, 1, 5, 0, 5, 4, // -Y
+        2, 7, 3, 2, 6, 7, // +Y
+        0, 3, 1, 0, 2, 3, // -Z
+        4, 5, 7, 4, 7, 6u32, // +Z
+    ];
+    let cube = VertexArray::new(&vertices, &indices);
+
+    let mut camera = Camera::new(
+        glm::vec3(0.0, 3.0, 8.0),
+        Projection::perspective(),
+        w.get_aspect_ratio(),
+    );
+    camera.look_at(&glm::Vec3::zeros());
+
+    let mut orbit = OrbitController::new(glm::Vec3::zeros(), 8.0);
+    orbit.pitch = 0.4;
+
+    let mut app = CameraSample {
+        renderer,
+        program,
+        cube,
+        camera,
+        fly: FlyController::default(),
+        orbit,
+        flying: false,
+    };
+    w.run(&mut app);
+}
@@ -0,0 +1,162 @@
+use std::rc::Rc;
+
+use nalgebra_glm as glm;
+use opengl_sandbox::{
+    application::Application,
+    camera::{Camera, CameraController, OrbitController, Projection},
+    clear_values::ClearValues,
+    event::{Event, Key, KeyAction},
+    material::Material,
+    primitives,
+    program::Program,
+    renderer::Renderer,
+    scene::{NodeId, Scene, SceneNode},
+    shader::{Shader, ShaderType},
+    transform::Transform,
+    window::Window,
+};
+
+const VERTEX_SHADER_SRC: &str = "#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+
+out vec3 vNormal;
+
+uniform mat4 uViewProjection;
+uniform mat4 uModel;
+
+void main() {
+    gl_Position = uViewProjection * uModel * vec4(aPos, 1.0);
+    vNormal = mat3(uModel) * aNormal;
+}";
+
+const FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec3 vNormal;
+
+uniform vec3 uColor;
+
+void main() {
+    float light = max(dot(normalize(vNormal), normalize(vec3(0.3, 1.0, 0.5))), 0.0);
+    FragColor = vec4(uColor * (0.3 + 0.7 * light), 1.0);
+}";
+
+// The planet orbits the sun and the moon orbits the planet: rotating a parent moves its
+// children
+struct SolarSystem {
+    renderer: Renderer,
+    scene: Scene,
+    sun: NodeId,
+    planet_orbit: NodeId,
+    moon_orbit: NodeId,
+    camera: Camera,
+    orbit: OrbitController,
+}
+
+impl Application for SolarSystem {
+    fn update(&mut self, window: &mut Window, dt: f32) {
+        self.orbit.update(&mut self.camera, window.get_input(), dt);
+
+        let y = glm::Vec3::y();
+        self.scene.get_transform_mut(self.sun).rotate(0.2 * dt, &y);
+        self.scene
+            .get_transform_mut(self.planet_orbit)
+            .rotate(0.8 * dt, &y);
+        self.scene
+            .get_transform_mut(self.moon_orbit)
+            .rotate(2.5 * dt, &y);
+    }
+
+    fn render(&mut self, _window: &mut Window) {
+        self.renderer.clear_with(&ClearValues::default());
+        self.renderer.draw_scene(&self.scene, &self.camera);
+    }
+
+    fn on_event(&mut self, window: &mut Window, event: &Event) {
+        if let Event::Key {
+            key: Key::Escape,
+            action: KeyAction::Press,
+            ..
+        } = event
+        {
+            window.close();
+        }
+    }
+
+    fn on_resize(&mut self, _window: &mut Window, width: u32, height: u32) {
+        self.camera.resize(width, height);
+    }
+}
+
+fn main() {
+    let mut w = Window::new(800, 600, "Scene graph");
+    let renderer = Renderer::default();
+
+    let vertex_shader = Shader::new(ShaderType::VertexShader, VERTEX_SHADER_SRC);
+    let fragment_shader = Shader::new(ShaderType::FragmentShader, FRAGMENT_SHADER_SRC);
+    // The program is shared, each material stores its own colour
+    let program = Rc::new(Program::new(&vertex_shader, &fragment_shader));
+    let colored_material = |r: f32, g: f32, b: f32| {
+        let mut material = Material::new(program.clone());
+        material.set_vec3("uColor", glm::vec3(r, g, b));
+        vec![Rc::new(material)]
+    };
+    // The mesh is shared by the three nodes
+    let cube = Rc::new(primitives::cube(1.0));
+
+    let scale =
+        |s: f32| Transform::new(glm::Vec3::zeros(), glm::quat_identity(), glm::vec3(s, s, s));
+
+    let mut scene = Scene::new();
+    let sun = scene.add_node(SceneNode::new_mesh(
+        "sun",
+        scale(2.0),
+        cube.clone(),
+        colored_material(1.0, 0.8, 0.2),
+    ));
+    // Orbit nodes have no mesh, they only rotate their children around the parent
+    let planet_orbit = scene.add_node(SceneNode::new("planet orbit", Transform::default()));
+    let planet = scene.add_child(
+        planet_orbit,
+        SceneNode::new_mesh(
+            "planet",
+            Transform::from_translation(glm::vec3(5.0, 0.0, 0.0)),
+            cube.clone(),
+            colored_material(0.2, 0.5, 1.0),
+        ),
+    );
+    let moon_orbit = scene.add_child(planet, SceneNode::new("moon orbit", Transform::default()));
+    scene.add_child(
+        moon_orbit,
+        SceneNode::new_mesh(
+            "moon",
+            Transform::new(
+                glm::vec3(1.5, 0.0, 0.0),
+                glm::quat_identity(),
+                glm::vec3(0.4, 0.4, 0.4),
+            ),
+            cube,
+            colored_material(0.7, 0.7, 0.7),
+        ),
+    );
+
+    let camera = Camera::new(
+        glm::Vec3::zeros(),
+        Projection::perspective(),
+        w.get_aspect_ratio(),
+    );
+    let mut orbit = OrbitController::new(glm::Vec3::zeros(), 14.0);
+    orbit.pitch = 0.5;
+
+    let mut app = SolarSystem {
+        renderer,
+        scene,
+        sun,
+        planet_orbit,
+        moon_orbit,
+        camera,
+        orbit,
+    };
+    w.run(&mut app);
+}
@@ -0,0 +1,141 @@
+use nalgebra_glm as glm;
+use opengl_sandbox::{
+    application::Application,
+    camera::{Camera, CameraController, OrbitController, Projection},
+    clear_values::ClearValues,
+    event::{Event, Key, KeyAction},
+    mesh::Mesh,
+    primitives,
+    program::Program,
+    render_state::{PolygonMode, RenderState},
+    renderer::Renderer,
+    shader::{Shader, ShaderType},
+    window::Window,
+};
+
+const VERTEX_SHADER_SRC: &str = "#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = 2) in vec2 aTexCoord;
+
+out vec3 vNormal;
+out vec2 vTexCoord;
+
+uniform mat4 uViewProjection;
+uniform mat4 uModel;
+
+void main() {
+    gl_Position = uViewProjection * uModel * vec4(aPos, 1.0);
+    vNormal = mat3(uModel) * aNormal;
+    vTexCoord = aTexCoord;
+}";
+
+// Checkerboard from the texture coordinates, shaded with a fixed light
+const FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec3 vNormal;
+in vec2 vTexCoord;
+
+void main() {
+    vec2 cell = floor(vTexCoord * 8.0);
+    float checker = mod(cell.x + cell.y, 2.0);
+    vec3 color = mix(vec3(0.9, 0.5, 0.2), vec3(0.95), checker);
+    float light = max(dot(normalize(vNormal), normalize(vec3(0.3, 1.0, 0.5))), 0.0);
+    FragColor = vec4(color * (0.25 + 0.75 * light), 1.0);
+}";
+
+// W toggles wireframe, Escape quits
+struct Primitives {
+    renderer: Renderer,
+    program: Program,
+    meshes: Vec<Mesh>,
+    camera: Camera,
+    orbit: OrbitController,
+    wireframe: bool,
+}
+
+impl Application for Primitives {
+    fn update(&mut self, window: &mut Window, dt: f32) {
+        self.orbit.update(&mut self.camera, window.get_input(), dt);
+    }
+
+    fn render(&mut self, _window: &mut Window) {
+        self.renderer.set_render_state(&RenderState {
+            polygon_mode: match self.wireframe {
+                true => PolygonMode::Line,
+                false => PolygonMode::Fill,
+            },
+            ..RenderState::opaque_3d()
+        });
+        self.renderer
+            .clear_with(&ClearValues::color(0.1, 0.1, 0.12, 1.0));
+
+        self.program
+            .set_uniform_mat4("uViewProjection", &self.camera.view_projection());
+        // Three rows of three shapes
+        for (i, mesh) in self.meshes.iter().enumerate() {
+            let position = glm::vec3((i % 3) as f32 * 3.0 - 3.0, 0.0, (i / 3) as f32 * 3.0 - 3.0);
+            let model = glm::translate(&glm::Mat4::identity(), &position);
+            self.program.set_uniform_mat4("uModel", &model);
+            self.renderer.draw_mesh(mesh, &self.program);
+        }
+    }
+
+    fn on_event(&mut self, window: &mut Window, event: &Event) {
+        if let Event::Key {
+            key,
+            action: KeyAction::Press,
+            ..
+        } = event
+        {
+            match key {
+                Key::Escape => window.close(),
+                Key::W => self.wireframe = !self.wireframe,
+                _ => {}
+            }
+        }
+    }
+
+    fn on_resize(&mut self, _window: &mut Window, width: u32, height: u32) {
+        self.camera.resize(width, height);
+    }
+}
+
+fn main() {
+    let mut w = Window::new(800, 600, "Primitives");
+
+    let vertex_shader = Shader::new(ShaderType::VertexShader, VERTEX_SHADER_SRC);
+    let fragment_shader = Shader::new(ShaderType::FragmentShader, FRAGMENT_SHADER_SRC);
+    let program = Program::new(&vertex_shader, &fragment_shader);
+
+    let meshes = vec![
+        primitives::quad(1.5, 1.5),
+        primitives::cube(1.5),
+        primitives::plane(2.0, 2.0, 4, 4),
+        primitives::uv_sphere(0.8, 32, 16),
+        primitives::icosphere(0.8, 3),
+        primitives::cylinder(0.6, 1.5, 32),
+        primitives::cone(0.7, 1.5, 32),
+        primitives::torus(0.6, 0.25, 48, 24),
+        primitives::capsule(0.4, 0.8, 32, 8),
+    ];
+
+    let camera = Camera::new(
+        glm::Vec3::zeros(),
+        Projection::perspective(),
+        w.get_aspect_ratio(),
+    );
+    let mut orbit = OrbitController::new(glm::Vec3::zeros(), 10.0);
+    orbit.pitch = 0.6;
+
+    let mut app = Primitives {
+        renderer: Renderer::default(),
+        program,
+        meshes,
+        camera,
+        orbit,
+        wireframe: false,
+    };
+    w.run(&mut app);
+}
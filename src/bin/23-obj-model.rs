@@ -0,0 +1,114 @@
+use std::rc::Rc;
+
+use nalgebra_glm as glm;
+use opengl_sandbox::{
+    application::Application,
+    camera::{Camera, CameraController, OrbitController, Projection},
+    clear_values::ClearValues,
+    event::{Event, Key, KeyAction},
+    light::{DirectionalLight, Lights},
+    material::Material,
+    obj_loader::ObjModel,
+    phong::{self, PhongMaterial},
+    program::Program,
+    renderer::Renderer,
+    window::Window,
+};
+
+struct ObjViewer {
+    renderer: Renderer,
+    program: Rc<Program>,
+    model: ObjModel,
+    // By material slot
+    materials: Vec<Rc<Material>>,
+    lights: Lights,
+    camera: Camera,
+    orbit: OrbitController,
+}
+
+impl Application for ObjViewer {
+    fn update(&mut self, window: &mut Window, dt: f32) {
+        self.orbit.update(&mut self.camera, window.get_input(), dt);
+    }
+
+    fn render(&mut self, _window: &mut Window) {
+        self.renderer
+            .clear_with(&ClearValues::color(0.5, 0.7, 0.9, 1.0));
+
+        self.lights.update(&self.camera.position);
+        let program = &self.program;
+        program.set_uniform_mat4("uViewProjection", &self.camera.view_projection());
+        program.set_uniform_mat4("uModel", &glm::Mat4::identity());
+
+        for group in &self.model.groups {
+            self.renderer
+                .draw_mesh_with_materials(&group.mesh, &self.materials);
+        }
+    }
+
+    fn on_event(&mut self, window: &mut Window, event: &Event) {
+        if let Event::Key {
+            key: Key::Escape,
+            action: KeyAction::Press,
+            ..
+        } = event
+        {
+            window.close();
+        }
+    }
+
+    fn on_resize(&mut self, _window: &mut Window, width: u32, height: u32) {
+        self.camera.resize(width, height);
+    }
+}
+
+// Usage: 23-obj-model [path/to/model.obj]
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or(String::from("res/models/house.obj"));
+
+    let mut w = Window::new(800, 600, "OBJ model");
+    let renderer = Renderer::default();
+    let program = Rc::new(phong::new_program());
+
+    let model = ObjModel::load(&path).unwrap_or_else(|error| panic!("{error}"));
+    let materials = model
+        .materials
+        .iter()
+        .map(|material| Rc::new(PhongMaterial::from_obj(material).to_material(program.clone())))
+        .collect();
+
+    let mut lights = Lights::new();
+    lights
+        .directional_lights
+        .push(DirectionalLight::new(glm::vec3(-0.3, -1.0, -0.5)));
+
+    // Frame the whole model
+    let bounds = model
+        .groups
+        .iter()
+        .map(|group| group.mesh.get_aabb())
+        .reduce(|a, b| a.union(&b))
+        .expect("The model is empty");
+    let mut orbit = OrbitController::new(bounds.get_center(), bounds.get_extents().norm() * 3.0);
+    orbit.pitch = 0.4;
+    orbit.yaw = 0.6;
+
+    let camera = Camera::new(
+        glm::Vec3::zeros(),
+        Projection::perspective(),
+        w.get_aspect_ratio(),
+    );
+
+    let mut app = ObjViewer {
+        renderer,
+        program,
+        model,
+        materials,
+        lights,
+        camera,
+        orbit,
+    };
+    w.run(&mut app);
+}
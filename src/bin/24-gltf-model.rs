@@ -0,0 +1,165 @@
+use nalgebra_glm as glm;
+use opengl_sandbox::{
+    application::Application,
+    camera::{Camera, CameraController, OrbitController, Projection},
+    clear_values::ClearValues,
+    event::{Event, Key, KeyAction},
+    gltf_loader::{GltfModel, BASE_COLOR_MAP_SLOT, BASE_COLOR_MAP_UNIFORM},
+    program::Program,
+    render_state::RenderState,
+    renderer::Renderer,
+    shader::{Shader, ShaderType},
+    window::Window,
+};
+
+const VERTEX_SHADER_SRC: &str = "#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = 2) in vec2 aTexCoord;
+
+out vec3 vNormal;
+out vec2 vTexCoord;
+
+uniform mat4 uViewProjection;
+uniform mat4 uModel;
+
+void main() {
+    gl_Position = uViewProjection * uModel * vec4(aPos, 1.0);
+    vNormal = mat3(uModel) * aNormal;
+    vTexCoord = aTexCoord;
+}";
+
+// Base colour only, lit by a fixed light. Lighting comes with the material and lighting modules
+const FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec3 vNormal;
+in vec2 vTexCoord;
+
+uniform vec4 uBaseColor;
+uniform bool uHasBaseColorMap;
+uniform sampler2D uBaseColorMap;
+
+void main() {
+    vec4 color = uBaseColor;
+    if (uHasBaseColorMap) {
+        color *= texture(uBaseColorMap, vTexCoord);
+    }
+    float light = max(dot(normalize(vNormal), normalize(vec3(0.3, 1.0, 0.5))), 0.0);
+    FragColor = vec4(color.rgb * (0.25 + 0.75 * light), color.a);
+}";
+
+// Space pauses the animations, Escape quits
+struct GltfViewer {
+    renderer: Renderer,
+    program: Program,
+    model: GltfModel,
+    camera: Camera,
+    orbit: OrbitController,
+    time: f32,
+    paused: bool,
+}
+
+impl Application for GltfViewer {
+    fn update(&mut self, window: &mut Window, dt: f32) {
+        self.orbit.update(&mut self.camera, window.get_input(), dt);
+
+        if !self.paused {
+            self.time += dt;
+        }
+        for animation in &self.model.animations {
+            let duration = animation.get_duration().max(f32::EPSILON);
+            animation.apply(&mut self.model.scene, self.time % duration);
+        }
+    }
+
+    fn render(&mut self, _window: &mut Window) {
+        self.renderer
+            .clear_with(&ClearValues::color(0.15, 0.15, 0.18, 1.0));
+
+        let program = &self.program;
+        program.set_uniform_mat4("uViewProjection", &self.camera.view_projection());
+        program.set_uniform_1i(BASE_COLOR_MAP_UNIFORM, BASE_COLOR_MAP_SLOT as i32);
+
+        let scene = &self.model.scene;
+        scene.update_transforms();
+        scene.traverse(|_, node| {
+            let Some(mesh) = &node.mesh else {
+                return;
+            };
+            program.set_uniform_mat4("uModel", &node.get_world_matrix());
+
+            for submesh in mesh.get_submeshes() {
+                let material = &self.model.materials[submesh.material_slot];
+                let color = material.base_color_factor;
+                program.set_uniform_4f("uBaseColor", color.x, color.y, color.z, color.w);
+                program
+                    .set_uniform_1i("uHasBaseColorMap", material.base_color_map.is_some() as i32);
+                if let Some(texture) = &material.base_color_map {
+                    texture.bind();
+                }
+
+                self.renderer.draw_submesh(mesh, submesh, program);
+
+                if let Some(texture) = &material.base_color_map {
+                    texture.unbind();
+                }
+            }
+        });
+    }
+
+    fn on_event(&mut self, window: &mut Window, event: &Event) {
+        if let Event::Key {
+            key,
+            action: KeyAction::Press,
+            ..
+        } = event
+        {
+            match key {
+                Key::Escape => window.close(),
+                Key::Space => self.paused = !self.paused,
+                _ => {}
+            }
+        }
+    }
+
+    fn on_resize(&mut self, _window: &mut Window, width: u32, height: u32) {
+        self.camera.resize(width, height);
+    }
+}
+
+// Usage: 24-gltf-model [path/to/model.gltf or .glb]
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or(String::from("res/models/spinning_cubes.gltf"));
+
+    let mut w = Window::new(800, 600, "glTF model");
+    let renderer = Renderer::default();
+    renderer.set_render_state(&RenderState::opaque_3d());
+
+    let vertex_shader = Shader::new(ShaderType::VertexShader, VERTEX_SHADER_SRC);
+    let fragment_shader = Shader::new(ShaderType::FragmentShader, FRAGMENT_SHADER_SRC);
+    let program = Program::new(&vertex_shader, &fragment_shader);
+
+    let model = GltfModel::load(&path).unwrap_or_else(|error| panic!("{path}: {error}"));
+
+    let camera = Camera::new(
+        glm::Vec3::zeros(),
+        Projection::perspective(),
+        w.get_aspect_ratio(),
+    );
+    let mut orbit = OrbitController::new(glm::Vec3::zeros(), 7.0);
+    orbit.pitch = 0.5;
+
+    let mut app = GltfViewer {
+        renderer,
+        program,
+        model,
+        camera,
+        orbit,
+        time: 0.0,
+        paused: false,
+    };
+    w.run(&mut app);
+}
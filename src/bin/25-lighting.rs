@@ -0,0 +1,283 @@
+use std::rc::Rc;
+
+use nalgebra_glm as glm;
+use opengl_sandbox::{
+    application::Application,
+    camera::{Camera, CameraController, OrbitController, Projection},
+    clear_values::ClearValues,
+    event::{Event, Key, KeyAction},
+    light::{DirectionalLight, Lights, PointLight, SpotLight},
+    material::Material,
+    phong::{
+        self, PhongMaterial, DIFFUSE_MAP_SLOT, DIFFUSE_MAP_UNIFORM, NORMAL_MAP_SLOT,
+        NORMAL_MAP_UNIFORM,
+    },
+    primitives,
+    renderer::Renderer,
+    scene::{NodeId, Scene, SceneNode},
+    texture::{Texture, TextureFormat, TextureSampler},
+    transform::Transform,
+    window::Window,
+};
+
+// Tangent-space normals of a grid of hemispherical bumps
+fn bumps_normal_map(size: u32, cells: u32) -> image::RgbaImage {
+    let cell_size = size as f32 / cells as f32;
+    let radius = cell_size * 0.4;
+    image::RgbaImage::from_fn(size, size, |x, y| {
+        let dx = (x as f32 + 0.5) % cell_size - cell_size * 0.5;
+        let dy = (y as f32 + 0.5) % cell_size - cell_size * 0.5;
+        let height_squared = radius * radius - dx * dx - dy * dy;
+        let normal = match height_squared > 0.0 {
+            true => glm::vec3(dx, dy, height_squared.sqrt()).normalize(),
+            false => glm::Vec3::z(),
+        };
+        let encode = |v: f32| ((v * 0.5 + 0.5) * 255.0).round() as u8;
+        image::Rgba([encode(normal.x), encode(normal.y), encode(normal.z), 255])
+    })
+}
+
+// Three coloured point lights orbit the objects, the camera carries a spot light. F toggles the
+// spot light, N the normal map of the floor, B switches between Blinn-Phong and Phong
+struct Lighting {
+    renderer: Renderer,
+    scene: Scene,
+    lights: Lights,
+    camera: Camera,
+    orbit: OrbitController,
+    // Light markers, moved with the point lights
+    markers: Vec<NodeId>,
+    // Nodes with their Blinn-Phong and Phong materials
+    nodes: Vec<(NodeId, Rc<Material>, Rc<Material>)>,
+    floor: NodeId,
+    floor_materials: [Rc<Material>; 2],
+    time: f32,
+    flashlight: bool,
+    normal_map: bool,
+    blinn: bool,
+}
+
+impl Lighting {
+    fn update_materials(&mut self) {
+        for (node, blinn, phong) in &self.nodes {
+            let material = match self.blinn {
+                true => blinn.clone(),
+                false => phong.clone(),
+            };
+            self.scene.get_node_mut(*node).materials = vec![material];
+        }
+        let floor_material = self.floor_materials[self.normal_map as usize].clone();
+        self.scene.get_node_mut(self.floor).materials = vec![floor_material];
+    }
+}
+
+impl Application for Lighting {
+    fn update(&mut self, window: &mut Window, dt: f32) {
+        self.orbit.update(&mut self.camera, window.get_input(), dt);
+        self.time += dt;
+
+        for (i, (light, marker)) in self
+            .lights
+            .point_lights
+            .iter_mut()
+            .zip(&self.markers)
+            .enumerate()
+        {
+            let angle = self.time * 0.7 + i as f32 * std::f32::consts::TAU / 3.0;
+            light.position = glm::vec3(angle.cos() * 4.0, 1.5, angle.sin() * 4.0);
+            self.scene
+                .get_transform_mut(*marker)
+                .set_translation(light.position);
+        }
+
+        self.lights.spot_lights.clear();
+        if self.flashlight {
+            self.lights.spot_lights.push(SpotLight::new(
+                self.camera.position,
+                self.camera.get_forward(),
+                30.0,
+                0.2,
+                0.3,
+            ));
+        }
+    }
+
+    fn render(&mut self, _window: &mut Window) {
+        self.renderer
+            .clear_with(&ClearValues::color(0.05, 0.05, 0.08, 1.0));
+        self.lights.update(&self.camera.position);
+        self.renderer.draw_scene(&self.scene, &self.camera);
+    }
+
+    fn on_event(&mut self, window: &mut Window, event: &Event) {
+        if let Event::Key {
+            key,
+            action: KeyAction::Press,
+            ..
+        } = event
+        {
+            match key {
+                Key::Escape => window.close(),
+                Key::F => self.flashlight = !self.flashlight,
+                Key::N => {
+                    self.normal_map = !self.normal_map;
+                    self.update_materials();
+                }
+                Key::B => {
+                    self.blinn = !self.blinn;
+                    self.update_materials();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn on_resize(&mut self, _window: &mut Window, width: u32, height: u32) {
+        self.camera.resize(width, height);
+    }
+}
+
+fn main() {
+    let mut w = Window::new(800, 600, "Lighting");
+    let program = Rc::new(phong::new_program());
+
+    let wall = Rc::new(Texture::new(
+        DIFFUSE_MAP_UNIFORM,
+        "res/textures/wall.jpg",
+        DIFFUSE_MAP_SLOT,
+    ));
+    let bumps = Rc::new(Texture::from_image(
+        NORMAL_MAP_UNIFORM,
+        &bumps_normal_map(256, 8),
+        TextureFormat::Rgba8,
+        NORMAL_MAP_SLOT,
+        &TextureSampler::default(),
+    ));
+    let floor = PhongMaterial {
+        ambient: glm::vec3(0.05, 0.05, 0.05),
+        diffuse: glm::vec3(1.0, 1.0, 1.0),
+        specular: glm::vec3(0.3, 0.3, 0.3),
+        diffuse_map: Some(wall),
+        ..PhongMaterial::default()
+    };
+    let floor_materials = [
+        Rc::new(floor.to_material(program.clone())),
+        Rc::new(
+            PhongMaterial {
+                normal_map: Some(bumps),
+                ..floor
+            }
+            .to_material(program.clone()),
+        ),
+    ];
+
+    let mut scene = Scene::new();
+    let floor = scene.add_node(SceneNode::new_mesh(
+        "floor",
+        Transform::default(),
+        Rc::new(primitives::plane(12.0, 12.0, 1, 1)),
+        vec![floor_materials[1].clone()],
+    ));
+
+    let objects = [
+        (
+            primitives::uv_sphere(0.8, 48, 24),
+            glm::vec3(0.8, 0.2, 0.2),
+            64.0,
+        ),
+        (primitives::cube(1.4), glm::vec3(0.2, 0.6, 0.9), 16.0),
+        (
+            primitives::torus(0.7, 0.25, 48, 24),
+            glm::vec3(0.9, 0.8, 0.3),
+            128.0,
+        ),
+        (
+            primitives::cylinder(0.6, 1.6, 32),
+            glm::vec3(0.3, 0.8, 0.4),
+            8.0,
+        ),
+    ];
+    let mut nodes = Vec::new();
+    for (i, (mesh, color, shininess)) in objects.into_iter().enumerate() {
+        let angle = i as f32 * std::f32::consts::FRAC_PI_2;
+        let material = PhongMaterial {
+            diffuse: color,
+            ambient: color * 0.1,
+            shininess,
+            ..PhongMaterial::default()
+        };
+        let blinn = Rc::new(material.to_material(program.clone()));
+        let phong = Rc::new(
+            PhongMaterial {
+                blinn: false,
+                ..material
+            }
+            .to_material(program.clone()),
+        );
+        let node = scene.add_node(SceneNode::new_mesh(
+            &format!("object {i}"),
+            Transform::from_translation(glm::vec3(angle.cos() * 2.0, 0.8, angle.sin() * 2.0)),
+            Rc::new(mesh),
+            vec![blinn.clone()],
+        ));
+        nodes.push((node, blinn, phong));
+    }
+
+    let mut lights = Lights::new();
+    lights.directional_lights.push(DirectionalLight {
+        intensity: 0.2,
+        ..DirectionalLight::new(glm::vec3(-0.3, -1.0, -0.5))
+    });
+
+    // Small spheres showing the point lights, lit only by the ambient term
+    let marker_mesh = Rc::new(primitives::icosphere(0.1, 2));
+    let mut markers = Vec::new();
+    for color in [
+        glm::vec3(1.0, 0.3, 0.3),
+        glm::vec3(0.3, 1.0, 0.3),
+        glm::vec3(0.3, 0.3, 1.0),
+    ] {
+        lights.point_lights.push(PointLight {
+            color,
+            intensity: 1.5,
+            ..PointLight::new(glm::Vec3::zeros(), 10.0)
+        });
+        let marker = PhongMaterial {
+            ambient: color,
+            diffuse: glm::vec3(1.0, 1.0, 1.0),
+            specular: glm::Vec3::zeros(),
+            ..PhongMaterial::default()
+        };
+        markers.push(scene.add_node(SceneNode::new_mesh(
+            "light",
+            Transform::default(),
+            marker_mesh.clone(),
+            vec![Rc::new(marker.to_material(program.clone()))],
+        )));
+    }
+
+    let camera = Camera::new(
+        glm::Vec3::zeros(),
+        Projection::perspective(),
+        w.get_aspect_ratio(),
+    );
+    let mut orbit = OrbitController::new(glm::vec3(0.0, 0.5, 0.0), 9.0);
+    orbit.pitch = 0.5;
+
+    let mut app = Lighting {
+        renderer: Renderer::default(),
+        scene,
+        lights,
+        camera,
+        orbit,
+        markers,
+        nodes,
+        floor,
+        floor_materials,
+        time: 0.0,
+        flashlight: false,
+        normal_map: true,
+        blinn: true,
+    };
+    w.run(&mut app);
+}
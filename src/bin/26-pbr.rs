@@ -0,0 +1,231 @@
+use std::f32::consts::{PI, TAU};
+use std::path::Path;
+use std::rc::Rc;
+
+use nalgebra_glm as glm;
+use opengl_sandbox::{
+    animation::Animation,
+    application::Application,
+    camera::{Camera, CameraController, OrbitController, Projection},
+    clear_values::ClearValues,
+    environment::Environment,
+    event::{Event, Key, KeyAction},
+    framebuffer::DefaultFramebuffer,
+    gltf_loader::GltfModel,
+    light::{DirectionalLight, Lights},
+    pbr::{self, PbrMaterial},
+    post_processing::{PostProcessing, ShaderEffect},
+    primitives,
+    program::Program,
+    renderer::Renderer,
+    scene::{Scene, SceneNode},
+    texture::{Texture, TextureFilter, TextureSampler, TextureWrap},
+    transform::Transform,
+    window::Window,
+};
+
+// Equirectangular sky with a small, very bright sun, so the reflections have something to show
+fn procedural_sky(width: u32, height: u32) -> image::Rgba32FImage {
+    let sun = glm::vec3(0.5, 0.35, -0.8).normalize();
+    let zenith = glm::vec3(0.15, 0.35, 0.9);
+    let horizon = glm::vec3(1.2, 1.1, 1.0);
+    let ground = glm::vec3(0.25, 0.2, 0.15);
+
+    image::Rgba32FImage::from_fn(width, height, |x, y| {
+        // Row 0 is straight up
+        let phi = ((x as f32 + 0.5) / width as f32 - 0.5) * TAU;
+        let theta = (y as f32 + 0.5) / height as f32 * PI;
+        let direction = glm::vec3(
+            theta.sin() * phi.cos(),
+            theta.cos(),
+            theta.sin() * phi.sin(),
+        );
+
+        let mut color = match direction.y > 0.0 {
+            true => glm::lerp(&horizon, &zenith, direction.y.sqrt()),
+            false => ground,
+        };
+        if direction.dot(&sun) > 0.9995 {
+            color = glm::vec3(200.0, 180.0, 150.0);
+        }
+        image::Rgba([color.x, color.y, color.z, 1.0])
+    })
+}
+
+// Spheres from dielectric (bottom) to metal (top) and from smooth (left) to rough (right)
+fn sphere_grid(program: &Rc<Program>) -> Scene {
+    const SIZE: usize = 7;
+    let sphere = Rc::new(primitives::uv_sphere(0.4, 48, 24));
+
+    let mut scene = Scene::new();
+    for row in 0..SIZE {
+        for column in 0..SIZE {
+            let material = PbrMaterial {
+                base_color: glm::vec4(0.95, 0.64, 0.54, 1.0),
+                metallic: row as f32 / (SIZE - 1) as f32,
+                roughness: column as f32 / (SIZE - 1) as f32,
+                ..PbrMaterial::default()
+            };
+            let offset = (SIZE - 1) as f32 * 0.5;
+            scene.add_node(SceneNode::new_mesh(
+                "sphere",
+                Transform::from_translation(glm::vec3(
+                    column as f32 - offset,
+                    row as f32 - offset,
+                    0.0,
+                )),
+                sphere.clone(),
+                vec![Rc::new(material.to_material(program.clone()))],
+            ));
+        }
+    }
+    scene
+}
+
+struct PbrViewer {
+    renderer: Renderer,
+    scene: Scene,
+    animations: Vec<Animation>,
+    lights: Lights,
+    environment: Environment,
+    post_processing: PostProcessing,
+    window_target: DefaultFramebuffer,
+    camera: Camera,
+    orbit: OrbitController,
+    time: f32,
+}
+
+impl Application for PbrViewer {
+    fn update(&mut self, window: &mut Window, dt: f32) {
+        self.orbit.update(&mut self.camera, window.get_input(), dt);
+
+        self.time += dt;
+        for animation in &self.animations {
+            let duration = animation.get_duration().max(f32::EPSILON);
+            animation.apply(&mut self.scene, self.time % duration);
+        }
+    }
+
+    fn render(&mut self, _window: &mut Window) {
+        let renderer = &self.renderer;
+        renderer.render_to(self.post_processing.get_scene_target(), || {
+            renderer.clear_with(&ClearValues::default());
+
+            self.lights.update(&self.camera.position);
+            self.environment.bind();
+            renderer.draw_scene(&self.scene, &self.camera);
+            self.environment.unbind();
+
+            self.environment.draw_skybox(renderer, &self.camera);
+        });
+        self.post_processing.render(renderer, &self.window_target);
+    }
+
+    fn on_event(&mut self, window: &mut Window, event: &Event) {
+        if let Event::Key {
+            key: Key::Escape,
+            action: KeyAction::Press,
+            ..
+        } = event
+        {
+            window.close();
+        }
+    }
+
+    fn on_resize(&mut self, _window: &mut Window, width: u32, height: u32) {
+        self.camera.resize(width, height);
+        self.post_processing.resize(width, height);
+        self.window_target = DefaultFramebuffer::new(width, height);
+    }
+}
+
+// Usage: 26-pbr [environment.hdr] [model.gltf or .glb]. Without arguments, a grid of spheres
+// under a procedural sky
+fn main() {
+    let mut environment_path = None;
+    let mut model_path = None;
+    for argument in std::env::args().skip(1) {
+        match Path::new(&argument).extension().and_then(|e| e.to_str()) {
+            Some("hdr") => environment_path = Some(argument),
+            Some("gltf" | "glb") => model_path = Some(argument),
+            _ => panic!("Unknown file type: {argument}"),
+        }
+    }
+
+    let mut w = Window::new(800, 600, "PBR");
+    let renderer = Renderer::default();
+
+    let environment = match &environment_path {
+        Some(path) => {
+            Environment::load_hdr(&renderer, path).unwrap_or_else(|error| panic!("{path}: {error}"))
+        }
+        None => {
+            let sky = Texture::from_hdr_image(
+                "uEquirectangularMap",
+                &procedural_sky(1024, 512),
+                0,
+                &TextureSampler {
+                    min_filter: TextureFilter::Linear,
+                    mag_filter: TextureFilter::Linear,
+                    wrap_s: TextureWrap::Repeat,
+                    wrap_t: TextureWrap::ClampToEdge,
+                },
+            );
+            Environment::from_equirectangular(&renderer, &sky)
+        }
+    };
+
+    let program = Rc::new(pbr::new_program(true));
+    let (scene, animations, distance) = match &model_path {
+        Some(path) => {
+            let model = GltfModel::load(path).unwrap_or_else(|error| panic!("{path}: {error}"));
+            let materials: Vec<_> = model
+                .materials
+                .iter()
+                .map(|material| {
+                    Rc::new(PbrMaterial::from_gltf(material).to_material(program.clone()))
+                })
+                .collect();
+
+            let mut scene = model.scene;
+            for node in model.nodes.into_iter().flatten() {
+                if scene.get_node(node).mesh.is_some() {
+                    scene.get_node_mut(node).materials = materials.clone();
+                }
+            }
+            (scene, model.animations, 7.0)
+        }
+        None => (sphere_grid(&program), Vec::new(), 10.0),
+    };
+
+    let mut lights = Lights::new();
+    lights.directional_lights.push(DirectionalLight {
+        intensity: 3.0,
+        ..DirectionalLight::new(glm::vec3(-0.5, -0.35, 0.8))
+    });
+
+    let (width, height) = w.get_framebuffer_size();
+    let mut post_processing = PostProcessing::new(width, height);
+    post_processing.add_effect(ShaderEffect::tonemapping(1.0, 2.2));
+
+    let camera = Camera::new(
+        glm::Vec3::zeros(),
+        Projection::perspective(),
+        w.get_aspect_ratio(),
+    );
+    let orbit = OrbitController::new(glm::Vec3::zeros(), distance);
+
+    let mut app = PbrViewer {
+        renderer,
+        scene,
+        animations,
+        lights,
+        environment,
+        post_processing,
+        window_target: DefaultFramebuffer::new(width, height),
+        camera,
+        orbit,
+        time: 0.0,
+    };
+    w.run(&mut app);
+}
@@ -0,0 +1,212 @@
+use std::rc::Rc;
+
+use nalgebra_glm as glm;
+use opengl_sandbox::{
+    application::Application,
+    camera::{Camera, CameraController, FlyController, Projection},
+    clear_values::ClearValues,
+    event::{Event, Key, KeyAction},
+    light::{DirectionalLight, Lights, PointLight, SpotLight},
+    phong::{self, PhongMaterial},
+    primitives,
+    renderer::Renderer,
+    scene::{Scene, SceneNode},
+    shadow::{ShadowMaps, MAX_PCF_RADIUS},
+    transform::Transform,
+    window::Window,
+};
+
+// A field of pillars under the sun, large enough for the cascades to matter, with a point light
+// circling the objects in the middle and a spot light above them.
+// 1, 2 and 3 toggle the sun, point and spot light shadows, P cycles the PCF radius, Up/Down
+// change the depth bias and Left/Right the normal bias
+struct Shadows {
+    renderer: Renderer,
+    scene: Scene,
+    lights: Lights,
+    shadow_maps: ShadowMaps,
+    camera: Camera,
+    fly: FlyController,
+    time: f32,
+}
+
+impl Shadows {
+    fn print_settings(&self) {
+        println!(
+            "PCF radius {}, depth bias {:.4}, normal bias {:.3}",
+            self.shadow_maps.pcf_radius, self.shadow_maps.depth_bias, self.shadow_maps.normal_bias
+        );
+    }
+}
+
+impl Application for Shadows {
+    fn update(&mut self, window: &mut Window, dt: f32) {
+        self.fly.update(&mut self.camera, window.get_input(), dt);
+        self.time += dt;
+
+        let angle = self.time * 0.8;
+        self.lights.point_lights[0].position = glm::vec3(angle.cos() * 3.0, 1.2, angle.sin() * 3.0);
+    }
+
+    fn render(&mut self, _window: &mut Window) {
+        let renderer = &self.renderer;
+        self.shadow_maps
+            .render(renderer, &self.scene, &self.camera, &mut self.lights);
+
+        renderer.clear_with(&ClearValues::color(0.45, 0.6, 0.8, 1.0));
+        self.lights.update(&self.camera.position);
+        self.shadow_maps.bind();
+        renderer.draw_scene(&self.scene, &self.camera);
+        self.shadow_maps.unbind();
+    }
+
+    fn on_event(&mut self, window: &mut Window, event: &Event) {
+        if let Event::Key {
+            key,
+            action: KeyAction::Press | KeyAction::Repeat,
+            ..
+        } = event
+        {
+            let toggle = |light: &mut Option<usize>| {
+                *light = match light {
+                    Some(_) => None,
+                    None => Some(0),
+                }
+            };
+            let shadow_maps = &mut self.shadow_maps;
+            match key {
+                Key::Escape => window.close(),
+                Key::Num1 => toggle(&mut shadow_maps.directional_light),
+                Key::Num2 => toggle(&mut shadow_maps.point_light),
+                Key::Num3 => toggle(&mut shadow_maps.spot_light),
+                Key::P => {
+                    shadow_maps.pcf_radius = (shadow_maps.pcf_radius + 1) % (MAX_PCF_RADIUS + 1)
+                }
+                Key::Up => shadow_maps.depth_bias += 0.0005,
+                Key::Down => shadow_maps.depth_bias = (shadow_maps.depth_bias - 0.0005).max(0.0),
+                Key::Right => shadow_maps.normal_bias += 0.005,
+                Key::Left => shadow_maps.normal_bias = (shadow_maps.normal_bias - 0.005).max(0.0),
+                _ => return,
+            }
+            self.print_settings();
+        }
+    }
+
+    fn on_resize(&mut self, _window: &mut Window, width: u32, height: u32) {
+        self.camera.resize(width, height);
+    }
+}
+
+fn main() {
+    let mut w = Window::new(800, 600, "Shadows");
+    let program = Rc::new(phong::new_program());
+    let material = |color: glm::Vec3| {
+        Rc::new(
+            PhongMaterial {
+                ambient: glm::vec3(0.3, 0.3, 0.3),
+                diffuse: color,
+                specular: glm::vec3(0.2, 0.2, 0.2),
+                ..PhongMaterial::default()
+            }
+            .to_material(program.clone()),
+        )
+    };
+
+    let mut scene = Scene::new();
+    scene.add_node(SceneNode::new_mesh(
+        "ground",
+        Transform::default(),
+        Rc::new(primitives::plane(120.0, 120.0, 1, 1)),
+        vec![material(glm::vec3(0.6, 0.65, 0.5))],
+    ));
+
+    // Pillars every 8 units, except in the middle
+    let pillar = Rc::new(primitives::cylinder(0.4, 4.0, 24));
+    let pillar_material = material(glm::vec3(0.8, 0.75, 0.7));
+    for x in -6..=6 {
+        for z in -6..=6 {
+            if x == 0 && z == 0 {
+                continue;
+            }
+            scene.add_node(SceneNode::new_mesh(
+                "pillar",
+                Transform::from_translation(glm::vec3(x as f32 * 8.0, 2.0, z as f32 * 8.0)),
+                pillar.clone(),
+                vec![pillar_material.clone()],
+            ));
+        }
+    }
+
+    let objects = [
+        (
+            primitives::uv_sphere(0.8, 48, 24),
+            glm::vec3(-1.5, 0.8, 0.0),
+            glm::vec3(0.8, 0.2, 0.2),
+        ),
+        (
+            primitives::cube(1.2),
+            glm::vec3(1.5, 0.6, 0.0),
+            glm::vec3(0.2, 0.6, 0.9),
+        ),
+        (
+            primitives::torus(0.7, 0.25, 48, 24),
+            glm::vec3(0.0, 1.5, -1.5),
+            glm::vec3(0.9, 0.8, 0.3),
+        ),
+    ];
+    for (mesh, position, color) in objects {
+        scene.add_node(SceneNode::new_mesh(
+            "object",
+            Transform::from_translation(position),
+            Rc::new(mesh),
+            vec![material(color)],
+        ));
+    }
+
+    let mut lights = Lights::new();
+    lights.directional_lights.push(DirectionalLight {
+        color: glm::vec3(1.0, 0.95, 0.85),
+        ..DirectionalLight::new(glm::vec3(-0.4, -1.0, -0.6))
+    });
+    lights.point_lights.push(PointLight {
+        color: glm::vec3(1.0, 0.6, 0.3),
+        intensity: 2.0,
+        ..PointLight::new(glm::Vec3::zeros(), 8.0)
+    });
+    lights.spot_lights.push(SpotLight {
+        color: glm::vec3(0.4, 0.6, 1.0),
+        intensity: 2.0,
+        ..SpotLight::new(
+            glm::vec3(4.0, 6.0, 4.0),
+            glm::vec3(-0.5, -1.0, -0.5),
+            20.0,
+            0.3,
+            0.45,
+        )
+    });
+
+    let mut shadow_maps = ShadowMaps::new(1024, 4);
+    shadow_maps.directional_light = Some(0);
+    shadow_maps.point_light = Some(0);
+    shadow_maps.spot_light = Some(0);
+    shadow_maps.max_distance = 60.0;
+
+    let mut camera = Camera::new(
+        glm::vec3(0.0, 4.0, 12.0),
+        Projection::perspective(),
+        w.get_aspect_ratio(),
+    );
+    camera.look_at(&glm::vec3(0.0, 1.0, 0.0));
+
+    let mut app = Shadows {
+        renderer: Renderer::default(),
+        scene,
+        lights,
+        shadow_maps,
+        camera,
+        fly: FlyController::new(8.0, 0.003),
+        time: 0.0,
+    };
+    app.print_settings();
+    w.run(&mut app);
+}
@@ -0,0 +1,97 @@
+use nalgebra_glm as glm;
+
+// Axis-aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: glm::Vec3,
+    pub max: glm::Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: glm::Vec3, max: glm::Vec3) -> Self {
+        Self { min, max }
+    }
+
+    // Empty iterators give a box of size 0 at the origin
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a glm::Vec3>) -> Self {
+        let mut points = points.into_iter();
+        let first = match points.next() {
+            Some(point) => *point,
+            None => return Self::new(glm::Vec3::zeros(), glm::Vec3::zeros()),
+        };
+
+        points.fold(Self::new(first, first), |aabb, point| {
+            Self::new(glm::min2(&aabb.min, point), glm::max2(&aabb.max, point))
+        })
+    }
+
+    pub fn get_center(&self) -> glm::Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    // Half the size along each axis
+    pub fn get_extents(&self) -> glm::Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn get_corners(&self) -> [glm::Vec3; 8] {
+        std::array::from_fn(|i| {
+            glm::vec3(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            )
+        })
+    }
+
+    pub fn contains(&self, point: &glm::Vec3) -> bool {
+        (0..3).all(|axis| self.min[axis] <= point[axis] && point[axis] <= self.max[axis])
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Self::new(
+            glm::min2(&self.min, &other.min),
+            glm::max2(&self.max, &other.max),
+        )
+    }
+
+    // Box containing the transformed box, for example, in world space with the world matrix
+    pub fn transform(&self, matrix: &glm::Mat4) -> Aabb {
+        let corners = self
+            .get_corners()
+            .map(|corner| (matrix * corner.push(1.0)).xyz());
+        Self::from_points(&corners)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: glm::Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: glm::Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    // Centered on the bounding box of the points. Not the smallest sphere, but close for most
+    // meshes
+    pub fn from_points(points: &[glm::Vec3]) -> Self {
+        let center = Aabb::from_points(points).get_center();
+        let radius = points
+            .iter()
+            .map(|point| glm::distance(&center, point))
+            .fold(0.0, f32::max);
+        Self::new(center, radius)
+    }
+
+    // The radius is scaled by the largest scale of the matrix
+    pub fn transform(&self, matrix: &glm::Mat4) -> BoundingSphere {
+        let center = (matrix * self.center.push(1.0)).xyz();
+        let scale = (0..3)
+            .map(|axis| matrix.column(axis).xyz().norm())
+            .fold(0.0, f32::max);
+        Self::new(center, self.radius * scale)
+    }
+}
@@ -0,0 +1,224 @@
+use std::marker::PhantomData;
+
+use bytemuck::Pod;
+use gl;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferTarget {
+    Array = gl::ARRAY_BUFFER,
+    ElementArray = gl::ELEMENT_ARRAY_BUFFER,
+    Uniform = gl::UNIFORM_BUFFER,
+    ShaderStorage = gl::SHADER_STORAGE_BUFFER,
+    AtomicCounter = gl::ATOMIC_COUNTER_BUFFER,
+    DrawIndirect = gl::DRAW_INDIRECT_BUFFER,
+    PixelPack = gl::PIXEL_PACK_BUFFER,
+}
+
+impl BufferTarget {
+    // Targets with binding points that shaders refer to with "layout (binding = N)"
+    fn is_indexed(&self) -> bool {
+        matches!(
+            self,
+            BufferTarget::Uniform | BufferTarget::ShaderStorage | BufferTarget::AtomicCounter
+        )
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy)]
+pub enum BufferUsage {
+    StaticDraw = gl::STATIC_DRAW,
+    DynamicDraw = gl::DYNAMIC_DRAW,
+    StreamDraw = gl::STREAM_DRAW,
+    // Written by the GPU and read back by the CPU
+    DynamicRead = gl::DYNAMIC_READ,
+    StreamRead = gl::STREAM_READ,
+    // Written and read by the GPU only
+    DynamicCopy = gl::DYNAMIC_COPY,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy)]
+pub enum MemoryBarrier {
+    VertexAttribArray = gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT,
+    ElementArray = gl::ELEMENT_ARRAY_BARRIER_BIT,
+    Uniform = gl::UNIFORM_BARRIER_BIT,
+    Command = gl::COMMAND_BARRIER_BIT,
+    BufferUpdate = gl::BUFFER_UPDATE_BARRIER_BIT,
+    ShaderStorage = gl::SHADER_STORAGE_BARRIER_BIT,
+    AtomicCounter = gl::ATOMIC_COUNTER_BARRIER_BIT,
+    All = gl::ALL_BARRIER_BITS,
+}
+
+// Makes the writes of previous shader invocations visible to the given kind of reads. For
+// example, MemoryBarrier::Command is needed before drawing with indirect commands generated by a
+// compute shader
+pub fn memory_barrier(barriers: &[MemoryBarrier]) {
+    let bits = barriers
+        .iter()
+        .fold(0, |bits, barrier| bits | *barrier as u32);
+    unsafe { gl::MemoryBarrier(bits) };
+}
+
+// GPU buffer that remembers the type and number of its elements. Only plain-old-data types can
+// be uploaded, so the bytes sent to OpenGL are always initialized and pointer free
+pub struct Buffer<T: Pod> {
+    id: u32,
+    target: BufferTarget,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> Buffer<T> {
+    pub fn new(target: BufferTarget, data: &[T]) -> Self {
+        Self::with_usage(target, data, BufferUsage::StaticDraw)
+    }
+
+    pub fn with_usage(target: BufferTarget, data: &[T], usage: BufferUsage) -> Self {
+        assert_ne!(std::mem::size_of::<T>(), 0);
+        assert_ne!(data.len(), 0);
+
+        let mut id = 0;
+        let bytes: &[u8] = bytemuck::cast_slice(data);
+
+        unsafe {
+            gl::GenBuffers(1, &mut id);
+            assert_ne!(id, 0);
+            gl::BindBuffer(target as u32, id);
+            gl::BufferData(
+                target as u32,
+                bytes.len() as isize,
+                bytes.as_ptr().cast(),
+                usage as u32,
+            );
+            gl::BindBuffer(target as u32, 0);
+        }
+
+        Self {
+            id,
+            target,
+            len: data.len(),
+            _marker: PhantomData,
+        }
+    }
+
+    // Buffer filled with zeros, useful when its content is generated on the GPU
+    pub fn zeroed(target: BufferTarget, len: usize, usage: BufferUsage) -> Self {
+        Self::with_usage(target, &vec![T::zeroed(); len], usage)
+    }
+
+    // Number of elements of type T stored in the buffer
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get_target(&self) -> BufferTarget {
+        self.target
+    }
+
+    pub fn bind(&self) {
+        unsafe { gl::BindBuffer(self.target as u32, self.id) };
+    }
+
+    pub fn unbind(&self) {
+        unsafe { gl::BindBuffer(self.target as u32, 0) };
+    }
+
+    // Binds the buffer to a target different from the one used to create it. For example, a
+    // shader storage buffer filled by a compute shader can be used as vertex or indirect buffer
+    pub fn bind_to(&self, target: BufferTarget) {
+        unsafe { gl::BindBuffer(target as u32, self.id) };
+    }
+
+    // Binds the whole buffer to the "layout (binding = index)" binding point
+    pub fn bind_base(&self, index: u32) {
+        assert!(self.target.is_indexed());
+        unsafe { gl::BindBufferBase(self.target as u32, index, self.id) };
+    }
+
+    // Binds len elements starting at the offset element. The offset must respect the alignment
+    // required by the target (for example, GL_UNIFORM_BUFFER_OFFSET_ALIGNMENT)
+    pub fn bind_range(&self, index: u32, offset: usize, len: usize) {
+        assert!(self.target.is_indexed());
+        assert!(offset + len <= self.len);
+
+        let element_size = std::mem::size_of::<T>();
+        unsafe {
+            gl::BindBufferRange(
+                self.target as u32,
+                index,
+                self.id,
+                (offset * element_size) as isize,
+                (len * element_size) as isize,
+            )
+        };
+    }
+
+    // Overwrites the elements starting at the offset element
+    pub fn update(&self, offset: usize, data: &[T]) {
+        assert!(offset + data.len() <= self.len);
+
+        let bytes: &[u8] = bytemuck::cast_slice(data);
+        self.bind();
+        unsafe {
+            gl::BufferSubData(
+                self.target as u32,
+                (offset * std::mem::size_of::<T>()) as isize,
+                bytes.len() as isize,
+                bytes.as_ptr().cast(),
+            );
+        }
+        self.unbind();
+    }
+
+    // Copies the buffer back to the CPU. Use memory_barrier(&[MemoryBarrier::BufferUpdate])
+    // before reading data written by a shader
+    pub fn read(&self) -> Vec<T> {
+        let mut data = vec![T::zeroed(); self.len];
+        let bytes: &mut [u8] = bytemuck::cast_slice_mut(&mut data);
+
+        self.bind();
+        unsafe {
+            gl::GetBufferSubData(
+                self.target as u32,
+                0,
+                bytes.len() as isize,
+                bytes.as_mut_ptr().cast(),
+            );
+        }
+        self.unbind();
+
+        data
+    }
+
+    // Maps the buffer for reading and passes its content to the callback, avoiding the copy made
+    // by read()
+    pub fn map_read<R>(&self, callback: impl FnOnce(&[T]) -> R) -> R {
+        let size = self.len * std::mem::size_of::<T>();
+
+        self.bind();
+        let ptr =
+            unsafe { gl::MapBufferRange(self.target as u32, 0, size as isize, gl::MAP_READ_BIT) };
+        assert!(!ptr.is_null(), "Error mapping buffer");
+
+        // Mapped buffers are aligned to at least GL_MIN_MAP_BUFFER_ALIGNMENT (64 bytes)
+        let bytes = unsafe { std::slice::from_raw_parts(ptr as *const u8, size) };
+        let result = callback(bytemuck::cast_slice(bytes));
+
+        unsafe { gl::UnmapBuffer(self.target as u32) };
+        self.unbind();
+
+        result
+    }
+}
+
+impl<T: Pod> Drop for Buffer<T> {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteBuffers(1, &self.id) };
+    }
+}
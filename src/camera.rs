@@ -0,0 +1,260 @@
+use nalgebra_glm as glm;
+
+use crate::event::{Key, MouseButton};
+use crate::input_state::InputState;
+
+// Pitch limit, looking straight up or down makes the yaw undefined
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    // fov_y: vertical field of view, in radians
+    Perspective { fov_y: f32, near: f32, far: f32 },
+    // height: visible height in world units, the width follows the aspect ratio
+    Orthographic { height: f32, near: f32, far: f32 },
+}
+
+impl Projection {
+    // 45 degrees field of view
+    pub fn perspective() -> Self {
+        Projection::Perspective {
+            fov_y: std::f32::consts::FRAC_PI_4,
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+
+    pub fn orthographic(height: f32) -> Self {
+        Projection::Orthographic {
+            height,
+            near: -100.0,
+            far: 100.0,
+        }
+    }
+}
+
+// Right-handed, looks along -Z with no rotation, like OpenGL
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub position: glm::Vec3,
+    pub orientation: glm::Quat,
+    pub projection: Projection,
+    aspect_ratio: f32,
+}
+
+impl Camera {
+    pub fn new(position: glm::Vec3, projection: Projection, aspect_ratio: f32) -> Self {
+        Self {
+            position,
+            orientation: glm::quat_identity(),
+            projection,
+            aspect_ratio,
+        }
+    }
+
+    // Call it when the window is resized, for example, from Application::on_resize
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
+        self.aspect_ratio = aspect_ratio;
+    }
+
+    // Minimised windows have a 0x0 framebuffer, the aspect ratio is kept
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.set_aspect_ratio(width as f32 / height as f32);
+    }
+
+    pub fn get_aspect_ratio(&self) -> f32 {
+        self.aspect_ratio
+    }
+
+    // Rotates the camera towards the target, without roll
+    pub fn look_at(&mut self, target: &glm::Vec3) {
+        let direction = target - self.position;
+        if direction.norm() > f32::EPSILON {
+            let direction = direction.normalize();
+            self.set_yaw_pitch(
+                f32::atan2(-direction.x, -direction.z),
+                direction.y.clamp(-1.0, 1.0).asin(),
+            );
+        }
+    }
+
+    // yaw: rotation around the world Y axis, pitch: rotation around the camera X axis. In
+    // radians, (0, 0) looks along -Z
+    pub fn set_yaw_pitch(&mut self, yaw: f32, pitch: f32) {
+        let pitch = pitch.clamp(-MAX_PITCH, MAX_PITCH);
+        self.orientation = glm::quat_angle_axis(yaw, &glm::Vec3::y())
+            * glm::quat_angle_axis(pitch, &glm::Vec3::x());
+    }
+
+    pub fn get_yaw(&self) -> f32 {
+        let forward = self.get_forward();
+        f32::atan2(-forward.x, -forward.z)
+    }
+
+    pub fn get_pitch(&self) -> f32 {
+        self.get_forward().y.clamp(-1.0, 1.0).asin()
+    }
+
+    pub fn get_forward(&self) -> glm::Vec3 {
+        glm::quat_rotate_vec3(&self.orientation, &-glm::Vec3::z())
+    }
+
+    pub fn get_right(&self) -> glm::Vec3 {
+        glm::quat_rotate_vec3(&self.orientation, &glm::Vec3::x())
+    }
+
+    pub fn get_up(&self) -> glm::Vec3 {
+        glm::quat_rotate_vec3(&self.orientation, &glm::Vec3::y())
+    }
+
+    pub fn get_view(&self) -> glm::Mat4 {
+        glm::look_at(
+            &self.position,
+            &(self.position + self.get_forward()),
+            &self.get_up(),
+        )
+    }
+
+    pub fn get_projection(&self) -> glm::Mat4 {
+        match self.projection {
+            Projection::Perspective { fov_y, near, far } => {
+                glm::perspective(self.aspect_ratio, fov_y, near, far)
+            }
+            Projection::Orthographic { height, near, far } => {
+                let half_height = height / 2.0;
+                let half_width = half_height * self.aspect_ratio;
+                glm::ortho(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    near,
+                    far,
+                )
+            }
+        }
+    }
+
+    // projection * view, upload it with Program::set_uniform_mat4
+    pub fn view_projection(&self) -> glm::Mat4 {
+        self.get_projection() * self.get_view()
+    }
+}
+
+// Moves a camera from the window input, once per frame
+pub trait CameraController {
+    fn update(&mut self, camera: &mut Camera, input: &InputState, dt: f32);
+}
+
+// First person camera: WASD to move, E/Q or Space/Left Shift to go up and down, drag with the
+// right mouse button to look around. Hold Left Control to move faster
+pub struct FlyController {
+    // World units per second
+    pub speed: f32,
+    // Radians per pixel
+    pub sensitivity: f32,
+}
+
+impl Default for FlyController {
+    fn default() -> Self {
+        Self::new(3.0, 0.003)
+    }
+}
+
+impl FlyController {
+    pub fn new(speed: f32, sensitivity: f32) -> Self {
+        Self { speed, sensitivity }
+    }
+}
+
+impl CameraController for FlyController {
+    fn update(&mut self, camera: &mut Camera, input: &InputState, dt: f32) {
+        if input.is_mouse_button_down(MouseButton::Button2) {
+            let (dx, dy) = input.get_cursor_delta();
+            camera.set_yaw_pitch(
+                camera.get_yaw() - dx as f32 * self.sensitivity,
+                camera.get_pitch() - dy as f32 * self.sensitivity,
+            );
+        }
+
+        let axis = |positive: &[Key], negative: &[Key]| {
+            let down = |keys: &[Key]| keys.iter().any(|&key| input.is_key_down(key));
+            down(positive) as i32 as f32 - down(negative) as i32 as f32
+        };
+        let direction = camera.get_forward() * axis(&[Key::W], &[Key::S])
+            + camera.get_right() * axis(&[Key::D], &[Key::A])
+            + glm::Vec3::y() * axis(&[Key::E, Key::Space], &[Key::Q, Key::LeftShift]);
+
+        if direction.norm() > f32::EPSILON {
+            let speed = match input.is_key_down(Key::LeftControl) {
+                true => self.speed * 4.0,
+                false => self.speed,
+            };
+            camera.position += direction.normalize() * speed * dt;
+        }
+    }
+}
+
+// Rotates the camera around a target: drag with the left mouse button to rotate, with the
+// middle button to pan, scroll to zoom
+pub struct OrbitController {
+    pub target: glm::Vec3,
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    // Radians per pixel
+    pub sensitivity: f32,
+    // Fraction of the distance per scroll step
+    pub zoom_speed: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+}
+
+impl OrbitController {
+    pub fn new(target: glm::Vec3, distance: f32) -> Self {
+        Self {
+            target,
+            distance,
+            yaw: 0.0,
+            pitch: 0.0,
+            sensitivity: 0.005,
+            zoom_speed: 0.1,
+            min_distance: 0.1,
+            max_distance: 1000.0,
+        }
+    }
+}
+
+impl CameraController for OrbitController {
+    fn update(&mut self, camera: &mut Camera, input: &InputState, _dt: f32) {
+        let (dx, dy) = input.get_cursor_delta();
+        let (dx, dy) = (dx as f32, dy as f32);
+
+        if input.is_mouse_button_down(MouseButton::Button1) {
+            self.yaw -= dx * self.sensitivity;
+            self.pitch = (self.pitch + dy * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+        }
+
+        if input.is_mouse_button_down(MouseButton::Button3) {
+            // Keep the target under the cursor, roughly
+            let pan_speed = self.distance * 0.002;
+            self.target += (camera.get_up() * dy - camera.get_right() * dx) * pan_speed;
+        }
+
+        let (_, scroll) = input.get_scroll_delta();
+        self.distance = (self.distance * (1.0 - scroll as f32 * self.zoom_speed))
+            .clamp(self.min_distance, self.max_distance);
+
+        // Spherical coordinates, (0, 0) is in front of the target looking along -Z
+        let offset = glm::vec3(
+            self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            self.yaw.cos() * self.pitch.cos(),
+        );
+        camera.position = self.target + offset * self.distance;
+        camera.look_at(&self.target);
+    }
+}
@@ -0,0 +1,44 @@
+// Buffers set to None are not cleared
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClearValues {
+    pub color: Option<[f32; 4]>,
+    pub depth: Option<f32>,
+    pub stencil: Option<i32>,
+}
+
+impl Default for ClearValues {
+    fn default() -> Self {
+        Self {
+            color: Some([0.0, 0.0, 0.0, 1.0]),
+            depth: Some(1.0),
+            stencil: None,
+        }
+    }
+}
+
+impl ClearValues {
+    pub fn color(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self {
+            color: Some([r, g, b, a]),
+            depth: None,
+            stencil: None,
+        }
+    }
+
+    pub fn depth(depth: f32) -> Self {
+        Self {
+            color: None,
+            depth: Some(depth),
+            stencil: None,
+        }
+    }
+}
+
+// Value used to clear a single colour attachment. The variant must match the attachment format:
+// float and normalized formats use Float, integer formats use Int or Uint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttachmentClearValue {
+    Float([f32; 4]),
+    Int([i32; 4]),
+    Uint([u32; 4]),
+}
@@ -0,0 +1,239 @@
+use gl;
+
+use crate::framebuffer::RenderTarget;
+use crate::texture::TextureFormat;
+
+// Faces in the order of GL_TEXTURE_CUBE_MAP_POSITIVE_X + index
+pub const NUM_FACES: u32 = 6;
+
+// Square faces, sampled in shaders with "uniform samplerCube". Filled by rendering into each face
+// and mip level, see Environment. Depth cubemaps are sampled with "uniform samplerCubeShadow"
+// once set_depth_compare is enabled, see ShadowMaps
+pub struct Cubemap {
+    id: u32,
+    uniform_name: String,
+    slot: u32,
+    format: TextureFormat,
+    size: u32,
+    num_mip_levels: u32,
+}
+
+impl Cubemap {
+    // Allocates num_mip_levels levels, each half the size of the previous one. Use
+    // get_max_mip_levels for a full chain
+    pub fn new_empty(
+        uniform_name: &str,
+        size: u32,
+        format: TextureFormat,
+        slot: u32,
+        num_mip_levels: u32,
+    ) -> Self {
+        let mut id = 0;
+
+        let valid_slot_range = 0..31;
+        assert!(valid_slot_range.contains(&slot));
+        assert!(!format.has_stencil() && !format.is_integer());
+        assert!((1..=Self::get_max_mip_levels(size)).contains(&num_mip_levels));
+
+        let (pixel_format, pixel_type) = format.pixel_format();
+        let min_filter = match num_mip_levels {
+            1 => gl::LINEAR,
+            _ => gl::LINEAR_MIPMAP_LINEAR,
+        };
+
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            assert_ne!(id, 0);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, id);
+
+            for wrap in [gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+                gl::TexParameteri(gl::TEXTURE_CUBE_MAP, wrap, gl::CLAMP_TO_EDGE as i32);
+            }
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MIN_FILTER,
+                min_filter as i32,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MAG_FILTER,
+                gl::LINEAR as i32,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MAX_LEVEL,
+                num_mip_levels as i32 - 1,
+            );
+
+            for level in 0..num_mip_levels {
+                let level_size = (size >> level).max(1) as i32;
+                for face in 0..NUM_FACES {
+                    gl::TexImage2D(
+                        gl::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+                        level as i32,
+                        format as i32,
+                        level_size,
+                        level_size,
+                        0,
+                        pixel_format,
+                        pixel_type,
+                        std::ptr::null(),
+                    );
+                }
+            }
+
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+
+        Self {
+            id,
+            uniform_name: String::from(uniform_name),
+            slot,
+            format,
+            size,
+            num_mip_levels,
+        }
+    }
+
+    pub fn get_max_mip_levels(size: u32) -> u32 {
+        u32::BITS - size.leading_zeros()
+    }
+
+    // Fills the mip levels from the base one
+    pub fn generate_mipmaps(&self) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+            gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+    }
+
+    // Depth cubemaps only. When enabled, shadow samplers return the result of comparing the
+    // reference value with the stored depth instead of the depth
+    pub fn set_depth_compare(&self, enabled: bool) {
+        assert!(self.format.is_depth());
+        let mode = match enabled {
+            true => gl::COMPARE_REF_TO_TEXTURE,
+            false => gl::NONE,
+        };
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_COMPARE_MODE, mode as i32);
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_COMPARE_FUNC,
+                gl::LEQUAL as i32,
+            );
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+    }
+
+    pub(crate) fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn get_format(&self) -> TextureFormat {
+        self.format
+    }
+
+    // Width and height of the faces of the base level
+    pub fn get_size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn get_mip_size(&self, level: u32) -> u32 {
+        assert!(level < self.num_mip_levels);
+        (self.size >> level).max(1)
+    }
+
+    pub fn get_num_mip_levels(&self) -> u32 {
+        self.num_mip_levels
+    }
+
+    pub fn get_uniform_name(&self) -> &str {
+        self.uniform_name.as_str()
+    }
+
+    pub fn get_slot(&self) -> u32 {
+        self.slot
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + self.slot);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+        }
+    }
+
+    pub fn unbind(&self) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + self.slot);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+    }
+}
+
+impl Drop for Cubemap {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteTextures(1, &self.id) };
+    }
+}
+
+// Framebuffer rendering into one face and mip level of a cubemap at a time, see attach
+pub(crate) struct CubemapFaceTarget {
+    framebuffer: u32,
+    size: u32,
+}
+
+impl CubemapFaceTarget {
+    // size: size of the mip level that will be attached
+    pub(crate) fn new(size: u32) -> Self {
+        let mut framebuffer = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut framebuffer);
+            assert_ne!(framebuffer, 0);
+        }
+        Self { framebuffer, size }
+    }
+
+    // Call it inside Renderer::render_to(self, ..), with the framebuffer bound. Depth cubemaps
+    // are attached as the depth buffer of a framebuffer without colour
+    pub(crate) fn attach(&self, cubemap: &Cubemap, face: u32, level: u32) {
+        assert!(face < NUM_FACES);
+        assert_eq!(cubemap.get_mip_size(level), self.size);
+
+        let attachment = match cubemap.get_format().is_depth() {
+            true => gl::DEPTH_ATTACHMENT,
+            false => gl::COLOR_ATTACHMENT0,
+        };
+        unsafe {
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                attachment,
+                gl::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+                cubemap.get_id(),
+                level as i32,
+            );
+            if attachment == gl::DEPTH_ATTACHMENT {
+                gl::DrawBuffer(gl::NONE);
+                gl::ReadBuffer(gl::NONE);
+            }
+        }
+    }
+}
+
+impl RenderTarget for CubemapFaceTarget {
+    fn get_framebuffer_id(&self) -> u32 {
+        self.framebuffer
+    }
+
+    fn get_size(&self) -> (u32, u32) {
+        (self.size, self.size)
+    }
+}
+
+impl Drop for CubemapFaceTarget {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteFramebuffers(1, &self.framebuffer) };
+    }
+}
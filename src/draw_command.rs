@@ -0,0 +1,191 @@
+use bytemuck::{Pod, Zeroable};
+
+// Range of indices of a vertex array. Several meshes can share the same vertex array, each one
+// using its own range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawRange {
+    pub first_index: u32,
+    pub num_indices: u32,
+    // Added to each index before fetching the vertex
+    pub base_vertex: i32,
+}
+
+impl DrawRange {
+    pub fn new(first_index: u32, num_indices: u32, base_vertex: i32) -> Self {
+        Self {
+            first_index,
+            num_indices,
+            base_vertex,
+        }
+    }
+
+    // Panics if the range is outside the indices, or if the vertices it fetches, with the base
+    // vertex added to the indices of the range, are outside the vertex array
+    pub fn check(&self, indices: &[u32], num_vertices: u32) {
+        let num_indices = indices.len();
+        let end = self.first_index.checked_add(self.num_indices);
+        assert!(
+            end.is_some_and(|end| end as usize <= num_indices),
+            "Draw range {self:?} out of bounds, the vertex array has {num_indices} indices"
+        );
+
+        // Without base vertex the indices were checked when the vertex array was created
+        if self.base_vertex == 0 {
+            return;
+        }
+        let indices = &indices[self.first_index as usize..][..self.num_indices as usize];
+        if let (Some(&min_index), Some(&max_index)) = (indices.iter().min(), indices.iter().max()) {
+            let base_vertex = self.base_vertex as i64;
+            assert!(
+                min_index as i64 + base_vertex >= 0
+                    && max_index as i64 + base_vertex < num_vertices as i64,
+                "Base vertex of {self:?} out of bounds, the range indices go from {min_index} to \
+                 {max_index} and the vertex array has {num_vertices} vertices"
+            );
+        }
+    }
+}
+
+// Same layout as the command read by glDrawElementsIndirect and glMultiDrawElementsIndirect
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct DrawElementsIndirectCommand {
+    pub count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub base_vertex: i32,
+    pub base_instance: u32,
+}
+
+// Builds one command per (range, instance count) entry. Instances are numbered consecutively, so
+// the base instance of each command can be used to index per-instance data with draw_indirect.
+// Renderer::multi_draw ignores it
+pub fn build_indirect_commands(entries: &[(DrawRange, u32)]) -> Vec<DrawElementsIndirectCommand> {
+    let mut base_instance = 0;
+
+    entries
+        .iter()
+        .map(|(range, instance_count)| {
+            let command = DrawElementsIndirectCommand {
+                count: range.num_indices,
+                instance_count: *instance_count,
+                first_index: range.first_index,
+                base_vertex: range.base_vertex,
+                base_instance,
+            };
+            base_instance += instance_count;
+            command
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Mesh A: 100 vertices, triangles with local indices 0..99. Mesh B: a quad of 4 vertices
+    // stored after them, with local indices 0..3
+    fn packed_indices() -> (Vec<u32>, u32) {
+        let mut indices: Vec<u32> = (0..99).collect();
+        indices.extend([0, 1, 2, 0, 2, 3]);
+        (indices, 104)
+    }
+
+    #[test]
+    fn check_packed_meshes() {
+        let (indices, num_vertices) = packed_indices();
+        DrawRange::new(0, 99, 0).check(&indices, num_vertices);
+        DrawRange::new(99, 6, 100).check(&indices, num_vertices);
+        // Only part of mesh B
+        DrawRange::new(102, 3, 100).check(&indices, num_vertices);
+    }
+
+    #[test]
+    fn check_negative_base_vertex() {
+        let indices = [10, 11, 12];
+        DrawRange::new(0, 3, -10).check(&indices, 3);
+    }
+
+    #[test]
+    fn check_empty_range() {
+        let (indices, num_vertices) = packed_indices();
+        DrawRange::new(105, 0, 1000).check(&indices, num_vertices);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn check_range_past_the_indices() {
+        let (indices, num_vertices) = packed_indices();
+        DrawRange::new(99, 7, 100).check(&indices, num_vertices);
+    }
+
+    #[test]
+    #[should_panic(expected = "Base vertex")]
+    fn check_base_vertex_past_the_vertices() {
+        let (indices, num_vertices) = packed_indices();
+        DrawRange::new(99, 6, 101).check(&indices, num_vertices);
+    }
+
+    #[test]
+    #[should_panic(expected = "Base vertex")]
+    fn check_base_vertex_before_the_vertices() {
+        let (indices, num_vertices) = packed_indices();
+        DrawRange::new(99, 6, -1).check(&indices, num_vertices);
+    }
+
+    #[test]
+    fn no_commands() {
+        assert!(build_indirect_commands(&[]).is_empty());
+    }
+
+    #[test]
+    fn indirect_commands() {
+        let a = DrawRange::new(0, 99, 0);
+        let b = DrawRange::new(99, 6, 100);
+        let c = DrawRange::new(105, 36, 104);
+        let commands = build_indirect_commands(&[(a, 1), (b, 3), (c, 5), (a, 2)]);
+
+        assert_eq!(commands.len(), 4);
+        let fields: Vec<_> = commands
+            .iter()
+            .map(|command| {
+                (
+                    command.count,
+                    command.instance_count,
+                    command.first_index,
+                    command.base_vertex,
+                    command.base_instance,
+                )
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                (99, 1, 0, 0, 0),
+                (6, 3, 99, 100, 1),
+                (36, 5, 105, 104, 4),
+                (99, 2, 0, 0, 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_instances_keep_the_base_instance() {
+        let range = DrawRange::new(0, 3, 0);
+        let commands = build_indirect_commands(&[(range, 2), (range, 0), (range, 1)]);
+        let base_instances: Vec<_> = commands
+            .iter()
+            .map(|command| command.base_instance)
+            .collect();
+        assert_eq!(base_instances, vec![0, 2, 2]);
+    }
+
+    #[test]
+    fn indirect_command_layout() {
+        // Five tightly packed 32-bit values, as read by glMultiDrawElementsIndirect
+        assert_eq!(std::mem::size_of::<DrawElementsIndirectCommand>(), 20);
+        let command = build_indirect_commands(&[(DrawRange::new(6, 3, -2), 4)])[0];
+        let words: &[u32] = bytemuck::cast_slice(std::slice::from_ref(&command));
+        assert_eq!(words, &[3, 4, 6, -2i32 as u32, 0]);
+    }
+}
@@ -0,0 +1,453 @@
+use std::path::Path;
+use std::rc::Rc;
+
+use gl;
+use nalgebra_glm as glm;
+
+use crate::camera::Camera;
+use crate::cubemap::{Cubemap, CubemapFaceTarget, NUM_FACES};
+use crate::framebuffer::{ColorAttachment, DepthStencilAttachment, Framebuffer};
+use crate::post_processing::new_fullscreen_triangle;
+use crate::program::Program;
+use crate::render_state::{DepthState, RenderState};
+use crate::renderer::Renderer;
+use crate::shader::{Shader, ShaderType};
+use crate::texture::{Texture, TextureFilter, TextureFormat, TextureSampler, TextureWrap};
+use crate::vertex_array::VertexArray;
+
+// Texture units and sampler names of the maps bound by Environment::bind. After the material
+// textures
+pub const IRRADIANCE_MAP_UNIFORM: &str = "uIrradianceMap";
+pub const IRRADIANCE_MAP_SLOT: u32 = 5;
+pub const PREFILTERED_MAP_UNIFORM: &str = "uPrefilteredMap";
+pub const PREFILTERED_MAP_SLOT: u32 = 6;
+pub const BRDF_LUT_UNIFORM: &str = "uBrdfLut";
+pub const BRDF_LUT_SLOT: u32 = 7;
+
+// One per roughness step, from 0 (level 0) to 1 (last level)
+pub const PREFILTERED_MAP_MIP_LEVELS: u32 = 5;
+
+const ENVIRONMENT_MAP_SIZE: u32 = 512;
+const IRRADIANCE_MAP_SIZE: u32 = 32;
+const PREFILTERED_MAP_SIZE: u32 = 128;
+const BRDF_LUT_SIZE: u32 = 512;
+
+// Draws a triangle covering the target, vPosition goes from -1 to 1
+const CAPTURE_VERTEX_SHADER_SRC: &str = "#version 330 core
+layout (location = 0) in vec2 aPos;
+
+out vec2 vPosition;
+
+void main() {
+    gl_Position = vec4(aPos, 0.0, 1.0);
+    vPosition = aPos;
+}";
+
+// Goes after the #version line of the shaders rendering into a cubemap face
+const CAPTURE_FACE_SHADER_SRC: &str = "
+in vec2 vPosition;
+
+uniform int uFace;
+
+// Direction of the texel being rendered, with the face orientations of the OpenGL specification
+vec3 faceDirection() {
+    float x = vPosition.x;
+    float y = vPosition.y;
+    vec3 direction;
+    if (uFace == 0) {
+        direction = vec3(1.0, -y, -x);
+    } else if (uFace == 1) {
+        direction = vec3(-1.0, -y, x);
+    } else if (uFace == 2) {
+        direction = vec3(x, 1.0, y);
+    } else if (uFace == 3) {
+        direction = vec3(x, -1.0, -y);
+    } else if (uFace == 4) {
+        direction = vec3(x, -y, 1.0);
+    } else {
+        direction = vec3(-x, -y, -1.0);
+    }
+    return normalize(direction);
+}
+";
+
+// GGX importance sampling (Karis, Real Shading in Unreal Engine 4)
+const IMPORTANCE_SAMPLING_SHADER_SRC: &str = "
+const float PI = 3.14159265359;
+
+// Low-discrepancy sequence, better coverage than random samples
+vec2 hammersley(uint i, uint numSamples) {
+    uint bits = i;
+    bits = (bits << 16u) | (bits >> 16u);
+    bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
+    bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
+    bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
+    bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
+    return vec2(float(i) / float(numSamples), float(bits) * 2.3283064365386963e-10);
+}
+
+// Half vector around the normal, distributed like the GGX lobe of the roughness
+vec3 importanceSampleGGX(vec2 xi, vec3 normal, float roughness) {
+    float a = roughness * roughness;
+    float phi = 2.0 * PI * xi.x;
+    float cosTheta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    float sinTheta = sqrt(1.0 - cosTheta * cosTheta);
+    vec3 h = vec3(cos(phi) * sinTheta, sin(phi) * sinTheta, cosTheta);
+
+    vec3 up = abs(normal.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, normal));
+    vec3 bitangent = cross(normal, tangent);
+    return normalize(tangent * h.x + bitangent * h.y + normal * h.z);
+}
+";
+
+// The rows of the image are stored top first (Texture::from_hdr_image): v = 0 is straight up
+const EQUIRECTANGULAR_FRAGMENT_SHADER_SRC: &str = "
+out vec4 FragColor;
+
+uniform sampler2D uEquirectangularMap;
+
+void main() {
+    vec3 direction = faceDirection();
+    vec2 uv = vec2(atan(direction.z, direction.x) * 0.15915494 + 0.5,
+                   acos(clamp(direction.y, -1.0, 1.0)) * 0.31830989);
+    FragColor = vec4(texture(uEquirectangularMap, uv).rgb, 1.0);
+}";
+
+// Cosine-weighted integral of the environment over the hemisphere around each direction
+const IRRADIANCE_FRAGMENT_SHADER_SRC: &str = "
+out vec4 FragColor;
+
+uniform samplerCube uEnvironmentMap;
+
+const float SAMPLE_DELTA = 0.025;
+
+void main() {
+    vec3 normal = faceDirection();
+    vec3 up = abs(normal.y) < 0.999 ? vec3(0.0, 1.0, 0.0) : vec3(0.0, 0.0, 1.0);
+    vec3 right = normalize(cross(up, normal));
+    up = cross(normal, right);
+
+    vec3 irradiance = vec3(0.0);
+    float numSamples = 0.0;
+    for (float phi = 0.0; phi < 2.0 * PI; phi += SAMPLE_DELTA) {
+        for (float theta = 0.0; theta < 0.5 * PI; theta += SAMPLE_DELTA) {
+            vec3 tangentSample = vec3(sin(theta) * cos(phi), sin(theta) * sin(phi), cos(theta));
+            vec3 direction = tangentSample.x * right + tangentSample.y * up
+                + tangentSample.z * normal;
+            // A smaller mip level keeps small bright sources from falling between the samples
+            irradiance += textureLod(uEnvironmentMap, direction, 3.0).rgb
+                * cos(theta) * sin(theta);
+            numSamples += 1.0;
+        }
+    }
+    FragColor = vec4(PI * irradiance / numSamples, 1.0);
+}";
+
+// Split-sum approximation: the environment convolved with the GGX lobe of the roughness,
+// assuming that the view direction is the normal
+const PREFILTER_FRAGMENT_SHADER_SRC: &str = "
+out vec4 FragColor;
+
+uniform samplerCube uEnvironmentMap;
+uniform float uEnvironmentSize;
+uniform float uRoughness;
+
+const uint NUM_SAMPLES = 1024u;
+
+float distributionGGX(float NdotH, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float d = NdotH * NdotH * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+void main() {
+    vec3 normal = faceDirection();
+    vec3 view = normal;
+
+    vec3 color = vec3(0.0);
+    float totalWeight = 0.0;
+    for (uint i = 0u; i < NUM_SAMPLES; i++) {
+        vec3 h = importanceSampleGGX(hammersley(i, NUM_SAMPLES), normal, uRoughness);
+        vec3 l = normalize(2.0 * dot(view, h) * h - view);
+        float NdotL = dot(normal, l);
+        if (NdotL > 0.0) {
+            // Reads the mip level whose texels cover the solid angle of the sample, avoids bright
+            // dots around small light sources (GPU Gems 3, chapter 20)
+            float NdotH = max(dot(normal, h), 0.0);
+            float pdf = distributionGGX(NdotH, uRoughness) * 0.25 + 0.0001;
+            float texelSolidAngle = 4.0 * PI / (6.0 * uEnvironmentSize * uEnvironmentSize);
+            float sampleSolidAngle = 1.0 / (float(NUM_SAMPLES) * pdf + 0.0001);
+            float level = uRoughness == 0.0
+                ? 0.0 : 0.5 * log2(sampleSolidAngle / texelSolidAngle);
+
+            color += textureLod(uEnvironmentMap, l, level).rgb * NdotL;
+            totalWeight += NdotL;
+        }
+    }
+    FragColor = vec4(color / totalWeight, 1.0);
+}";
+
+// Scale (R) and bias (G) applied to F0 by the split-sum approximation, by NdotV (x) and
+// roughness (y)
+const BRDF_LUT_FRAGMENT_SHADER_SRC: &str = "
+out vec4 FragColor;
+
+const uint NUM_SAMPLES = 1024u;
+
+float geometrySchlickGGX(float NdotV, float roughness) {
+    // k for image-based lighting
+    float k = roughness * roughness / 2.0;
+    return NdotV / (NdotV * (1.0 - k) + k);
+}
+
+vec2 integrateBrdf(float NdotV, float roughness) {
+    vec3 view = vec3(sqrt(1.0 - NdotV * NdotV), 0.0, NdotV);
+    vec3 normal = vec3(0.0, 0.0, 1.0);
+
+    float scale = 0.0;
+    float bias = 0.0;
+    for (uint i = 0u; i < NUM_SAMPLES; i++) {
+        vec3 h = importanceSampleGGX(hammersley(i, NUM_SAMPLES), normal, roughness);
+        vec3 l = normalize(2.0 * dot(view, h) * h - view);
+        float NdotL = max(l.z, 0.0);
+        float NdotH = max(h.z, 0.0);
+        float VdotH = max(dot(view, h), 0.0);
+        if (NdotL > 0.0) {
+            float g = geometrySchlickGGX(NdotV, roughness) * geometrySchlickGGX(NdotL, roughness);
+            float gVisibility = g * VdotH / (NdotH * NdotV);
+            float fresnel = pow(1.0 - VdotH, 5.0);
+            scale += (1.0 - fresnel) * gVisibility;
+            bias += fresnel * gVisibility;
+        }
+    }
+    return vec2(scale, bias) / float(NUM_SAMPLES);
+}
+
+void main() {
+    vec2 uv = vPosition * 0.5 + 0.5;
+    FragColor = vec4(integrateBrdf(max(uv.x, 0.001), uv.y), 0.0, 1.0);
+}";
+
+// Triangle on the far plane, drawn after the opaque geometry so only the background is shaded
+const SKYBOX_VERTEX_SHADER_SRC: &str = "#version 330 core
+layout (location = 0) in vec2 aPos;
+
+out vec2 vPosition;
+
+void main() {
+    gl_Position = vec4(aPos, 1.0, 1.0);
+    vPosition = aPos;
+}";
+
+const SKYBOX_FRAGMENT_SHADER_SRC: &str = "#version 330 core
+in vec2 vPosition;
+
+out vec4 FragColor;
+
+uniform samplerCube uEnvironmentMap;
+// Without the translation of the camera
+uniform mat4 uInverseViewProjection;
+
+void main() {
+    vec4 direction = uInverseViewProjection * vec4(vPosition, 1.0, 1.0);
+    FragColor = vec4(texture(uEnvironmentMap, direction.xyz / direction.w).rgb, 1.0);
+}";
+
+fn new_capture_program(fragment_shader_src: &str) -> Program {
+    let vertex_shader = Shader::new(ShaderType::VertexShader, CAPTURE_VERTEX_SHADER_SRC);
+    let fragment_shader = Shader::new(
+        ShaderType::FragmentShader,
+        &format!(
+            "#version 330 core\n{CAPTURE_FACE_SHADER_SRC}\n{IMPORTANCE_SAMPLING_SHADER_SRC}\n{fragment_shader_src}"
+        ),
+    );
+    Program::new(&vertex_shader, &fragment_shader)
+}
+
+// Runs the program once per face, writing into the mip level of the cubemap
+fn render_to_cubemap(
+    renderer: &Renderer,
+    fullscreen: &VertexArray,
+    program: &Program,
+    cubemap: &Cubemap,
+    level: u32,
+) {
+    let target = CubemapFaceTarget::new(cubemap.get_mip_size(level));
+    for face in 0..NUM_FACES {
+        program.set_uniform_1i("uFace", face as i32);
+        renderer.render_to(&target, || {
+            target.attach(cubemap, face, level);
+            renderer.draw(fullscreen, program);
+        });
+    }
+}
+
+// Image-based lighting from an HDR environment: the environment cubemap (for the skybox), its
+// irradiance (diffuse), the prefiltered mip chain (specular) and the BRDF lookup table. Generated
+// on the GPU when created
+pub struct Environment {
+    environment_map: Cubemap,
+    irradiance_map: Cubemap,
+    prefiltered_map: Cubemap,
+    brdf_lut: Rc<Texture>,
+    skybox_program: Program,
+    fullscreen: VertexArray,
+}
+
+impl Environment {
+    // Equirectangular (latitude-longitude) image, for example, a .hdr file
+    pub fn load_hdr(renderer: &Renderer, path: impl AsRef<Path>) -> image::ImageResult<Self> {
+        let image = image::open(path)?.into_rgba32f();
+        let equirectangular = Texture::from_hdr_image(
+            "uEquirectangularMap",
+            &image,
+            0,
+            &TextureSampler {
+                min_filter: TextureFilter::Linear,
+                mag_filter: TextureFilter::Linear,
+                wrap_s: TextureWrap::Repeat,
+                wrap_t: TextureWrap::ClampToEdge,
+            },
+        );
+        Ok(Self::from_equirectangular(renderer, &equirectangular))
+    }
+
+    // The texture is only read while creating the environment
+    pub fn from_equirectangular(renderer: &Renderer, equirectangular: &Texture) -> Self {
+        // Filters across the faces, otherwise the seams show on rough surfaces. Context state
+        unsafe { gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS) };
+
+        let render_state = renderer.get_render_state();
+        renderer.set_render_state(&RenderState::gl_initial());
+        let fullscreen = new_fullscreen_triangle();
+
+        let environment_map = Cubemap::new_empty(
+            "uEnvironmentMap",
+            ENVIRONMENT_MAP_SIZE,
+            TextureFormat::Rgba16F,
+            0,
+            Cubemap::get_max_mip_levels(ENVIRONMENT_MAP_SIZE),
+        );
+        let program = new_capture_program(EQUIRECTANGULAR_FRAGMENT_SHADER_SRC);
+        program.set_uniform_1i(
+            equirectangular.get_uniform_name(),
+            equirectangular.get_slot() as i32,
+        );
+        equirectangular.bind();
+        render_to_cubemap(renderer, &fullscreen, &program, &environment_map, 0);
+        equirectangular.unbind();
+        environment_map.generate_mipmaps();
+
+        environment_map.bind();
+
+        let irradiance_map = Cubemap::new_empty(
+            IRRADIANCE_MAP_UNIFORM,
+            IRRADIANCE_MAP_SIZE,
+            TextureFormat::Rgba16F,
+            IRRADIANCE_MAP_SLOT,
+            1,
+        );
+        let program = new_capture_program(IRRADIANCE_FRAGMENT_SHADER_SRC);
+        program.set_uniform_1i("uEnvironmentMap", environment_map.get_slot() as i32);
+        render_to_cubemap(renderer, &fullscreen, &program, &irradiance_map, 0);
+
+        let prefiltered_map = Cubemap::new_empty(
+            PREFILTERED_MAP_UNIFORM,
+            PREFILTERED_MAP_SIZE,
+            TextureFormat::Rgba16F,
+            PREFILTERED_MAP_SLOT,
+            PREFILTERED_MAP_MIP_LEVELS,
+        );
+        let program = new_capture_program(PREFILTER_FRAGMENT_SHADER_SRC);
+        program.set_uniform_1i("uEnvironmentMap", environment_map.get_slot() as i32);
+        program.set_uniform_1f("uEnvironmentSize", ENVIRONMENT_MAP_SIZE as f32);
+        for level in 0..PREFILTERED_MAP_MIP_LEVELS {
+            let roughness = level as f32 / (PREFILTERED_MAP_MIP_LEVELS - 1) as f32;
+            program.set_uniform_1f("uRoughness", roughness);
+            render_to_cubemap(renderer, &fullscreen, &program, &prefiltered_map, level);
+        }
+
+        environment_map.unbind();
+
+        let brdf_target = Framebuffer::new(
+            BRDF_LUT_SIZE,
+            BRDF_LUT_SIZE,
+            &[ColorAttachment::new(
+                BRDF_LUT_UNIFORM,
+                TextureFormat::Rgba16F,
+                BRDF_LUT_SLOT,
+            )],
+            DepthStencilAttachment::None,
+        );
+        let program = new_capture_program(BRDF_LUT_FRAGMENT_SHADER_SRC);
+        renderer.render_to(&brdf_target, || renderer.draw(&fullscreen, &program));
+
+        renderer.set_render_state(&render_state);
+
+        let vertex_shader = Shader::new(ShaderType::VertexShader, SKYBOX_VERTEX_SHADER_SRC);
+        let fragment_shader = Shader::new(ShaderType::FragmentShader, SKYBOX_FRAGMENT_SHADER_SRC);
+        let skybox_program = Program::new(&vertex_shader, &fragment_shader);
+        skybox_program.set_uniform_1i("uEnvironmentMap", environment_map.get_slot() as i32);
+
+        Self {
+            environment_map,
+            irradiance_map,
+            prefiltered_map,
+            brdf_lut: brdf_target.get_color_attachment(0),
+            skybox_program,
+            fullscreen,
+        }
+    }
+
+    // Binds the irradiance map, the prefiltered map and the BRDF lookup table to their slots.
+    // Call it before drawing with programs that use them, like pbr::new_program(true)
+    pub fn bind(&self) {
+        self.irradiance_map.bind();
+        self.prefiltered_map.bind();
+        self.brdf_lut.bind();
+    }
+
+    pub fn unbind(&self) {
+        self.irradiance_map.unbind();
+        self.prefiltered_map.unbind();
+        self.brdf_lut.unbind();
+    }
+
+    pub fn get_environment_map(&self) -> &Cubemap {
+        &self.environment_map
+    }
+
+    pub fn get_irradiance_map(&self) -> &Cubemap {
+        &self.irradiance_map
+    }
+
+    pub fn get_prefiltered_map(&self) -> &Cubemap {
+        &self.prefiltered_map
+    }
+
+    pub fn get_brdf_lut(&self) -> &Rc<Texture> {
+        &self.brdf_lut
+    }
+
+    // Fills the pixels that are still at the far plane. Draw it after the opaque geometry
+    pub fn draw_skybox(&self, renderer: &Renderer, camera: &Camera) {
+        let view_rotation = glm::mat3_to_mat4(&glm::mat4_to_mat3(&camera.get_view()));
+        let inverse_view_projection = glm::inverse(&(camera.get_projection() * view_rotation));
+        self.skybox_program
+            .set_uniform_mat4("uInverseViewProjection", &inverse_view_projection);
+
+        let render_state = renderer.get_render_state();
+        renderer.set_render_state(&RenderState {
+            depth: DepthState::read_only(),
+            ..RenderState::gl_initial()
+        });
+
+        self.environment_map.bind();
+        renderer.draw(&self.fullscreen, &self.skybox_program);
+        self.environment_map.unbind();
+
+        renderer.set_render_state(&render_state);
+    }
+}
@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+pub use glfw::{Key, Modifiers, MouseButton};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    Press,
+    Release,
+    // The key was held down until it repeated
+    Repeat,
+}
+
+impl From<glfw::Action> for KeyAction {
+    fn from(action: glfw::Action) -> Self {
+        match action {
+            glfw::Action::Press => KeyAction::Press,
+            glfw::Action::Release => KeyAction::Release,
+            glfw::Action::Repeat => KeyAction::Repeat,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Key {
+        key: Key,
+        action: KeyAction,
+        modifiers: Modifiers,
+    },
+    MouseButton {
+        button: MouseButton,
+        pressed: bool,
+        modifiers: Modifiers,
+    },
+    // Position in screen coordinates, relative to the top left corner of the window
+    CursorPosition {
+        x: f64,
+        y: f64,
+    },
+    Scroll {
+        x: f64,
+        y: f64,
+    },
+    // Unicode character typed, use it for text input instead of key events
+    Text(char),
+    FileDrop(Vec<PathBuf>),
+    Focus(bool),
+    // New framebuffer size in pixels
+    Resize {
+        width: u32,
+        height: u32,
+    },
+    Close,
+}
+
+impl Event {
+    pub(crate) fn from_glfw(event: glfw::WindowEvent) -> Option<Self> {
+        let event = match event {
+            glfw::WindowEvent::Key(key, _, action, modifiers) => Event::Key {
+                key,
+                action: action.into(),
+                modifiers,
+            },
+            glfw::WindowEvent::MouseButton(button, action, modifiers) => Event::MouseButton {
+                button,
+                pressed: action != glfw::Action::Release,
+                modifiers,
+            },
+            glfw::WindowEvent::CursorPos(x, y) => Event::CursorPosition { x, y },
+            glfw::WindowEvent::Scroll(x, y) => Event::Scroll { x, y },
+            glfw::WindowEvent::Char(character) => Event::Text(character),
+            glfw::WindowEvent::FileDrop(paths) => Event::FileDrop(paths),
+            glfw::WindowEvent::Focus(focused) => Event::Focus(focused),
+            glfw::WindowEvent::FramebufferSize(width, height) => Event::Resize {
+                width: width as u32,
+                height: height as u32,
+            },
+            glfw::WindowEvent::Close => Event::Close,
+            _ => return None,
+        };
+        Some(event)
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Number of frames used for the FPS and frame time statistics
+const NUM_SAMPLES: usize = 240;
+
+// Longest delta accepted by the fixed timestep accumulator. Longer frames (breakpoints, window
+// dragging...) would otherwise need more steps than a frame can run
+const MAX_DELTA: f64 = 0.25;
+
+// Times in seconds, as f64 so that the time keeps its precision in long-running applications
+pub struct FrameClock {
+    start: Instant,
+    last_frame: Instant,
+    time: f64,
+    delta: f64,
+    fixed_timestep: f64,
+    accumulator: f64,
+    target_fps: Option<f64>,
+    frame_times: VecDeque<f64>,
+    frame_count: u64,
+}
+
+impl Default for FrameClock {
+    // Fixed timestep of 60 updates per second, no frame rate limit
+    fn default() -> Self {
+        Self::new(1.0 / 60.0)
+    }
+}
+
+impl FrameClock {
+    pub fn new(fixed_timestep: f64) -> Self {
+        assert!(fixed_timestep > 0.0);
+
+        let now = Instant::now();
+        Self {
+            start: now,
+            last_frame: now,
+            time: 0.0,
+            delta: 0.0,
+            fixed_timestep,
+            accumulator: 0.0,
+            target_fps: None,
+            frame_times: VecDeque::with_capacity(NUM_SAMPLES),
+            frame_count: 0,
+        }
+    }
+
+    // Starts a new frame. Returns the seconds since the previous frame
+    pub fn tick(&mut self) -> f64 {
+        let now = Instant::now();
+        self.delta = now.duration_since(self.last_frame).as_secs_f64();
+        self.time = now.duration_since(self.start).as_secs_f64();
+        self.last_frame = now;
+
+        self.accumulator += self.delta.min(MAX_DELTA);
+
+        if self.frame_times.len() == NUM_SAMPLES {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(self.delta);
+        self.frame_count += 1;
+
+        self.delta
+    }
+
+    // Makes the next frame start now without recording the elapsed time, for example, to skip
+    // the setup before the first frame
+    pub fn reset(&mut self) {
+        self.last_frame = Instant::now();
+    }
+
+    // Consumes one fixed timestep of the accumulated time. Call it in a loop each frame:
+    // while clock.step_fixed() { simulate(clock.get_fixed_timestep()) }
+    pub fn step_fixed(&mut self) -> bool {
+        match self.accumulator >= self.fixed_timestep {
+            true => {
+                self.accumulator -= self.fixed_timestep;
+                true
+            }
+            false => false,
+        }
+    }
+
+    // How far the current frame is between the last two fixed steps, in [0, 1). Use it to
+    // interpolate the rendered state
+    pub fn get_alpha(&self) -> f64 {
+        self.accumulator / self.fixed_timestep
+    }
+
+    // Sleeps until the next frame should start. Does nothing without a target FPS, vsync already
+    // limits the frame rate
+    pub fn limit_frame_rate(&self) {
+        if let Some(target_fps) = self.target_fps {
+            let next_frame = self.last_frame + Duration::from_secs_f64(1.0 / target_fps);
+            let now = Instant::now();
+            if next_frame > now {
+                std::thread::sleep(next_frame - now);
+            }
+        }
+    }
+
+    pub fn set_target_fps(&mut self, target_fps: Option<f64>) {
+        if let Some(fps) = target_fps {
+            assert!(fps > 0.0);
+        }
+        self.target_fps = target_fps;
+    }
+
+    pub fn get_target_fps(&self) -> Option<f64> {
+        self.target_fps
+    }
+
+    pub fn set_fixed_timestep(&mut self, fixed_timestep: f64) {
+        assert!(fixed_timestep > 0.0);
+        self.fixed_timestep = fixed_timestep;
+    }
+
+    pub fn get_fixed_timestep(&self) -> f64 {
+        self.fixed_timestep
+    }
+
+    // Seconds since the clock was created, at the start of the current frame
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn get_delta(&self) -> f64 {
+        self.delta
+    }
+
+    pub fn get_frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    // Average over the last frames
+    pub fn get_fps(&self) -> f64 {
+        match self.get_average_frame_time() {
+            0.0 => 0.0,
+            average => 1.0 / average,
+        }
+    }
+
+    pub fn get_average_frame_time(&self) -> f64 {
+        match self.frame_times.len() {
+            0 => 0.0,
+            len => self.frame_times.iter().sum::<f64>() / len as f64,
+        }
+    }
+
+    // Frame time under which the given percentage of the last frames are, for example, 99.0
+    // gives the 1% slowest frames (stutter)
+    pub fn get_frame_time_percentile(&self, percentile: f64) -> f64 {
+        assert!((0.0..=100.0).contains(&percentile));
+
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+
+        let mut frame_times: Vec<f64> = self.frame_times.iter().copied().collect();
+        frame_times.sort_by(f64::total_cmp);
+        let index = (percentile / 100.0 * (frame_times.len() - 1) as f64).round() as usize;
+        frame_times[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock_with_frame_times(frame_times: &[f64]) -> FrameClock {
+        let mut clock = FrameClock::default();
+        clock.frame_times.extend(frame_times);
+        clock
+    }
+
+    #[test]
+    fn empty_stats() {
+        let clock = FrameClock::default();
+        assert_eq!(clock.get_average_frame_time(), 0.0);
+        assert_eq!(clock.get_fps(), 0.0);
+        assert_eq!(clock.get_frame_time_percentile(50.0), 0.0);
+    }
+
+    #[test]
+    fn average_and_fps() {
+        let clock = clock_with_frame_times(&[0.01, 0.02, 0.03, 0.04]);
+        assert!((clock.get_average_frame_time() - 0.025).abs() < 1e-12);
+        assert!((clock.get_fps() - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn percentiles() {
+        let clock = clock_with_frame_times(&[0.05, 0.01, 0.04, 0.02, 0.03]);
+        assert_eq!(clock.get_frame_time_percentile(0.0), 0.01);
+        assert_eq!(clock.get_frame_time_percentile(50.0), 0.03);
+        assert_eq!(clock.get_frame_time_percentile(100.0), 0.05);
+    }
+
+    #[test]
+    #[should_panic]
+    fn percentile_out_of_range() {
+        clock_with_frame_times(&[0.01]).get_frame_time_percentile(101.0);
+    }
+
+    #[test]
+    fn fixed_steps() {
+        let mut clock = FrameClock::new(0.1);
+        clock.accumulator = 0.25;
+
+        let mut steps = 0;
+        while clock.step_fixed() {
+            steps += 1;
+        }
+        assert_eq!(steps, 2);
+        assert!((clock.get_alpha() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tick_records_frame_times() {
+        let mut clock = FrameClock::default();
+        for _ in 0..NUM_SAMPLES + 10 {
+            clock.tick();
+        }
+        assert_eq!(clock.frame_times.len(), NUM_SAMPLES);
+        assert_eq!(clock.get_frame_count(), NUM_SAMPLES as u64 + 10);
+    }
+}
@@ -0,0 +1,313 @@
+use std::rc::Rc;
+
+use gl;
+
+use image::RgbaImage;
+
+use crate::readback;
+use crate::renderbuffer::Renderbuffer;
+use crate::texture::{Texture, TextureFormat};
+
+// Anything the Renderer can draw into
+pub trait RenderTarget {
+    fn get_framebuffer_id(&self) -> u32;
+    fn get_size(&self) -> (u32, u32);
+
+    // Samples per pixel, 0 when the target is not multisampled
+    fn get_samples(&self) -> u32 {
+        0
+    }
+}
+
+// The window. Its size must be provided because OpenGL doesn't track it
+#[derive(Debug, Clone, Copy)]
+pub struct DefaultFramebuffer {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl DefaultFramebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+
+    // Call it before swapping the buffers
+    pub fn read_pixels(&self) -> RgbaImage {
+        readback::read_pixels(self, 0)
+    }
+
+    pub fn save_screenshot(&self, path: &str) -> image::ImageResult<()> {
+        readback::save_screenshot(self, path)
+    }
+}
+
+impl RenderTarget for DefaultFramebuffer {
+    fn get_framebuffer_id(&self) -> u32 {
+        0
+    }
+
+    fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+// Colour attachment rendered into a texture that can be sampled later with the given uniform
+// name and slot
+pub struct ColorAttachment {
+    pub uniform_name: String,
+    pub format: TextureFormat,
+    pub slot: u32,
+}
+
+impl ColorAttachment {
+    pub fn new(uniform_name: &str, format: TextureFormat, slot: u32) -> Self {
+        Self {
+            uniform_name: String::from(uniform_name),
+            format,
+            slot,
+        }
+    }
+}
+
+pub enum DepthStencilAttachment {
+    None,
+    // Depth (and stencil, depending on the format) that can't be sampled
+    Renderbuffer(TextureFormat),
+    // Depth texture that can be sampled, for example, for shadow mapping
+    Texture(ColorAttachment),
+}
+
+enum DepthStencil {
+    Renderbuffer(Renderbuffer),
+    Texture(Rc<Texture>),
+}
+
+pub struct Framebuffer {
+    id: u32,
+    width: u32,
+    height: u32,
+    color_attachments: Vec<Rc<Texture>>,
+    depth_stencil: Option<DepthStencil>,
+}
+
+impl Framebuffer {
+    pub fn new(
+        width: u32,
+        height: u32,
+        color_attachments: &[ColorAttachment],
+        depth_stencil_attachment: DepthStencilAttachment,
+    ) -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut id);
+            assert_ne!(id, 0);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, id);
+        }
+
+        let color_attachments: Vec<Rc<Texture>> = color_attachments
+            .iter()
+            .enumerate()
+            .map(|(index, attachment)| {
+                assert!(!attachment.format.is_depth());
+                let texture = Texture::new_empty(
+                    &attachment.uniform_name,
+                    width,
+                    height,
+                    attachment.format,
+                    attachment.slot,
+                );
+                unsafe {
+                    gl::FramebufferTexture2D(
+                        gl::FRAMEBUFFER,
+                        gl::COLOR_ATTACHMENT0 + index as u32,
+                        gl::TEXTURE_2D,
+                        texture.get_id(),
+                        0,
+                    );
+                }
+                Rc::new(texture)
+            })
+            .collect();
+
+        let depth_stencil = match depth_stencil_attachment {
+            DepthStencilAttachment::None => None,
+            DepthStencilAttachment::Renderbuffer(format) => {
+                let renderbuffer = Renderbuffer::new(width, height, format);
+                unsafe {
+                    gl::FramebufferRenderbuffer(
+                        gl::FRAMEBUFFER,
+                        Self::depth_attachment_point(format),
+                        gl::RENDERBUFFER,
+                        renderbuffer.get_id(),
+                    );
+                }
+                Some(DepthStencil::Renderbuffer(renderbuffer))
+            }
+            DepthStencilAttachment::Texture(attachment) => {
+                let texture = Texture::new_empty(
+                    &attachment.uniform_name,
+                    width,
+                    height,
+                    attachment.format,
+                    attachment.slot,
+                );
+                unsafe {
+                    gl::FramebufferTexture2D(
+                        gl::FRAMEBUFFER,
+                        Self::depth_attachment_point(attachment.format),
+                        gl::TEXTURE_2D,
+                        texture.get_id(),
+                        0,
+                    );
+                }
+                Some(DepthStencil::Texture(Rc::new(texture)))
+            }
+        };
+
+        // Fragment shader output N is written to colour attachment N. Depth-only framebuffers
+        // don't write colour at all
+        let draw_buffers: Vec<u32> = (0..color_attachments.len() as u32)
+            .map(|index| gl::COLOR_ATTACHMENT0 + index)
+            .collect();
+        unsafe {
+            match draw_buffers.is_empty() {
+                true => {
+                    gl::DrawBuffer(gl::NONE);
+                    gl::ReadBuffer(gl::NONE);
+                }
+                false => gl::DrawBuffers(draw_buffers.len() as i32, draw_buffers.as_ptr()),
+            }
+        }
+
+        Self::check_status();
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0) };
+
+        Self {
+            id,
+            width,
+            height,
+            color_attachments,
+            depth_stencil,
+        }
+    }
+
+    fn depth_attachment_point(format: TextureFormat) -> u32 {
+        assert!(format.is_depth());
+        match format.has_stencil() {
+            true => gl::DEPTH_STENCIL_ATTACHMENT,
+            false => gl::DEPTH_ATTACHMENT,
+        }
+    }
+
+    // Panics with a readable message if the bound framebuffer can't be rendered to
+    pub(crate) fn check_status() {
+        let status = unsafe { gl::CheckFramebufferStatus(gl::FRAMEBUFFER) };
+        let error = match status {
+            gl::FRAMEBUFFER_COMPLETE => return,
+            gl::FRAMEBUFFER_UNDEFINED => "the default framebuffer doesn't exist",
+            gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => {
+                "an attachment is incomplete, check its size and format"
+            }
+            gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => "the framebuffer has no attachments",
+            gl::FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER => {
+                "a draw buffer points to a missing colour attachment"
+            }
+            gl::FRAMEBUFFER_INCOMPLETE_READ_BUFFER => {
+                "the read buffer points to a missing colour attachment"
+            }
+            gl::FRAMEBUFFER_UNSUPPORTED => {
+                "the combination of attachment formats is not supported by the driver"
+            }
+            gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => {
+                "the attachments don't have the same number of samples"
+            }
+            gl::FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS => {
+                "layered and non-layered attachments are mixed"
+            }
+            _ => "unknown status",
+        };
+        panic!("Framebuffer incomplete: {} (0x{:X})", error, status);
+    }
+
+    // Reallocates every attachment. Their content is lost, but textures shared with programs
+    // keep working. Zero sizes (a minimised window) are ignored, a framebuffer can't be empty
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if (width, height) == (self.width, self.height) || width == 0 || height == 0 {
+            return;
+        }
+
+        self.color_attachments
+            .iter()
+            .for_each(|texture| texture.resize(width, height));
+        match &self.depth_stencil {
+            Some(DepthStencil::Renderbuffer(renderbuffer)) => renderbuffer.resize(width, height),
+            Some(DepthStencil::Texture(texture)) => texture.resize(width, height),
+            None => {}
+        }
+
+        self.width = width;
+        self.height = height;
+
+        self.bind();
+        Self::check_status();
+        self.unbind();
+    }
+
+    pub fn get_color_attachment(&self, index: usize) -> Rc<Texture> {
+        Rc::clone(&self.color_attachments[index])
+    }
+
+    pub fn read_pixels(&self, attachment: u32) -> RgbaImage {
+        assert!((attachment as usize) < self.color_attachments.len());
+        readback::read_pixels(self, attachment)
+    }
+
+    pub fn get_num_color_attachments(&self) -> usize {
+        self.color_attachments.len()
+    }
+
+    pub fn has_depth(&self) -> bool {
+        self.depth_stencil.is_some()
+    }
+
+    // None when there is no depth attachment or when it is a renderbuffer
+    pub fn get_depth_texture(&self) -> Option<Rc<Texture>> {
+        match &self.depth_stencil {
+            Some(DepthStencil::Texture(texture)) => Some(Rc::clone(texture)),
+            _ => None,
+        }
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn bind(&self) {
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, self.id) };
+    }
+
+    // Binds the default framebuffer (the window)
+    pub fn unbind(&self) {
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0) };
+    }
+}
+
+impl RenderTarget for Framebuffer {
+    fn get_framebuffer_id(&self) -> u32 {
+        self.id
+    }
+
+    fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteFramebuffers(1, &self.id) };
+    }
+}
@@ -0,0 +1,531 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use bytemuck::Zeroable;
+
+use nalgebra_glm as glm;
+
+use crate::animation::{Animation, AnimationChannel, AnimationProperty, Interpolation, Skin};
+use crate::mesh::{Mesh, MeshVertex, SkinVertex, Submesh};
+use crate::scene::{NodeId, Scene, SceneNode};
+use crate::texture::{Texture, TextureFilter, TextureFormat, TextureSampler, TextureWrap};
+use crate::transform::Transform;
+
+// Texture units and sampler names of the material textures, the ones of the standard PBR program
+pub use crate::pbr::{
+    BASE_COLOR_MAP_SLOT, BASE_COLOR_MAP_UNIFORM, EMISSIVE_MAP_SLOT, EMISSIVE_MAP_UNIFORM,
+    METALLIC_ROUGHNESS_MAP_SLOT, METALLIC_ROUGHNESS_MAP_UNIFORM, NORMAL_MAP_SLOT,
+    NORMAL_MAP_UNIFORM, OCCLUSION_MAP_SLOT, OCCLUSION_MAP_UNIFORM,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlphaMode {
+    Opaque,
+    // Fragments with an alpha below the cutoff are discarded
+    Mask(f32),
+    Blend,
+}
+
+// PBR metallic-roughness material. The metallic-roughness map stores roughness in G and
+// metalness in B
+pub struct GltfMaterial {
+    pub name: String,
+    pub base_color_factor: glm::Vec4,
+    pub base_color_map: Option<Rc<Texture>>,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub metallic_roughness_map: Option<Rc<Texture>>,
+    pub normal_map: Option<Rc<Texture>>,
+    pub normal_scale: f32,
+    pub occlusion_map: Option<Rc<Texture>>,
+    pub occlusion_strength: f32,
+    pub emissive_factor: glm::Vec3,
+    pub emissive_map: Option<Rc<Texture>>,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+}
+
+impl Default for GltfMaterial {
+    // Default material of the specification, for primitives without material
+    fn default() -> Self {
+        Self {
+            name: String::from("default"),
+            base_color_factor: glm::vec4(1.0, 1.0, 1.0, 1.0),
+            base_color_map: None,
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+            metallic_roughness_map: None,
+            normal_map: None,
+            normal_scale: 1.0,
+            occlusion_map: None,
+            occlusion_strength: 1.0,
+            emissive_factor: glm::Vec3::zeros(),
+            emissive_map: None,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+        }
+    }
+}
+
+// The triangle primitives of a glTF mesh are submeshes, their material slots index
+// GltfModel::materials. Skinned meshes have skin vertices
+pub struct GltfMesh {
+    pub name: String,
+    pub mesh: Rc<Mesh>,
+}
+
+pub struct GltfModel {
+    // Nodes of the default scene, with the meshes attached. Set the materials to draw them
+    pub scene: Scene,
+    // Node of the Scene for each glTF node, None for nodes outside the default scene
+    pub nodes: Vec<Option<NodeId>>,
+    // None for meshes without triangles
+    pub meshes: Vec<Option<GltfMesh>>,
+    // The last material is the default one
+    pub materials: Vec<GltfMaterial>,
+    // Also set on the nodes with a skinned mesh. None for skins with joints outside the default
+    // scene
+    pub skins: Vec<Option<Rc<Skin>>>,
+    pub animations: Vec<Animation>,
+}
+
+impl GltfModel {
+    // Loads a .gltf (with external or base64 buffers and images) or a .glb file. Requires an
+    // OpenGL context for the meshes and textures
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, gltf::Error> {
+        let (document, buffers, images) = gltf::import(path)?;
+        let get_buffer = |buffer: gltf::Buffer| Some(&*buffers[buffer.index()]);
+
+        let mut textures = TextureCache {
+            images: &images,
+            textures: HashMap::new(),
+        };
+        let mut materials: Vec<GltfMaterial> = document
+            .materials()
+            .map(|material| load_material(&material, &mut textures))
+            .collect();
+        materials.push(GltfMaterial::default());
+        let default_material = materials.len() - 1;
+
+        let meshes: Vec<Option<GltfMesh>> = document
+            .meshes()
+            .map(|mesh| load_mesh(&mesh, &buffers, default_material))
+            .collect();
+
+        let mut scene = Scene::new();
+        let mut nodes = vec![None; document.nodes().len()];
+        let roots: Vec<gltf::Node> = match document.default_scene().or(document.scenes().next()) {
+            Some(gltf_scene) => gltf_scene.nodes().collect(),
+            None => Vec::new(),
+        };
+        for root in roots {
+            add_node(&root, None, &meshes, &mut scene, &mut nodes);
+        }
+        let node_id = |node: gltf::Node| nodes[node.index()];
+
+        let skins: Vec<Option<Rc<Skin>>> = document
+            .skins()
+            .map(|skin| {
+                let joints: Vec<NodeId> = skin.joints().map(node_id).collect::<Option<_>>()?;
+                // Identity matrices when not specified
+                let inverse_bind_matrices =
+                    match skin.reader(get_buffer).read_inverse_bind_matrices() {
+                        Some(matrices) => matrices.map(glm::Mat4::from).collect(),
+                        None => vec![glm::Mat4::identity(); joints.len()],
+                    };
+                Some(Rc::new(Skin {
+                    name: skin.name().unwrap_or_default().to_string(),
+                    joints,
+                    inverse_bind_matrices,
+                }))
+            })
+            .collect();
+
+        for node in document.nodes() {
+            if let (Some(id), Some(skin)) = (node_id(node.clone()), node.skin()) {
+                scene.get_node_mut(id).skin = skins[skin.index()].clone();
+            }
+        }
+
+        let animations = document
+            .animations()
+            .map(|animation| Animation {
+                name: animation.name().unwrap_or_default().to_string(),
+                channels: animation
+                    .channels()
+                    .filter_map(|channel| load_channel(&channel, &buffers, &nodes))
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Self {
+            scene,
+            nodes,
+            meshes,
+            materials,
+            skins,
+            animations,
+        })
+    }
+}
+
+fn add_node(
+    node: &gltf::Node,
+    parent: Option<NodeId>,
+    meshes: &[Option<GltfMesh>],
+    scene: &mut Scene,
+    nodes: &mut [Option<NodeId>],
+) {
+    let (translation, [x, y, z, w], scale) = node.transform().decomposed();
+    let transform = Transform::new(
+        glm::Vec3::from(translation),
+        glm::quat(x, y, z, w),
+        glm::Vec3::from(scale),
+    );
+
+    let name = match node.name() {
+        Some(name) => name.to_string(),
+        None => format!("node {}", node.index()),
+    };
+    let mut scene_node = SceneNode::new(&name, transform);
+    scene_node.mesh = node
+        .mesh()
+        .and_then(|mesh| meshes[mesh.index()].as_ref())
+        .map(|mesh| mesh.mesh.clone());
+
+    let id = match parent {
+        Some(parent) => scene.add_child(parent, scene_node),
+        None => scene.add_node(scene_node),
+    };
+    nodes[node.index()] = Some(id);
+
+    for child in node.children() {
+        add_node(&child, Some(id), meshes, scene, nodes);
+    }
+}
+
+fn load_mesh(
+    mesh: &gltf::Mesh,
+    buffers: &[gltf::buffer::Data],
+    default_material: usize,
+) -> Option<GltfMesh> {
+    let get_buffer = |buffer: gltf::Buffer| Some(&*buffers[buffer.index()]);
+    let mut vertices: Vec<MeshVertex> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    let mut submeshes: Vec<Submesh> = Vec::new();
+    let mut skin_vertices: Vec<SkinVertex> = Vec::new();
+    let mut has_normals = true;
+    let mut has_tangents = true;
+    let mut skinned = false;
+
+    // Points and lines are not supported
+    let primitives = mesh
+        .primitives()
+        .filter(|primitive| primitive.mode() == gltf::mesh::Mode::Triangles);
+
+    for primitive in primitives {
+        let reader = primitive.reader(get_buffer);
+        let Some(positions) = reader.read_positions() else {
+            continue;
+        };
+
+        let first_vertex = vertices.len();
+        vertices.extend(positions.map(|p| MeshVertex::new(glm::Vec3::from(p), glm::Vec2::zeros())));
+        let primitive_vertices = &mut vertices[first_vertex..];
+
+        match reader.read_normals() {
+            Some(normals) => primitive_vertices
+                .iter_mut()
+                .zip(normals)
+                .for_each(|(vertex, normal)| vertex.normal = glm::Vec3::from(normal)),
+            None => has_normals = false,
+        }
+        match reader.read_tangents() {
+            Some(tangents) => primitive_vertices
+                .iter_mut()
+                .zip(tangents)
+                .for_each(|(vertex, tangent)| vertex.tangent = glm::Vec4::from(tangent)),
+            None => has_tangents = false,
+        }
+        if let Some(tex_coords) = reader.read_tex_coords(0) {
+            primitive_vertices
+                .iter_mut()
+                .zip(tex_coords.into_f32())
+                .for_each(|(vertex, uv)| vertex.tex_coords = glm::Vec2::from(uv));
+        }
+
+        // Vertices of the unskinned primitives are bound to joint 0 with a weight of 0
+        let num_vertices = primitive_vertices.len();
+        match (reader.read_joints(0), reader.read_weights(0)) {
+            (Some(joints), Some(weights)) => {
+                skinned = true;
+                skin_vertices.extend(joints.into_u16().zip(weights.into_f32()).map(
+                    |(joints, weights)| SkinVertex {
+                        joints: joints.map(u32::from),
+                        weights,
+                    },
+                ));
+            }
+            _ => skin_vertices.extend(std::iter::repeat_n(SkinVertex::zeroed(), num_vertices)),
+        }
+
+        // Non-indexed primitives draw their vertices in order
+        let first_index = indices.len() as u32;
+        match reader.read_indices() {
+            Some(primitive_indices) => indices.extend(
+                primitive_indices
+                    .into_u32()
+                    .map(|index| first_vertex as u32 + index),
+            ),
+            None => indices.extend(first_vertex as u32..vertices.len() as u32),
+        }
+        submeshes.push(Submesh::new(
+            first_index,
+            indices.len() as u32 - first_index,
+            primitive.material().index().unwrap_or(default_material),
+        ));
+    }
+
+    // Points and lines only, or no positions
+    if indices.is_empty() {
+        return None;
+    }
+
+    let mut gpu_mesh = Mesh::with_submeshes(vertices, indices, submeshes);
+    // Generated for the whole mesh when a primitive doesn't provide them
+    if !has_normals {
+        gpu_mesh.compute_normals();
+    }
+    if !has_tangents {
+        gpu_mesh.compute_tangents();
+    }
+
+    if skinned {
+        gpu_mesh.set_skin_vertices(skin_vertices);
+    }
+
+    Some(GltfMesh {
+        name: mesh.name().unwrap_or_default().to_string(),
+        mesh: Rc::new(gpu_mesh),
+    })
+}
+
+// Creates each texture once per role: uniform name, slot and colour space are part of Texture
+struct TextureCache<'a> {
+    images: &'a [gltf::image::Data],
+    textures: HashMap<(usize, u32), Rc<Texture>>,
+}
+
+impl TextureCache<'_> {
+    fn get(
+        &mut self,
+        texture: gltf::Texture,
+        uniform_name: &str,
+        slot: u32,
+        format: TextureFormat,
+    ) -> Rc<Texture> {
+        let images = self.images;
+
+        self.textures
+            .entry((texture.index(), slot))
+            .or_insert_with(|| {
+                let image = to_rgba8(&images[texture.source().index()]);
+                let sampler = to_sampler(&texture.sampler());
+                Rc::new(Texture::from_image(
+                    uniform_name,
+                    &image,
+                    format,
+                    slot,
+                    &sampler,
+                ))
+            })
+            .clone()
+    }
+}
+
+fn load_material(material: &gltf::Material, textures: &mut TextureCache) -> GltfMaterial {
+    let pbr = material.pbr_metallic_roughness();
+
+    GltfMaterial {
+        name: material.name().unwrap_or_default().to_string(),
+        base_color_factor: glm::Vec4::from(pbr.base_color_factor()),
+        base_color_map: pbr.base_color_texture().map(|info| {
+            textures.get(
+                info.texture(),
+                BASE_COLOR_MAP_UNIFORM,
+                BASE_COLOR_MAP_SLOT,
+                TextureFormat::Srgb8Alpha8,
+            )
+        }),
+        metallic_factor: pbr.metallic_factor(),
+        roughness_factor: pbr.roughness_factor(),
+        metallic_roughness_map: pbr.metallic_roughness_texture().map(|info| {
+            textures.get(
+                info.texture(),
+                METALLIC_ROUGHNESS_MAP_UNIFORM,
+                METALLIC_ROUGHNESS_MAP_SLOT,
+                TextureFormat::Rgba8,
+            )
+        }),
+        normal_map: material.normal_texture().map(|normal| {
+            textures.get(
+                normal.texture(),
+                NORMAL_MAP_UNIFORM,
+                NORMAL_MAP_SLOT,
+                TextureFormat::Rgba8,
+            )
+        }),
+        normal_scale: material
+            .normal_texture()
+            .map_or(1.0, |normal| normal.scale()),
+        occlusion_map: material.occlusion_texture().map(|occlusion| {
+            textures.get(
+                occlusion.texture(),
+                OCCLUSION_MAP_UNIFORM,
+                OCCLUSION_MAP_SLOT,
+                TextureFormat::Rgba8,
+            )
+        }),
+        occlusion_strength: material
+            .occlusion_texture()
+            .map_or(1.0, |occlusion| occlusion.strength()),
+        emissive_factor: glm::Vec3::from(material.emissive_factor()),
+        emissive_map: material.emissive_texture().map(|info| {
+            textures.get(
+                info.texture(),
+                EMISSIVE_MAP_UNIFORM,
+                EMISSIVE_MAP_SLOT,
+                TextureFormat::Srgb8Alpha8,
+            )
+        }),
+        alpha_mode: match material.alpha_mode() {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            gltf::material::AlphaMode::Mask => {
+                AlphaMode::Mask(material.alpha_cutoff().unwrap_or(0.5))
+            }
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        },
+        double_sided: material.double_sided(),
+    }
+}
+
+fn to_sampler(sampler: &gltf::texture::Sampler) -> TextureSampler {
+    use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+
+    let wrap = |mode: WrappingMode| match mode {
+        WrappingMode::ClampToEdge => TextureWrap::ClampToEdge,
+        WrappingMode::MirroredRepeat => TextureWrap::MirroredRepeat,
+        WrappingMode::Repeat => TextureWrap::Repeat,
+    };
+    let default = TextureSampler::default();
+
+    TextureSampler {
+        min_filter: sampler
+            .min_filter()
+            .map_or(default.min_filter, |filter| match filter {
+                MinFilter::Nearest => TextureFilter::Nearest,
+                MinFilter::Linear => TextureFilter::Linear,
+                MinFilter::NearestMipmapNearest => TextureFilter::NearestMipmapNearest,
+                MinFilter::LinearMipmapNearest => TextureFilter::LinearMipmapNearest,
+                MinFilter::NearestMipmapLinear => TextureFilter::NearestMipmapLinear,
+                MinFilter::LinearMipmapLinear => TextureFilter::LinearMipmapLinear,
+            }),
+        mag_filter: sampler
+            .mag_filter()
+            .map_or(default.mag_filter, |filter| match filter {
+                MagFilter::Nearest => TextureFilter::Nearest,
+                MagFilter::Linear => TextureFilter::Linear,
+            }),
+        wrap_s: wrap(sampler.wrap_s()),
+        wrap_t: wrap(sampler.wrap_t()),
+    }
+}
+
+// The images are decoded by gltf::import with the image crate, in their own pixel format
+fn to_rgba8(image: &gltf::image::Data) -> image::RgbaImage {
+    use gltf::image::Format;
+
+    // Bytes per channel and number of channels
+    let (channel_size, channels) = match image.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (1, 2),
+        Format::R8G8B8 => (1, 3),
+        Format::R8G8B8A8 => (1, 4),
+        Format::R16 => (2, 1),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (2, 3),
+        Format::R16G16B16A16 => (2, 4),
+        Format::R32G32B32FLOAT => (4, 3),
+        Format::R32G32B32A32FLOAT => (4, 4),
+    };
+    let to_u8 = |bytes: &[u8]| match channel_size {
+        1 => bytes[0],
+        2 => (u16::from_le_bytes([bytes[0], bytes[1]]) >> 8) as u8,
+        _ => {
+            let value = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            (value.clamp(0.0, 1.0) * 255.0).round() as u8
+        }
+    };
+
+    let pixels: Vec<u8> = image
+        .pixels
+        .chunks_exact(channel_size * channels)
+        .flat_map(|pixel| {
+            let channel = |i: usize| to_u8(&pixel[i * channel_size..]);
+            match channels {
+                // Grey
+                1 => [channel(0), channel(0), channel(0), 255],
+                2 => [channel(0), channel(1), 0, 255],
+                3 => [channel(0), channel(1), channel(2), 255],
+                _ => [channel(0), channel(1), channel(2), channel(3)],
+            }
+        })
+        .collect();
+
+    image::RgbaImage::from_raw(image.width, image.height, pixels).expect("Invalid image size")
+}
+
+fn load_channel(
+    channel: &gltf::animation::Channel,
+    buffers: &[gltf::buffer::Data],
+    nodes: &[Option<NodeId>],
+) -> Option<AnimationChannel> {
+    use gltf::animation::util::ReadOutputs;
+
+    let get_buffer = |buffer: gltf::Buffer| Some(&*buffers[buffer.index()]);
+    let node = nodes[channel.target().node().index()]?;
+    let reader = channel.reader(get_buffer);
+    let times: Vec<f32> = reader.read_inputs()?.collect();
+
+    let (property, values): (AnimationProperty, Vec<glm::Vec4>) = match reader.read_outputs()? {
+        ReadOutputs::Translations(translations) => (
+            AnimationProperty::Translation,
+            translations.map(|t| glm::Vec3::from(t).push(0.0)).collect(),
+        ),
+        ReadOutputs::Rotations(rotations) => (
+            AnimationProperty::Rotation,
+            rotations.into_f32().map(glm::Vec4::from).collect(),
+        ),
+        ReadOutputs::Scales(scales) => (
+            AnimationProperty::Scale,
+            scales.map(|s| glm::Vec3::from(s).push(0.0)).collect(),
+        ),
+        // Morph targets are not supported
+        ReadOutputs::MorphTargetWeights(_) => return None,
+    };
+
+    let interpolation = match channel.sampler().interpolation() {
+        gltf::animation::Interpolation::Step => Interpolation::Step,
+        gltf::animation::Interpolation::Linear => Interpolation::Linear,
+        gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+    };
+
+    Some(AnimationChannel {
+        node,
+        property,
+        interpolation,
+        times,
+        values,
+    })
+}
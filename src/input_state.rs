@@ -0,0 +1,84 @@
+use std::collections::HashSet;
+
+use crate::event::{Event, Key, KeyAction, MouseButton};
+
+// Keyboard and mouse state built from the window events
+#[derive(Default)]
+pub struct InputState {
+    keys_down: HashSet<Key>,
+    mouse_buttons_down: HashSet<MouseButton>,
+    cursor_position: Option<(f64, f64)>,
+    // Accumulated since the last frame
+    cursor_delta: (f64, f64),
+    scroll_delta: (f64, f64),
+}
+
+impl InputState {
+    pub fn is_key_down(&self, key: Key) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_down.contains(&button)
+    }
+
+    pub fn get_cursor_position(&self) -> (f64, f64) {
+        self.cursor_position.unwrap_or_default()
+    }
+
+    // Cursor movement during the last frame
+    pub fn get_cursor_delta(&self) -> (f64, f64) {
+        self.cursor_delta
+    }
+
+    // Scroll during the last frame
+    pub fn get_scroll_delta(&self) -> (f64, f64) {
+        self.scroll_delta
+    }
+
+    pub(crate) fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::Key { key, action, .. } => match action {
+                KeyAction::Press | KeyAction::Repeat => {
+                    self.keys_down.insert(key);
+                }
+                KeyAction::Release => {
+                    self.keys_down.remove(&key);
+                }
+            },
+            Event::MouseButton {
+                button, pressed, ..
+            } => match pressed {
+                true => {
+                    self.mouse_buttons_down.insert(button);
+                }
+                false => {
+                    self.mouse_buttons_down.remove(&button);
+                }
+            },
+            Event::CursorPosition { x, y } => {
+                // The first position is not a movement
+                if let Some((previous_x, previous_y)) = self.cursor_position {
+                    self.cursor_delta.0 += x - previous_x;
+                    self.cursor_delta.1 += y - previous_y;
+                }
+                self.cursor_position = Some((x, y));
+            }
+            Event::Scroll { x, y } => {
+                self.scroll_delta.0 += x;
+                self.scroll_delta.1 += y;
+            }
+            // Releases are not received while the window is not focused
+            Event::Focus(false) => {
+                self.keys_down.clear();
+                self.mouse_buttons_down.clear();
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn end_frame(&mut self) {
+        self.cursor_delta = (0.0, 0.0);
+        self.scroll_delta = (0.0, 0.0);
+    }
+}
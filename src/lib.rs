@@ -1,9 +1,40 @@
+pub mod animation;
+pub mod application;
+pub mod bounding_volume;
+pub mod buffer;
+pub mod camera;
+pub mod clear_values;
+pub mod cubemap;
+pub mod draw_command;
 pub mod element_buffer;
+pub mod environment;
+pub mod event;
+pub mod frame_clock;
+pub mod framebuffer;
+pub mod gltf_loader;
+pub mod input_state;
+pub mod light;
+pub mod material;
+pub mod mesh;
+pub mod multisample_framebuffer;
+pub mod obj_loader;
+pub mod pbr;
+pub mod phong;
+pub mod post_processing;
+pub mod primitives;
 pub mod program;
+pub mod readback;
+pub mod render_state;
+pub mod renderbuffer;
 pub mod renderer;
+pub mod scene;
 pub mod shader;
+pub mod shadow;
 pub mod texture;
+pub mod transform;
+pub mod vertex;
 pub mod vertex_array;
 pub mod vertex_buffer;
 pub mod vertex_buffer_layout;
 pub mod window;
+pub mod window_builder;
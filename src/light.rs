@@ -0,0 +1,573 @@
+use bytemuck::{Pod, Zeroable};
+use nalgebra_glm as glm;
+
+use crate::animation::skinning_shader_src;
+use crate::buffer::{Buffer, BufferTarget, BufferUsage};
+use crate::program::Program;
+use crate::shader::{Shader, ShaderType};
+use crate::shadow::{
+    CASCADE_SHADOW_MAP_SLOT, CASCADE_SHADOW_MAP_UNIFORM, POINT_SHADOW_MAP_SLOT,
+    POINT_SHADOW_MAP_UNIFORM, SPOT_SHADOW_MAP_SLOT, SPOT_SHADOW_MAP_UNIFORM,
+};
+
+pub const MAX_DIRECTIONAL_LIGHTS: usize = 4;
+pub const MAX_POINT_LIGHTS: usize = 16;
+pub const MAX_SPOT_LIGHTS: usize = 8;
+pub const MAX_SHADOW_CASCADES: usize = 4;
+
+// The cascade splits are stored in a vec4
+const _: () = assert!(MAX_SHADOW_CASCADES <= 4);
+
+// Uniform buffer binding point of the Lights block
+pub const LIGHTS_BLOCK_BINDING: u32 = 0;
+
+// Lights block written by Lights::update, the attenuation helpers and the shadow lookups. Goes
+// after the #version line of the shaders
+pub fn lights_shader_src() -> String {
+    format!(
+        "
+#define MAX_DIRECTIONAL_LIGHTS {MAX_DIRECTIONAL_LIGHTS}
+#define MAX_POINT_LIGHTS {MAX_POINT_LIGHTS}
+#define MAX_SPOT_LIGHTS {MAX_SPOT_LIGHTS}
+#define MAX_SHADOW_CASCADES {MAX_SHADOW_CASCADES}
+{LIGHTS_BLOCK_SRC}"
+    )
+}
+
+const LIGHTS_BLOCK_SRC: &str = "
+struct DirectionalLight {
+    vec4 direction;
+    vec4 color;
+};
+
+struct PointLight {
+    vec4 position;
+    vec4 color;
+    // Constant, linear and quadratic factors
+    vec4 attenuation;
+};
+
+struct SpotLight {
+    vec4 position;
+    vec4 direction;
+    vec4 color;
+    vec4 attenuation;
+    // Cosines of the inner and outer angles
+    vec4 cone;
+};
+
+layout (std140) uniform Lights {
+    vec4 uAmbientLight;
+    vec4 uViewPosition;
+    // Directional, point and spot lights
+    ivec4 uNumLights;
+    DirectionalLight uDirectionalLights[MAX_DIRECTIONAL_LIGHTS];
+    PointLight uPointLights[MAX_POINT_LIGHTS];
+    SpotLight uSpotLights[MAX_SPOT_LIGHTS];
+
+    // Written by ShadowMaps::render. Index of the directional, point and spot light casting
+    // shadows (-1 for none), and number of cascades
+    ivec4 uShadowLights;
+    // Depth bias, normal bias in world units and PCF radius in texels
+    vec4 uShadowParams;
+    // Camera forward direction, the view depth selects the cascade
+    vec4 uViewDirection;
+    // View depth where each cascade ends
+    vec4 uCascadeSplits;
+    // World space to shadow map coordinates and depth, inside the tile of the cascade
+    mat4 uCascadeMatrices[MAX_SHADOW_CASCADES];
+    // Position and far plane of the point and spot lights. Their shadow maps store the distance
+    // to the light divided by the far plane
+    vec4 uPointShadowPosition;
+    vec4 uSpotShadowPosition;
+    mat4 uSpotShadowMatrix;
+};
+
+uniform sampler2DShadow uCascadeShadowMap;
+uniform samplerCubeShadow uPointShadowMap;
+uniform sampler2DShadow uSpotShadowMap;
+
+float attenuate(vec4 attenuation, float distance) {
+    return 1.0 / (attenuation.x + attenuation.y * distance + attenuation.z * distance * distance);
+}
+
+// 1 inside the inner cone, 0 outside the outer one. lightDirection goes from the surface to
+// the light
+float spotCone(SpotLight light, vec3 lightDirection) {
+    float cosAngle = dot(-lightDirection, light.direction.xyz);
+    return smoothstep(light.cone.y, light.cone.x, cosAngle);
+}
+
+// Percentage-closer filtering: average of the comparisons around the coordinates, each one
+// filtered between texels by the hardware. The samples stay in the [minUv, maxUv] rectangle
+float filterShadow(sampler2DShadow shadowMap, vec3 coords, vec2 minUv, vec2 maxUv) {
+    vec2 texelSize = 1.0 / vec2(textureSize(shadowMap, 0));
+    int radius = int(uShadowParams.z);
+    float lit = 0.0;
+    for (int x = -radius; x <= radius; x++) {
+        for (int y = -radius; y <= radius; y++) {
+            vec2 uv = clamp(coords.xy + vec2(x, y) * texelSize, minUv, maxUv);
+            lit += texture(shadowMap, vec3(uv, coords.z));
+        }
+    }
+    return lit / float((2 * radius + 1) * (2 * radius + 1));
+}
+
+// The shadow functions return 1 when lit and 0 in the shadow of the light at the index. The
+// position is moved along the normal against shadow acne
+float directionalShadow(int light, vec3 worldPos, vec3 normal) {
+    int numCascades = uShadowLights.w;
+    float depth = dot(worldPos - uViewPosition.xyz, uViewDirection.xyz);
+    if (light != uShadowLights.x || depth > uCascadeSplits[numCascades - 1]) {
+        return 1.0;
+    }
+
+    int cascade = 0;
+    while (depth > uCascadeSplits[cascade]) {
+        cascade++;
+    }
+    vec4 position = vec4(worldPos + normal * uShadowParams.y, 1.0);
+    vec3 coords = (uCascadeMatrices[cascade] * position).xyz;
+
+    // The cascades are side by side in the shadow map
+    vec2 halfTexel = 0.5 / vec2(textureSize(uCascadeShadowMap, 0));
+    vec2 minUv = vec2(float(cascade) / float(numCascades), 0.0) + halfTexel;
+    vec2 maxUv = vec2(float(cascade + 1) / float(numCascades), 1.0) - halfTexel;
+    return filterShadow(uCascadeShadowMap, vec3(coords.xy, coords.z - uShadowParams.x), minUv,
+                        maxUv);
+}
+
+// Corners, then edge centres, of a cube
+const vec3 POINT_SHADOW_OFFSETS[20] = vec3[](
+    vec3(1, 1, 1), vec3(1, -1, 1), vec3(-1, -1, 1), vec3(-1, 1, 1),
+    vec3(1, 1, -1), vec3(1, -1, -1), vec3(-1, -1, -1), vec3(-1, 1, -1),
+    vec3(1, 1, 0), vec3(1, -1, 0), vec3(-1, -1, 0), vec3(-1, 1, 0),
+    vec3(1, 0, 1), vec3(-1, 0, 1), vec3(1, 0, -1), vec3(-1, 0, -1),
+    vec3(0, 1, 1), vec3(0, -1, 1), vec3(0, -1, -1), vec3(0, 1, -1)
+);
+
+float pointShadow(int light, vec3 worldPos, vec3 normal) {
+    if (light != uShadowLights.y) {
+        return 1.0;
+    }
+
+    vec3 toSurface = worldPos + normal * uShadowParams.y - uPointShadowPosition.xyz;
+    float distance = length(toSurface);
+    float depth = distance / uPointShadowPosition.w - uShadowParams.x;
+
+    float radius = uShadowParams.z;
+    if (radius == 0.0) {
+        return texture(uPointShadowMap, vec4(toSurface, depth));
+    }
+
+    // Fixed directions around the sample, radius texels apart, instead of a (2r+1)^3 kernel
+    float offset = radius * 2.0 * distance / float(textureSize(uPointShadowMap, 0).x);
+    float lit = 0.0;
+    for (int i = 0; i < 20; i++) {
+        vec3 direction = toSurface + POINT_SHADOW_OFFSETS[i] * offset;
+        lit += texture(uPointShadowMap, vec4(direction, depth));
+    }
+    return lit / 20.0;
+}
+
+float spotShadow(int light, vec3 worldPos, vec3 normal) {
+    if (light != uShadowLights.z) {
+        return 1.0;
+    }
+
+    vec3 position = worldPos + normal * uShadowParams.y;
+    vec4 coords = uSpotShadowMatrix * vec4(position, 1.0);
+    if (coords.w <= 0.0) {
+        return 1.0;
+    }
+    float depth = distance(position, uSpotShadowPosition.xyz) / uSpotShadowPosition.w
+        - uShadowParams.x;
+    return filterShadow(uSpotShadowMap, vec3(coords.xy / coords.w, depth), vec2(0.0), vec2(1.0));
+}
+";
+
+// Shared by the fragment shaders of the lit programs, after lights_shader_src(): the outputs of
+// the lit vertex shader, normal mapping and the sum over the lights. The programs define shade()
+const LIT_FRAGMENT_SHADER_SRC: &str = "
+in vec3 vWorldPos;
+in vec3 vNormal;
+in vec4 vTangent;
+in vec2 vTexCoord;
+
+uniform bool uHasNormalMap;
+uniform sampler2D uNormalMap;
+
+// Phong uses color and specular as the diffuse and specular colours, PBR uses color as the
+// albedo and specular as the reflectance at normal incidence (f0)
+struct Surface {
+    vec3 position;
+    vec3 normal;
+    // From the surface to the camera
+    vec3 view;
+    vec3 color;
+    vec3 specular;
+    float metallic;
+    float roughness;
+};
+
+// Light reflected towards the camera. lightDirection goes from the surface to the light
+vec3 shade(Surface surface, vec3 lightDirection, vec3 radiance);
+
+vec3 getNormal(float normalScale) {
+    vec3 normal = normalize(vNormal);
+    if (uHasNormalMap) {
+        // Tangent space to world space, the w of the tangent is the handedness of the bitangent
+        vec3 tangent = normalize(vTangent.xyz - normal * dot(normal, vTangent.xyz));
+        vec3 bitangent = cross(normal, tangent) * vTangent.w;
+        vec3 tangentNormal = texture(uNormalMap, vTexCoord).xyz * 2.0 - 1.0;
+        tangentNormal.xy *= normalScale;
+        normal = normalize(mat3(tangent, bitangent, normal) * tangentNormal);
+    }
+    return gl_FrontFacing ? normal : -normal;
+}
+
+// Direct lighting of the directional, point and spot lights, with their shadows
+vec3 shadeLights(Surface surface) {
+    vec3 color = vec3(0.0);
+    for (int i = 0; i < uNumLights.x; i++) {
+        DirectionalLight light = uDirectionalLights[i];
+        vec3 radiance = light.color.rgb * directionalShadow(i, surface.position, surface.normal);
+        color += shade(surface, -light.direction.xyz, radiance);
+    }
+    for (int i = 0; i < uNumLights.y; i++) {
+        PointLight light = uPointLights[i];
+        vec3 toLight = light.position.xyz - surface.position;
+        float distance = length(toLight);
+        vec3 radiance = light.color.rgb * attenuate(light.attenuation, distance)
+            * pointShadow(i, surface.position, surface.normal);
+        color += shade(surface, toLight / distance, radiance);
+    }
+    for (int i = 0; i < uNumLights.z; i++) {
+        SpotLight light = uSpotLights[i];
+        vec3 toLight = light.position.xyz - surface.position;
+        float distance = length(toLight);
+        vec3 lightDirection = toLight / distance;
+        vec3 radiance = light.color.rgb * attenuate(light.attenuation, distance)
+            * spotCone(light, lightDirection) * spotShadow(i, surface.position, surface.normal);
+        color += shade(surface, lightDirection, radiance);
+    }
+    return color;
+}
+";
+
+// Vertex shader of the lit programs (phong, pbr), takes the MeshVertex attributes, and the
+// SkinVertex ones for skinned meshes. The normal matrix is computed per vertex, so non-uniform
+// scales don't need an extra uniform
+fn lit_vertex_shader_src() -> String {
+    format!(
+        "#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = 2) in vec2 aTexCoord;
+layout (location = 3) in vec4 aTangent;
+
+out vec3 vWorldPos;
+out vec3 vNormal;
+out vec4 vTangent;
+out vec2 vTexCoord;
+
+uniform mat4 uViewProjection;
+uniform mat4 uModel;
+
+{}
+void main() {{
+    mat4 model = uModel * getSkinMatrix();
+    vec4 worldPos = model * vec4(aPos, 1.0);
+    gl_Position = uViewProjection * worldPos;
+    vWorldPos = worldPos.xyz;
+    vNormal = transpose(inverse(mat3(model))) * aNormal;
+    vTangent = vec4(mat3(model) * aTangent.xyz, aTangent.w);
+    vTexCoord = aTexCoord;
+}}",
+        skinning_shader_src()
+    )
+}
+
+// Program of the lit vertex shader and a fragment shader that defines shade() and calls
+// shadeLights(). defines go after the #version line
+pub(crate) fn new_lit_program(defines: &str, fragment_shader_src: &str) -> Program {
+    let vertex_shader = Shader::new(ShaderType::VertexShader, &lit_vertex_shader_src());
+    let fragment_shader = Shader::new(
+        ShaderType::FragmentShader,
+        &format!(
+            "#version 330 core\n{defines}\n{}\n{LIT_FRAGMENT_SHADER_SRC}\n{fragment_shader_src}",
+            lights_shader_src()
+        ),
+    );
+    let program = Program::new(&vertex_shader, &fragment_shader);
+    setup_lit_program(&program);
+    program
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Attenuation {
+    pub fn new(constant: f32, linear: f32, quadratic: f32) -> Self {
+        Self {
+            constant,
+            linear,
+            quadratic,
+        }
+    }
+
+    // Fit of the usual table of factors, the light is around 1% of its intensity at the range
+    pub fn from_range(range: f32) -> Self {
+        assert!(range > 0.0);
+        Self::new(1.0, 4.5 / range, 75.0 / (range * range))
+    }
+
+    pub fn get_factor(&self, distance: f32) -> f32 {
+        1.0 / (self.constant + self.linear * distance + self.quadratic * distance * distance)
+    }
+}
+
+// Infinitely far light, like the sun. The direction is the one the light travels in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    pub direction: glm::Vec3,
+    pub color: glm::Vec3,
+    pub intensity: f32,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: glm::Vec3) -> Self {
+        Self {
+            direction,
+            color: glm::vec3(1.0, 1.0, 1.0),
+            intensity: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointLight {
+    pub position: glm::Vec3,
+    pub color: glm::Vec3,
+    pub intensity: f32,
+    pub attenuation: Attenuation,
+}
+
+impl PointLight {
+    pub fn new(position: glm::Vec3, range: f32) -> Self {
+        Self {
+            position,
+            color: glm::vec3(1.0, 1.0, 1.0),
+            intensity: 1.0,
+            attenuation: Attenuation::from_range(range),
+        }
+    }
+}
+
+// Point light limited to a cone. The intensity fades from the inner to the outer angle, both
+// measured from the direction, in radians
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpotLight {
+    pub position: glm::Vec3,
+    pub direction: glm::Vec3,
+    pub color: glm::Vec3,
+    pub intensity: f32,
+    pub attenuation: Attenuation,
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: glm::Vec3,
+        direction: glm::Vec3,
+        range: f32,
+        inner_angle: f32,
+        outer_angle: f32,
+    ) -> Self {
+        assert!(inner_angle <= outer_angle);
+        Self {
+            position,
+            direction,
+            color: glm::vec3(1.0, 1.0, 1.0),
+            intensity: 1.0,
+            attenuation: Attenuation::from_range(range),
+            inner_angle,
+            outer_angle,
+        }
+    }
+}
+
+// std140 layout of the lights block, every member is a vec4 so there's no padding to add
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct DirectionalLightData {
+    direction: [f32; 4],
+    color: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct PointLightData {
+    position: [f32; 4],
+    color: [f32; 4],
+    attenuation: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct SpotLightData {
+    position: [f32; 4],
+    direction: [f32; 4],
+    color: [f32; 4],
+    attenuation: [f32; 4],
+    cone: [f32; 4],
+}
+
+// Shadow part of the lights block, filled by ShadowMaps::render
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub(crate) struct ShadowData {
+    pub(crate) shadow_lights: [i32; 4],
+    pub(crate) params: [f32; 4],
+    pub(crate) view_direction: [f32; 4],
+    pub(crate) cascade_splits: [f32; 4],
+    pub(crate) cascade_matrices: [glm::Mat4; MAX_SHADOW_CASCADES],
+    pub(crate) point_position: [f32; 4],
+    pub(crate) spot_position: [f32; 4],
+    pub(crate) spot_matrix: glm::Mat4,
+}
+
+impl ShadowData {
+    // No light casts shadows
+    pub(crate) fn none() -> Self {
+        Self {
+            shadow_lights: [-1, -1, -1, 0],
+            ..Self::zeroed()
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct LightsData {
+    ambient: [f32; 4],
+    view_position: [f32; 4],
+    num_lights: [i32; 4],
+    directional_lights: [DirectionalLightData; MAX_DIRECTIONAL_LIGHTS],
+    point_lights: [PointLightData; MAX_POINT_LIGHTS],
+    spot_lights: [SpotLightData; MAX_SPOT_LIGHTS],
+    shadows: ShadowData,
+}
+
+pub(crate) fn vec4(v: &glm::Vec3, w: f32) -> [f32; 4] {
+    [v.x, v.y, v.z, w]
+}
+
+fn attenuation_vec4(attenuation: &Attenuation) -> [f32; 4] {
+    [
+        attenuation.constant,
+        attenuation.linear,
+        attenuation.quadratic,
+        0.0,
+    ]
+}
+
+// Lights of a scene, shared by every program that declares the Lights block (see
+// lights_shader_src()) bound to LIGHTS_BLOCK_BINDING
+pub struct Lights {
+    // Multiplied by the ambient colour of the materials
+    pub ambient: glm::Vec3,
+    pub directional_lights: Vec<DirectionalLight>,
+    pub point_lights: Vec<PointLight>,
+    pub spot_lights: Vec<SpotLight>,
+    pub(crate) shadows: ShadowData,
+    buffer: Buffer<LightsData>,
+}
+
+impl Lights {
+    pub fn new() -> Self {
+        Self {
+            ambient: glm::vec3(1.0, 1.0, 1.0),
+            directional_lights: Vec::new(),
+            point_lights: Vec::new(),
+            spot_lights: Vec::new(),
+            shadows: ShadowData::none(),
+            buffer: Buffer::zeroed(BufferTarget::Uniform, 1, BufferUsage::DynamicDraw),
+        }
+    }
+
+    // Uploads the lights and binds the buffer to LIGHTS_BLOCK_BINDING. Call once per frame before
+    // drawing, after moving the lights or the camera, and after ShadowMaps::render
+    pub fn update(&self, view_position: &glm::Vec3) {
+        assert!(self.directional_lights.len() <= MAX_DIRECTIONAL_LIGHTS);
+        assert!(self.point_lights.len() <= MAX_POINT_LIGHTS);
+        assert!(self.spot_lights.len() <= MAX_SPOT_LIGHTS);
+        // One light of each type casts shadows, set by ShadowMaps::render
+        let [directional_shadow, point_shadow, spot_shadow, _] = self.shadows.shadow_lights;
+        assert!(directional_shadow < self.directional_lights.len() as i32);
+        assert!(point_shadow < self.point_lights.len() as i32);
+        assert!(spot_shadow < self.spot_lights.len() as i32);
+
+        let mut data = LightsData::zeroed();
+        data.ambient = vec4(&self.ambient, 1.0);
+        data.view_position = vec4(view_position, 1.0);
+        data.num_lights = [
+            self.directional_lights.len() as i32,
+            self.point_lights.len() as i32,
+            self.spot_lights.len() as i32,
+            0,
+        ];
+
+        for (data, light) in data
+            .directional_lights
+            .iter_mut()
+            .zip(&self.directional_lights)
+        {
+            *data = DirectionalLightData {
+                direction: vec4(&light.direction.normalize(), 0.0),
+                color: vec4(&(light.color * light.intensity), 1.0),
+            };
+        }
+        for (data, light) in data.point_lights.iter_mut().zip(&self.point_lights) {
+            *data = PointLightData {
+                position: vec4(&light.position, 1.0),
+                color: vec4(&(light.color * light.intensity), 1.0),
+                attenuation: attenuation_vec4(&light.attenuation),
+            };
+        }
+        for (data, light) in data.spot_lights.iter_mut().zip(&self.spot_lights) {
+            *data = SpotLightData {
+                position: vec4(&light.position, 1.0),
+                direction: vec4(&light.direction.normalize(), 0.0),
+                color: vec4(&(light.color * light.intensity), 1.0),
+                attenuation: attenuation_vec4(&light.attenuation),
+                cone: [light.inner_angle.cos(), light.outer_angle.cos(), 0.0, 0.0],
+            };
+        }
+
+        data.shadows = self.shadows;
+
+        self.buffer.update(0, &[data]);
+        self.buffer.bind_base(LIGHTS_BLOCK_BINDING);
+    }
+}
+
+// Binds the Lights block of a program that includes lights_shader_src() and points its shadow
+// samplers to the slots of ShadowMaps
+fn setup_lit_program(program: &Program) {
+    program.set_uniform_block_binding("Lights", LIGHTS_BLOCK_BINDING);
+    program.set_uniform_1i(CASCADE_SHADOW_MAP_UNIFORM, CASCADE_SHADOW_MAP_SLOT as i32);
+    program.set_uniform_1i(POINT_SHADOW_MAP_UNIFORM, POINT_SHADOW_MAP_SLOT as i32);
+    program.set_uniform_1i(SPOT_SHADOW_MAP_UNIFORM, SPOT_SHADOW_MAP_SLOT as i32);
+}
+
+impl Default for Lights {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,170 @@
+use std::rc::Rc;
+
+use gl;
+use nalgebra_glm as glm;
+
+use crate::program::Program;
+use crate::render_state::RenderState;
+use crate::texture::Texture;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UniformValue {
+    Int(i32),
+    Float(f32),
+    Vec2(glm::Vec2),
+    Vec3(glm::Vec3),
+    Vec4(glm::Vec4),
+    Mat4(glm::Mat4),
+}
+
+// Orders materials so that the opaque ones come first, then draws sharing a program, then the
+// same textures, are consecutive. Blended draws are sorted back to front by Renderer::draw_scene
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MaterialSortKey {
+    blended: bool,
+    program: u32,
+    textures: Vec<u32>,
+}
+
+impl MaterialSortKey {
+    pub fn is_blended(&self) -> bool {
+        self.blended
+    }
+}
+
+// Shared program with its own uniform values, textures and render state. Uniforms are uploaded
+// before every draw with the material, so several materials can use the same program
+pub struct Material {
+    program: Rc<Program>,
+    uniforms: Vec<(String, UniformValue)>,
+    textures: Vec<Rc<Texture>>,
+    pub render_state: RenderState,
+}
+
+impl Material {
+    // Opaque 3D render state: depth test and back-face culling
+    pub fn new(program: Rc<Program>) -> Self {
+        Self {
+            program,
+            uniforms: Vec::new(),
+            textures: Vec::new(),
+            render_state: RenderState::opaque_3d(),
+        }
+    }
+
+    pub fn get_program(&self) -> &Rc<Program> {
+        &self.program
+    }
+
+    // Uniforms not declared by the program, or removed by the compiler, are ignored
+    pub fn set_uniform(&mut self, uniform_name: &str, value: UniformValue) {
+        match self
+            .uniforms
+            .iter_mut()
+            .find(|(name, _)| name == uniform_name)
+        {
+            Some((_, current)) => *current = value,
+            None => self.uniforms.push((String::from(uniform_name), value)),
+        }
+    }
+
+    pub fn set_int(&mut self, uniform_name: &str, value: i32) {
+        self.set_uniform(uniform_name, UniformValue::Int(value));
+    }
+
+    pub fn set_float(&mut self, uniform_name: &str, value: f32) {
+        self.set_uniform(uniform_name, UniformValue::Float(value));
+    }
+
+    pub fn set_vec2(&mut self, uniform_name: &str, value: glm::Vec2) {
+        self.set_uniform(uniform_name, UniformValue::Vec2(value));
+    }
+
+    pub fn set_vec3(&mut self, uniform_name: &str, value: glm::Vec3) {
+        self.set_uniform(uniform_name, UniformValue::Vec3(value));
+    }
+
+    pub fn set_vec4(&mut self, uniform_name: &str, value: glm::Vec4) {
+        self.set_uniform(uniform_name, UniformValue::Vec4(value));
+    }
+
+    pub fn set_mat4(&mut self, uniform_name: &str, value: glm::Mat4) {
+        self.set_uniform(uniform_name, UniformValue::Mat4(value));
+    }
+
+    pub fn get_uniform(&self, uniform_name: &str) -> Option<UniformValue> {
+        self.uniforms
+            .iter()
+            .find(|(name, _)| name == uniform_name)
+            .map(|(_, value)| *value)
+    }
+
+    // The sampler uniform is the one of the texture. Replaces the texture using the same slot
+    pub fn set_texture(&mut self, texture: impl Into<Rc<Texture>>) {
+        let texture = texture.into();
+        self.textures
+            .retain(|current| current.get_slot() != texture.get_slot());
+        self.textures.push(texture);
+    }
+
+    pub fn get_textures(&self) -> &[Rc<Texture>] {
+        &self.textures
+    }
+
+    pub fn get_sort_key(&self) -> MaterialSortKey {
+        let mut textures: Vec<u32> = self.textures.iter().map(|t| t.get_id()).collect();
+        textures.sort();
+        MaterialSortKey {
+            blended: self.render_state.blend.enabled,
+            program: self.program.get_id(),
+            textures,
+        }
+    }
+
+    // Binds the program and the textures, and uploads the uniforms
+    pub(crate) fn bind(&self) {
+        self.bind_to(&self.program);
+    }
+
+    // Same with another program, for example, a depth-only one, which gets the uniforms and
+    // textures it declares
+    pub(crate) fn bind_to(&self, program: &Program) {
+        program.bind();
+        program.bind_textures();
+
+        for texture in &self.textures {
+            texture.bind();
+            if let Some(location) = program.get_uniform_location(texture.get_uniform_name()) {
+                unsafe { gl::Uniform1i(location, texture.get_slot() as i32) };
+            }
+        }
+
+        for (name, value) in &self.uniforms {
+            let Some(location) = program.get_uniform_location(name) else {
+                continue;
+            };
+            unsafe {
+                match value {
+                    UniformValue::Int(v) => gl::Uniform1i(location, *v),
+                    UniformValue::Float(v) => gl::Uniform1f(location, *v),
+                    UniformValue::Vec2(v) => gl::Uniform2f(location, v.x, v.y),
+                    UniformValue::Vec3(v) => gl::Uniform3f(location, v.x, v.y, v.z),
+                    UniformValue::Vec4(v) => gl::Uniform4f(location, v.x, v.y, v.z, v.w),
+                    UniformValue::Mat4(v) => {
+                        gl::UniformMatrix4fv(location, 1, gl::FALSE, v.as_ptr())
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn unbind(&self) {
+        self.unbind_from(&self.program);
+    }
+
+    pub(crate) fn unbind_from(&self, program: &Program) {
+        self.textures.iter().for_each(|texture| texture.unbind());
+        program.unbind_textures();
+        program.unbind();
+    }
+}
@@ -0,0 +1,249 @@
+use bytemuck::{Pod, Zeroable};
+use nalgebra_glm as glm;
+
+use crate::bounding_volume::{Aabb, BoundingSphere};
+use crate::draw_command::DrawRange;
+use crate::impl_vertex;
+use crate::vertex_array::VertexArray;
+
+// Vertex format of meshes. Attribute locations: 0 position, 1 normal, 2 tex_coords, 3 tangent
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct MeshVertex {
+    pub position: glm::Vec3,
+    pub normal: glm::Vec3,
+    pub tex_coords: glm::Vec2,
+    // xyz: tangent, w: handedness of the bitangent (1 or -1), bitangent = cross(normal,
+    // tangent) * w
+    pub tangent: glm::Vec4,
+}
+
+impl_vertex!(MeshVertex {
+    position,
+    normal,
+    tex_coords,
+    tangent
+});
+
+impl MeshVertex {
+    // Normal and tangent are zero, generate them with Mesh::compute_normals and
+    // Mesh::compute_tangents
+    pub fn new(position: glm::Vec3, tex_coords: glm::Vec2) -> Self {
+        Self {
+            position,
+            normal: glm::Vec3::zeros(),
+            tex_coords,
+            tangent: glm::Vec4::zeros(),
+        }
+    }
+}
+
+// Skinning data of a vertex, in a second vertex buffer of the mesh. joints: indices in the
+// joints of the Skin, weights: their influence, summing to 1. Attribute locations: 4 joints, 5
+// weights
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct SkinVertex {
+    pub joints: [u32; 4],
+    pub weights: [f32; 4],
+}
+
+impl_vertex!(SkinVertex {
+    joints = 4,
+    weights = 5
+});
+
+// Range of the index buffer drawn with the same material
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Submesh {
+    pub first_index: u32,
+    pub num_indices: u32,
+    // Index in the list of materials of the model
+    pub material_slot: usize,
+}
+
+impl Submesh {
+    pub fn new(first_index: u32, num_indices: u32, material_slot: usize) -> Self {
+        Self {
+            first_index,
+            num_indices,
+            material_slot,
+        }
+    }
+
+    pub fn get_range(&self) -> DrawRange {
+        DrawRange::new(self.first_index, self.num_indices, 0)
+    }
+}
+
+// Triangle mesh: CPU copy of the vertices and indices, the VertexArray created from them,
+// submeshes and bounding volumes
+pub struct Mesh {
+    vertices: Vec<MeshVertex>,
+    // Empty if the mesh is not skinned
+    skin_vertices: Vec<SkinVertex>,
+    indices: Vec<u32>,
+    submeshes: Vec<Submesh>,
+    vertex_array: VertexArray,
+    aabb: Aabb,
+    bounding_sphere: BoundingSphere,
+}
+
+impl Mesh {
+    // Single submesh using material slot 0
+    pub fn new(vertices: Vec<MeshVertex>, indices: Vec<u32>) -> Self {
+        let submeshes = vec![Submesh::new(0, indices.len() as u32, 0)];
+        Self::with_submeshes(vertices, indices, submeshes)
+    }
+
+    pub fn with_submeshes(
+        vertices: Vec<MeshVertex>,
+        indices: Vec<u32>,
+        submeshes: Vec<Submesh>,
+    ) -> Self {
+        assert_eq!(indices.len() % 3, 0, "Meshes are made of triangles");
+        for submesh in &submeshes {
+            assert!(
+                (submesh.first_index + submesh.num_indices) as usize <= indices.len(),
+                "Submesh {submesh:?} out of bounds, the mesh has {} indices",
+                indices.len()
+            );
+        }
+
+        let positions: Vec<glm::Vec3> = vertices.iter().map(|v| v.position).collect();
+        Self {
+            vertex_array: VertexArray::new(&vertices, &indices),
+            aabb: Aabb::from_points(&positions),
+            bounding_sphere: BoundingSphere::from_points(&positions),
+            vertices,
+            skin_vertices: Vec::new(),
+            indices,
+            submeshes,
+        }
+    }
+
+    // One per vertex. Draw the mesh from a SceneNode with a skin to animate it
+    pub fn set_skin_vertices(&mut self, skin_vertices: Vec<SkinVertex>) {
+        assert_eq!(
+            skin_vertices.len(),
+            self.vertices.len(),
+            "The mesh needs one skin vertex per vertex"
+        );
+        self.skin_vertices = skin_vertices;
+        self.upload();
+    }
+
+    // Smooth normals: each vertex gets the average of the normals of its triangles, weighted by
+    // their area. Vertices are not split, duplicate them to get hard edges
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![glm::Vec3::zeros(); self.vertices.len()];
+        for triangle in self.indices.chunks_exact(3) {
+            let [p0, p1, p2] = [0, 1, 2].map(|i| self.vertices[triangle[i] as usize].position);
+            // The length of the cross product is twice the area of the triangle
+            let normal = glm::cross(&(p1 - p0), &(p2 - p0));
+            triangle
+                .iter()
+                .for_each(|&index| normals[index as usize] += normal);
+        }
+
+        for (vertex, normal) in self.vertices.iter_mut().zip(normals) {
+            vertex.normal = match normal.norm() > f32::EPSILON {
+                true => normal.normalize(),
+                false => glm::Vec3::y(),
+            };
+        }
+
+        self.upload();
+    }
+
+    // Tangents along the U texture direction, for normal mapping. Requires normals and texture
+    // coordinates
+    pub fn compute_tangents(&mut self) {
+        let mut tangents = vec![glm::Vec3::zeros(); self.vertices.len()];
+        let mut bitangents = vec![glm::Vec3::zeros(); self.vertices.len()];
+
+        for triangle in self.indices.chunks_exact(3) {
+            let [v0, v1, v2] = [0, 1, 2].map(|i| &self.vertices[triangle[i] as usize]);
+            let edge1 = v1.position - v0.position;
+            let edge2 = v2.position - v0.position;
+            let delta_uv1 = v1.tex_coords - v0.tex_coords;
+            let delta_uv2 = v2.tex_coords - v0.tex_coords;
+
+            let determinant = delta_uv1.x * delta_uv2.y - delta_uv2.x * delta_uv1.y;
+            // Degenerate texture coordinates
+            if determinant.abs() < f32::EPSILON {
+                continue;
+            }
+            let tangent = (edge1 * delta_uv2.y - edge2 * delta_uv1.y) / determinant;
+            let bitangent = (edge2 * delta_uv1.x - edge1 * delta_uv2.x) / determinant;
+
+            for &index in triangle {
+                tangents[index as usize] += tangent;
+                bitangents[index as usize] += bitangent;
+            }
+        }
+
+        for (i, vertex) in self.vertices.iter_mut().enumerate() {
+            let normal = vertex.normal;
+            // Gram-Schmidt: make the tangent perpendicular to the normal
+            let mut tangent = tangents[i] - normal * glm::dot(&normal, &tangents[i]);
+            if tangent.norm() < f32::EPSILON {
+                tangent = any_perpendicular(&normal);
+            }
+            let tangent = tangent.normalize();
+
+            let handedness = match glm::dot(&glm::cross(&normal, &tangent), &bitangents[i]) < 0.0 {
+                true => -1.0,
+                false => 1.0,
+            };
+            vertex.tangent = tangent.push(handedness);
+        }
+
+        self.upload();
+    }
+
+    fn upload(&mut self) {
+        self.vertex_array = VertexArray::new(&self.vertices, &self.indices);
+        if !self.skin_vertices.is_empty() {
+            self.vertex_array.add_vertex_buffer(&self.skin_vertices);
+        }
+    }
+
+    pub fn get_vertices(&self) -> &[MeshVertex] {
+        &self.vertices
+    }
+
+    pub fn get_skin_vertices(&self) -> &[SkinVertex] {
+        &self.skin_vertices
+    }
+
+    pub fn get_indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    pub fn get_submeshes(&self) -> &[Submesh] {
+        &self.submeshes
+    }
+
+    pub fn get_vertex_array(&self) -> &VertexArray {
+        &self.vertex_array
+    }
+
+    // In model space
+    pub fn get_aabb(&self) -> Aabb {
+        self.aabb
+    }
+
+    // In model space
+    pub fn get_bounding_sphere(&self) -> BoundingSphere {
+        self.bounding_sphere
+    }
+}
+
+fn any_perpendicular(v: &glm::Vec3) -> glm::Vec3 {
+    let axis = match v.x.abs() < 0.9 {
+        true => glm::Vec3::x(),
+        false => glm::Vec3::y(),
+    };
+    glm::cross(v, &axis)
+}
@@ -0,0 +1,197 @@
+use gl;
+
+use crate::framebuffer::{Framebuffer, RenderTarget};
+use crate::renderbuffer::Renderbuffer;
+use crate::texture::TextureFormat;
+
+// Anti-aliased offscreen target. Multisampled attachments can't be sampled by shaders, resolve
+// them into a Framebuffer (or into the window) first
+pub struct MultisampleFramebuffer {
+    id: u32,
+    width: u32,
+    height: u32,
+    samples: u32,
+    color_attachments: Vec<Renderbuffer>,
+    depth_stencil: Option<Renderbuffer>,
+}
+
+impl MultisampleFramebuffer {
+    pub fn new(
+        width: u32,
+        height: u32,
+        samples: u32,
+        color_formats: &[TextureFormat],
+        depth_stencil_format: Option<TextureFormat>,
+    ) -> Self {
+        let mut max_samples = 0;
+        unsafe { gl::GetIntegerv(gl::MAX_SAMPLES, &mut max_samples) };
+        assert!(
+            samples <= max_samples as u32,
+            "{samples} samples requested, the maximum supported is {max_samples}"
+        );
+
+        let mut id = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut id);
+            assert_ne!(id, 0);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, id);
+        }
+
+        let color_attachments: Vec<Renderbuffer> = color_formats
+            .iter()
+            .enumerate()
+            .map(|(index, format)| {
+                assert!(!format.is_depth());
+                let renderbuffer = Renderbuffer::new_multisampled(width, height, *format, samples);
+                unsafe {
+                    gl::FramebufferRenderbuffer(
+                        gl::FRAMEBUFFER,
+                        gl::COLOR_ATTACHMENT0 + index as u32,
+                        gl::RENDERBUFFER,
+                        renderbuffer.get_id(),
+                    );
+                }
+                renderbuffer
+            })
+            .collect();
+
+        let depth_stencil = depth_stencil_format.map(|format| {
+            assert!(format.is_depth());
+            let attachment_point = match format.has_stencil() {
+                true => gl::DEPTH_STENCIL_ATTACHMENT,
+                false => gl::DEPTH_ATTACHMENT,
+            };
+            let renderbuffer = Renderbuffer::new_multisampled(width, height, format, samples);
+            unsafe {
+                gl::FramebufferRenderbuffer(
+                    gl::FRAMEBUFFER,
+                    attachment_point,
+                    gl::RENDERBUFFER,
+                    renderbuffer.get_id(),
+                );
+            }
+            renderbuffer
+        });
+
+        let draw_buffers: Vec<u32> = (0..color_attachments.len() as u32)
+            .map(|index| gl::COLOR_ATTACHMENT0 + index)
+            .collect();
+        unsafe { gl::DrawBuffers(draw_buffers.len() as i32, draw_buffers.as_ptr()) };
+
+        Framebuffer::check_status();
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0) };
+
+        Self {
+            id,
+            width,
+            height,
+            samples,
+            color_attachments,
+            depth_stencil,
+        }
+    }
+
+    // Reallocates every attachment. The content is lost. Same as Framebuffer::resize, zero sizes
+    // (minimised windows) are ignored
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if (width, height) == (self.width, self.height) || width == 0 || height == 0 {
+            return;
+        }
+
+        self.color_attachments
+            .iter()
+            .chain(self.depth_stencil.iter())
+            .for_each(|renderbuffer| renderbuffer.resize(width, height));
+
+        self.width = width;
+        self.height = height;
+
+        self.bind();
+        Framebuffer::check_status();
+        self.unbind();
+    }
+
+    // Averages the samples of each colour attachment into the colour attachment with the same
+    // index of the target. The depth buffer is copied too when both have one
+    pub fn resolve(&self, target: &Framebuffer) {
+        assert_eq!(
+            target.get_num_color_attachments(),
+            self.color_attachments.len()
+        );
+        assert_eq!(target.get_size(), self.get_size());
+
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, target.get_framebuffer_id());
+
+            for index in 0..self.color_attachments.len() as u32 {
+                gl::ReadBuffer(gl::COLOR_ATTACHMENT0 + index);
+                gl::DrawBuffers(1, &(gl::COLOR_ATTACHMENT0 + index));
+                self.blit(gl::COLOR_BUFFER_BIT);
+            }
+
+            if self.depth_stencil.is_some() && target.has_depth() {
+                self.blit(gl::DEPTH_BUFFER_BIT);
+            }
+
+            // Restore the draw buffers of the target
+            let draw_buffers: Vec<u32> = (0..self.color_attachments.len() as u32)
+                .map(|index| gl::COLOR_ATTACHMENT0 + index)
+                .collect();
+            gl::DrawBuffers(draw_buffers.len() as i32, draw_buffers.as_ptr());
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    // Resolves the first colour attachment into the window. The window must not be multisampled
+    // and must have the same size
+    pub fn resolve_to_window(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+            gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+            self.blit(gl::COLOR_BUFFER_BIT);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    // Source and destination rectangles must match when resolving samples
+    unsafe fn blit(&self, mask: u32) {
+        let (width, height) = (self.width as i32, self.height as i32);
+        gl::BlitFramebuffer(0, 0, width, height, 0, 0, width, height, mask, gl::NEAREST);
+    }
+
+    pub fn get_samples(&self) -> u32 {
+        self.samples
+    }
+
+    pub fn bind(&self) {
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, self.id) };
+    }
+
+    // Binds the default framebuffer (the window)
+    pub fn unbind(&self) {
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0) };
+    }
+}
+
+impl RenderTarget for MultisampleFramebuffer {
+    fn get_framebuffer_id(&self) -> u32 {
+        self.id
+    }
+
+    fn get_samples(&self) -> u32 {
+        self.samples
+    }
+
+    fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+impl Drop for MultisampleFramebuffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteFramebuffers(1, &self.id) };
+    }
+}
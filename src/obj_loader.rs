@@ -0,0 +1,680 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use image::imageops;
+use nalgebra_glm as glm;
+
+use crate::mesh::{Mesh, MeshVertex, Submesh};
+use crate::texture::{Texture, TextureFormat, TextureSampler};
+
+// Texture units and sampler names of the material textures, the ones of the standard lit program
+pub use crate::phong::{
+    DIFFUSE_MAP_SLOT, DIFFUSE_MAP_UNIFORM, NORMAL_MAP_SLOT, NORMAL_MAP_UNIFORM, SPECULAR_MAP_SLOT,
+    SPECULAR_MAP_UNIFORM,
+};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(PathBuf, std::io::Error),
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    MissingTexture(PathBuf),
+    Image(PathBuf, image::ImageError),
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io(path, error) => write!(f, "Error reading {}: {error}", path.display()),
+            ObjError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{line}: {message}", path.display()),
+            ObjError::MissingTexture(path) => write!(f, "Texture {} not found", path.display()),
+            ObjError::Image(path, error) => {
+                write!(f, "Error decoding {}: {error}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+// Material of a MTL file. Faces without material use a default white one
+pub struct ObjMaterial {
+    pub name: String,
+    pub ambient: glm::Vec3,
+    pub diffuse: glm::Vec3,
+    pub specular: glm::Vec3,
+    pub shininess: f32,
+    // 1 is opaque
+    pub opacity: f32,
+    pub diffuse_map: Option<Rc<Texture>>,
+    pub specular_map: Option<Rc<Texture>>,
+    pub normal_map: Option<Rc<Texture>>,
+}
+
+impl ObjMaterial {
+    fn new(name: &str) -> Self {
+        Self {
+            name: String::from(name),
+            ambient: glm::vec3(0.2, 0.2, 0.2),
+            diffuse: glm::vec3(0.8, 0.8, 0.8),
+            specular: glm::Vec3::zeros(),
+            shininess: 1.0,
+            opacity: 1.0,
+            diffuse_map: None,
+            specular_map: None,
+            normal_map: None,
+        }
+    }
+}
+
+// Object or group (o and g statements) of the file. The submeshes use the material slots of
+// ObjModel::materials
+pub struct ObjGroup {
+    pub name: String,
+    pub mesh: Mesh,
+}
+
+pub struct ObjModel {
+    pub groups: Vec<ObjGroup>,
+    pub materials: Vec<ObjMaterial>,
+}
+
+// Position, texture coordinates and normal indices of a face corner, 0-based
+type Corner = (usize, Option<usize>, Option<usize>);
+
+struct GroupBuilder {
+    name: String,
+    // Triangles by material slot, in order of first use
+    triangles: Vec<(usize, Vec<[Corner; 3]>)>,
+}
+
+impl GroupBuilder {
+    fn new(name: &str) -> Self {
+        Self {
+            name: String::from(name),
+            triangles: Vec::new(),
+        }
+    }
+
+    fn add_triangle(&mut self, material: usize, triangle: [Corner; 3]) {
+        match self
+            .triangles
+            .iter_mut()
+            .find(|(slot, _)| *slot == material)
+        {
+            Some((_, triangles)) => triangles.push(triangle),
+            None => self.triangles.push((material, vec![triangle])),
+        }
+    }
+
+    // De-duplicates the corners into indexed vertices, one submesh per material
+    fn build(
+        self,
+        positions: &[glm::Vec3],
+        tex_coords: &[glm::Vec2],
+        normals: &[glm::Vec3],
+    ) -> ObjGroup {
+        let mut vertices: Vec<MeshVertex> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+        let mut submeshes: Vec<Submesh> = Vec::new();
+        let mut vertex_indices: HashMap<Corner, u32> = HashMap::new();
+        let mut has_normals = true;
+
+        for (material, triangles) in self.triangles {
+            let first_index = indices.len() as u32;
+            for corner in triangles.into_iter().flatten() {
+                let index = *vertex_indices.entry(corner).or_insert_with(|| {
+                    let (position, tex_coords_index, normal) = corner;
+                    has_normals &= normal.is_some();
+                    vertices.push(MeshVertex {
+                        normal: normal.map_or(glm::Vec3::zeros(), |n| normals[n]),
+                        ..MeshVertex::new(
+                            positions[position],
+                            tex_coords_index.map_or(glm::Vec2::zeros(), |t| tex_coords[t]),
+                        )
+                    });
+                    vertices.len() as u32 - 1
+                });
+                indices.push(index);
+            }
+            submeshes.push(Submesh::new(
+                first_index,
+                indices.len() as u32 - first_index,
+                material,
+            ));
+        }
+
+        let mut mesh = Mesh::with_submeshes(vertices, indices, submeshes);
+        if !has_normals {
+            mesh.compute_normals();
+        }
+        mesh.compute_tangents();
+
+        ObjGroup {
+            name: self.name,
+            mesh,
+        }
+    }
+}
+
+struct Parser<'a> {
+    path: &'a Path,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: impl Into<String>) -> ObjError {
+        ObjError::Parse {
+            path: self.path.to_path_buf(),
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn floats<const N: usize>(&self, args: &[&str]) -> Result<[f32; N], ObjError> {
+        if args.len() < N {
+            return Err(self.error(format!("Expected {N} numbers, found {}", args.len())));
+        }
+        let mut values = [0.0; N];
+        for (value, arg) in values.iter_mut().zip(args) {
+            *value = arg
+                .parse()
+                .map_err(|_| self.error(format!("Invalid number '{arg}'")))?;
+        }
+        Ok(values)
+    }
+
+    // OBJ indices start at 1, negative ones count back from the last element
+    fn index(&self, index: &str, len: usize) -> Result<usize, ObjError> {
+        let value: i64 = index
+            .parse()
+            .map_err(|_| self.error(format!("Invalid index '{index}'")))?;
+        let resolved = match value {
+            value if value > 0 => value - 1,
+            value if value < 0 => len as i64 + value,
+            _ => return Err(self.error("Index 0 is not valid")),
+        };
+        match (0..len as i64).contains(&resolved) {
+            true => Ok(resolved as usize),
+            false => Err(self.error(format!("Index {value} out of bounds ({len} elements)"))),
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<String, ObjError> {
+    std::fs::read_to_string(path).map_err(|error| ObjError::Io(path.to_path_buf(), error))
+}
+
+// Splits a line into the statement and its arguments, without the comment
+fn split_line(line: &str) -> Option<(&str, Vec<&str>)> {
+    let line = line.split('#').next().unwrap_or("");
+    let mut tokens = line.split_whitespace();
+    let statement = tokens.next()?;
+    Some((statement, tokens.collect()))
+}
+
+impl ObjModel {
+    // Texture paths are relative to the MTL file. Requires an OpenGL context for the meshes and
+    // textures
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ObjError> {
+        let path = path.as_ref();
+        let source = read_file(path)?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let mut parser = Parser { path, line: 0 };
+
+        let mut positions: Vec<glm::Vec3> = Vec::new();
+        let mut tex_coords: Vec<glm::Vec2> = Vec::new();
+        let mut normals: Vec<glm::Vec3> = Vec::new();
+
+        let mut materials: Vec<ObjMaterial> = Vec::new();
+        let mut textures: HashMap<(PathBuf, u32), Rc<Texture>> = HashMap::new();
+        let mut current_material: Option<usize> = None;
+
+        let mut groups: Vec<GroupBuilder> = vec![GroupBuilder::new("default")];
+
+        for (line_index, line) in source.lines().enumerate() {
+            parser.line = line_index + 1;
+            let Some((statement, args)) = split_line(line) else {
+                continue;
+            };
+
+            match statement {
+                "v" => positions.push(glm::Vec3::from(parser.floats::<3>(&args)?)),
+                // The v coordinate is optional
+                "vt" => {
+                    let [u] = parser.floats::<1>(&args)?;
+                    let v = match args.len() {
+                        1 => 0.0,
+                        _ => parser.floats::<2>(&args)?[1],
+                    };
+                    tex_coords.push(glm::vec2(u, v));
+                }
+                "vn" => normals.push(glm::Vec3::from(parser.floats::<3>(&args)?)),
+                "f" => {
+                    if args.len() < 3 {
+                        return Err(parser.error("Faces need at least 3 vertices"));
+                    }
+                    let corners = args
+                        .iter()
+                        .map(|arg| {
+                            let mut parts = arg.split('/');
+                            let position = parser.index(parts.next().unwrap(), positions.len())?;
+                            let tex_coords_index = match parts.next() {
+                                Some("") | None => None,
+                                Some(index) => Some(parser.index(index, tex_coords.len())?),
+                            };
+                            let normal = match parts.next() {
+                                Some("") | None => None,
+                                Some(index) => Some(parser.index(index, normals.len())?),
+                            };
+                            Ok((position, tex_coords_index, normal))
+                        })
+                        .collect::<Result<Vec<Corner>, ObjError>>()?;
+
+                    let material = *current_material.get_or_insert_with(|| {
+                        materials.push(ObjMaterial::new("default"));
+                        materials.len() - 1
+                    });
+                    let polygon: Vec<glm::Vec3> = corners.iter().map(|c| positions[c.0]).collect();
+                    let group = groups.last_mut().unwrap();
+                    for [a, b, c] in triangulate(&polygon) {
+                        group.add_triangle(material, [corners[a], corners[b], corners[c]]);
+                    }
+                }
+                "o" | "g" => {
+                    let name = args.join(" ");
+                    groups.push(GroupBuilder::new(&name));
+                }
+                "mtllib" => {
+                    // The file name can contain spaces
+                    let mtl_path = directory.join(args.join(" "));
+                    load_mtl(&mtl_path, &mut materials, &mut textures)?;
+                }
+                "usemtl" => {
+                    let name = args.join(" ");
+                    let slot = match materials.iter().position(|m| m.name == name) {
+                        Some(slot) => slot,
+                        None => {
+                            // Unknown materials get default values
+                            materials.push(ObjMaterial::new(&name));
+                            materials.len() - 1
+                        }
+                    };
+                    current_material = Some(slot);
+                }
+                // Smoothing groups, lines, points, curves... are ignored
+                _ => {}
+            }
+        }
+
+        let groups = groups
+            .into_iter()
+            .filter(|group| !group.triangles.is_empty())
+            .map(|group| group.build(&positions, &tex_coords, &normals))
+            .collect();
+
+        Ok(Self { groups, materials })
+    }
+}
+
+fn load_mtl(
+    path: &Path,
+    materials: &mut Vec<ObjMaterial>,
+    textures: &mut HashMap<(PathBuf, u32), Rc<Texture>>,
+) -> Result<(), ObjError> {
+    let source = read_file(path)?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let mut parser = Parser { path, line: 0 };
+
+    let mut load_texture = |args: &[&str], uniform_name: &str, slot: u32| {
+        // Options (-bm 1.0, -clamp on...) come before the file name, the last argument
+        let file_name = args.last().unwrap_or(&"").replace('\\', "/");
+        let texture_path = directory.join(file_name);
+        if !texture_path.is_file() {
+            return Err(ObjError::MissingTexture(texture_path));
+        }
+
+        let key = (texture_path, slot);
+        if let Some(texture) = textures.get(&key) {
+            return Ok(Some(texture.clone()));
+        }
+
+        let mut image = image::open(&key.0)
+            .map_err(|error| ObjError::Image(key.0.clone(), error))?
+            .into_rgba8();
+        // The texture coordinate v = 0 is the bottom of the image
+        imageops::flip_vertical_in_place(&mut image);
+        let texture = Rc::new(Texture::from_image(
+            uniform_name,
+            &image,
+            TextureFormat::Rgba8,
+            slot,
+            &TextureSampler::default(),
+        ));
+        textures.insert(key, texture.clone());
+        Ok(Some(texture))
+    };
+
+    for (line_index, line) in source.lines().enumerate() {
+        parser.line = line_index + 1;
+        let Some((statement, args)) = split_line(line) else {
+            continue;
+        };
+
+        if statement == "newmtl" {
+            materials.push(ObjMaterial::new(&args.join(" ")));
+            continue;
+        }
+        let material = match materials.last_mut() {
+            Some(material) => material,
+            None => return Err(parser.error(format!("'{statement}' before newmtl"))),
+        };
+
+        match statement {
+            "Ka" => material.ambient = glm::Vec3::from(parser.floats::<3>(&args)?),
+            "Kd" => material.diffuse = glm::Vec3::from(parser.floats::<3>(&args)?),
+            "Ks" => material.specular = glm::Vec3::from(parser.floats::<3>(&args)?),
+            "Ns" => material.shininess = parser.floats::<1>(&args)?[0],
+            "d" => material.opacity = parser.floats::<1>(&args)?[0],
+            // Transparency, the opposite of d
+            "Tr" => material.opacity = 1.0 - parser.floats::<1>(&args)?[0],
+            "map_Kd" => {
+                material.diffuse_map = load_texture(&args, DIFFUSE_MAP_UNIFORM, DIFFUSE_MAP_SLOT)?
+            }
+            "map_Ks" => {
+                material.specular_map =
+                    load_texture(&args, SPECULAR_MAP_UNIFORM, SPECULAR_MAP_SLOT)?
+            }
+            // Bump maps are assumed to be tangent-space normal maps
+            "map_Bump" | "map_bump" | "bump" | "norm" => {
+                material.normal_map = load_texture(&args, NORMAL_MAP_UNIFORM, NORMAL_MAP_SLOT)?
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+// Splits a polygon into triangles with ear clipping, so concave polygons are supported.
+// Returns indices into the polygon, with its winding
+fn triangulate(polygon: &[glm::Vec3]) -> Vec<[usize; 3]> {
+    let fan = |indices: &[usize]| -> Vec<[usize; 3]> {
+        (1..indices.len() - 1)
+            .map(|i| [indices[0], indices[i], indices[i + 1]])
+            .collect()
+    };
+
+    if polygon.len() == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    // Newell's method, works for non-planar polygons
+    let normal = (0..polygon.len()).fold(glm::Vec3::zeros(), |normal, i| {
+        let (current, next) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        normal + glm::cross(&current, &next)
+    });
+    let all_indices: Vec<usize> = (0..polygon.len()).collect();
+    if normal.norm() < f32::EPSILON {
+        return fan(&all_indices);
+    }
+
+    // Project on the plane of the polygon, counter-clockwise
+    let tangent = match normal.x.abs() < 0.9 * normal.norm() {
+        true => glm::cross(&normal, &glm::Vec3::x()),
+        false => glm::cross(&normal, &glm::Vec3::y()),
+    }
+    .normalize();
+    let bitangent = glm::cross(&normal.normalize(), &tangent);
+    let points: Vec<glm::Vec2> = polygon
+        .iter()
+        .map(|p| glm::vec2(glm::dot(p, &tangent), glm::dot(p, &bitangent)))
+        .collect();
+
+    let cross_2d = |a: glm::Vec2, b: glm::Vec2, c: glm::Vec2| {
+        (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+    };
+    let in_triangle = |p: glm::Vec2, a: glm::Vec2, b: glm::Vec2, c: glm::Vec2| {
+        cross_2d(a, b, p) >= 0.0 && cross_2d(b, c, p) >= 0.0 && cross_2d(c, a, p) >= 0.0
+    };
+
+    let mut remaining = all_indices;
+    let mut triangles = Vec::with_capacity(polygon.len() - 2);
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        let ear = (0..len).find(|&i| {
+            let (prev, current, next) = (
+                remaining[(i + len - 1) % len],
+                remaining[i],
+                remaining[(i + 1) % len],
+            );
+            let (a, b, c) = (points[prev], points[current], points[next]);
+            // Reflex corners are not ears
+            if cross_2d(a, b, c) <= 0.0 {
+                return false;
+            }
+            remaining
+                .iter()
+                .filter(|&&other| other != prev && other != current && other != next)
+                .all(|&other| !in_triangle(points[other], a, b, c))
+        });
+
+        match ear {
+            Some(i) => {
+                triangles.push([
+                    remaining[(i + len - 1) % len],
+                    remaining[i],
+                    remaining[(i + 1) % len],
+                ]);
+                remaining.remove(i);
+            }
+            // Self-intersecting polygon
+            None => break,
+        }
+    }
+    triangles.extend(fan(&remaining));
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(polygon: &[glm::Vec3], triangles: &[[usize; 3]]) -> f32 {
+        triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                glm::cross(&(polygon[b] - polygon[a]), &(polygon[c] - polygon[a])).norm() / 2.0
+            })
+            .sum()
+    }
+
+    // Normal of each triangle, with the winding of the polygon
+    fn normals(polygon: &[glm::Vec3], triangles: &[[usize; 3]]) -> Vec<glm::Vec3> {
+        triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                glm::cross(&(polygon[b] - polygon[a]), &(polygon[c] - polygon[a])).normalize()
+            })
+            .collect()
+    }
+
+    fn write_mtl(name: &str, source: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("opengl_sandbox_{name}.mtl"));
+        std::fs::write(&path, source).unwrap();
+        path
+    }
+
+    #[test]
+    fn triangulate_triangle() {
+        let polygon = [
+            glm::vec3(0.0, 0.0, 0.0),
+            glm::vec3(1.0, 0.0, 0.0),
+            glm::vec3(0.0, 1.0, 0.0),
+        ];
+        assert_eq!(triangulate(&polygon), vec![[0, 1, 2]]);
+    }
+
+    #[test]
+    fn triangulate_quad() {
+        let polygon = [
+            glm::vec3(0.0, 0.0, 0.0),
+            glm::vec3(2.0, 0.0, 0.0),
+            glm::vec3(2.0, 1.0, 0.0),
+            glm::vec3(0.0, 1.0, 0.0),
+        ];
+        let triangles = triangulate(&polygon);
+        assert_eq!(triangles.len(), 2);
+        assert!((area(&polygon, &triangles) - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn triangulate_concave_polygon() {
+        // L shape, counter-clockwise, reflex corner at (1, 1)
+        let polygon = [
+            glm::vec3(0.0, 0.0, 0.0),
+            glm::vec3(2.0, 0.0, 0.0),
+            glm::vec3(2.0, 1.0, 0.0),
+            glm::vec3(1.0, 1.0, 0.0),
+            glm::vec3(1.0, 2.0, 0.0),
+            glm::vec3(0.0, 2.0, 0.0),
+        ];
+        let triangles = triangulate(&polygon);
+        assert_eq!(triangles.len(), 4);
+        // A fan from the first corner would cover the notch and have a larger area
+        assert!((area(&polygon, &triangles) - 3.0).abs() < 1e-5);
+        for normal in normals(&polygon, &triangles) {
+            assert!((normal - glm::Vec3::z()).norm() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn triangulate_keeps_clockwise_winding() {
+        let polygon = [
+            glm::vec3(0.0, 0.0, 0.0),
+            glm::vec3(0.0, 1.0, 0.0),
+            glm::vec3(1.0, 1.0, 0.0),
+            glm::vec3(1.0, 0.0, 0.0),
+        ];
+        for normal in normals(&polygon, &triangulate(&polygon)) {
+            assert!((normal + glm::Vec3::z()).norm() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn split_line_removes_comments() {
+        assert_eq!(
+            split_line("v 1 2 3 # comment"),
+            Some(("v", vec!["1", "2", "3"]))
+        );
+        assert_eq!(split_line("  usemtl  red  "), Some(("usemtl", vec!["red"])));
+        assert_eq!(split_line("# comment"), None);
+        assert_eq!(split_line(""), None);
+    }
+
+    #[test]
+    fn parse_floats() {
+        let parser = Parser {
+            path: Path::new("test.obj"),
+            line: 1,
+        };
+        assert_eq!(
+            parser.floats::<3>(&["1", "-2.5", "3e2"]).unwrap(),
+            [1.0, -2.5, 300.0]
+        );
+        // Extra values, like the w of positions, are ignored
+        assert_eq!(parser.floats::<2>(&["1", "2", "3"]).unwrap(), [1.0, 2.0]);
+        assert!(matches!(
+            parser.floats::<3>(&["1", "2"]),
+            Err(ObjError::Parse { line: 1, .. })
+        ));
+        assert!(parser.floats::<1>(&["one"]).is_err());
+    }
+
+    #[test]
+    fn parse_indices() {
+        let parser = Parser {
+            path: Path::new("test.obj"),
+            line: 1,
+        };
+        assert_eq!(parser.index("1", 4).unwrap(), 0);
+        assert_eq!(parser.index("4", 4).unwrap(), 3);
+        assert_eq!(parser.index("-1", 4).unwrap(), 3);
+        assert_eq!(parser.index("-4", 4).unwrap(), 0);
+        assert!(parser.index("0", 4).is_err());
+        assert!(parser.index("5", 4).is_err());
+        assert!(parser.index("-5", 4).is_err());
+        assert!(parser.index("x", 4).is_err());
+    }
+
+    #[test]
+    fn load_mtl_materials() {
+        let path = write_mtl(
+            "materials",
+            "# Two materials
+newmtl red
+Ka 0.1 0.0 0.0
+Kd 1.0 0.0 0.0
+Ks 0.5 0.5 0.5
+Ns 32
+d 0.5
+
+newmtl glass
+Tr 0.75
+",
+        );
+        let mut materials = Vec::new();
+        load_mtl(&path, &mut materials, &mut HashMap::new()).unwrap();
+
+        assert_eq!(materials.len(), 2);
+        let red = &materials[0];
+        assert_eq!(red.name, "red");
+        assert_eq!(red.ambient, glm::vec3(0.1, 0.0, 0.0));
+        assert_eq!(red.diffuse, glm::vec3(1.0, 0.0, 0.0));
+        assert_eq!(red.specular, glm::vec3(0.5, 0.5, 0.5));
+        assert_eq!(red.shininess, 32.0);
+        assert_eq!(red.opacity, 0.5);
+        assert!(red.diffuse_map.is_none());
+
+        let glass = &materials[1];
+        assert_eq!(glass.name, "glass");
+        assert_eq!(glass.diffuse, ObjMaterial::new("").diffuse);
+        assert!((glass.opacity - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn load_mtl_errors() {
+        let path = write_mtl("before_newmtl", "Kd 1 0 0\n");
+        assert!(matches!(
+            load_mtl(&path, &mut Vec::new(), &mut HashMap::new()),
+            Err(ObjError::Parse { line: 1, .. })
+        ));
+
+        let path = write_mtl("invalid_number", "newmtl red\nKd 1 zero 0\n");
+        assert!(matches!(
+            load_mtl(&path, &mut Vec::new(), &mut HashMap::new()),
+            Err(ObjError::Parse { line: 2, .. })
+        ));
+
+        let path = write_mtl("missing_texture", "newmtl red\nmap_Kd missing.png\n");
+        assert!(matches!(
+            load_mtl(&path, &mut Vec::new(), &mut HashMap::new()),
+            Err(ObjError::MissingTexture(_))
+        ));
+
+        let missing = std::env::temp_dir().join("opengl_sandbox_missing.mtl");
+        assert!(matches!(
+            load_mtl(&missing, &mut Vec::new(), &mut HashMap::new()),
+            Err(ObjError::Io(..))
+        ));
+    }
+}
@@ -0,0 +1,287 @@
+use std::rc::Rc;
+
+use nalgebra_glm as glm;
+
+use crate::environment::{
+    BRDF_LUT_SLOT, BRDF_LUT_UNIFORM, IRRADIANCE_MAP_SLOT, IRRADIANCE_MAP_UNIFORM,
+    PREFILTERED_MAP_MIP_LEVELS, PREFILTERED_MAP_SLOT, PREFILTERED_MAP_UNIFORM,
+};
+use crate::gltf_loader::{AlphaMode, GltfMaterial};
+use crate::light;
+use crate::material::Material;
+use crate::program::Program;
+use crate::render_state::{CullFace, RenderState};
+use crate::texture::Texture;
+
+pub const BASE_COLOR_MAP_UNIFORM: &str = "uBaseColorMap";
+pub const BASE_COLOR_MAP_SLOT: u32 = 0;
+pub const METALLIC_ROUGHNESS_MAP_UNIFORM: &str = "uMetallicRoughnessMap";
+pub const METALLIC_ROUGHNESS_MAP_SLOT: u32 = 1;
+pub const NORMAL_MAP_UNIFORM: &str = "uNormalMap";
+pub const NORMAL_MAP_SLOT: u32 = 2;
+pub const OCCLUSION_MAP_UNIFORM: &str = "uOcclusionMap";
+pub const OCCLUSION_MAP_SLOT: u32 = 3;
+pub const EMISSIVE_MAP_UNIFORM: &str = "uEmissiveMap";
+pub const EMISSIVE_MAP_SLOT: u32 = 4;
+
+// Metallic-roughness model of glTF 2.0: Cook-Torrance with the GGX distribution, Smith-Schlick
+// geometry and Schlick fresnel. Appended to the shared lit fragment shader source, see
+// light::new_lit_program. The output is linear HDR colour, tonemap it afterwards
+const FRAGMENT_SHADER_SRC: &str = "
+out vec4 FragColor;
+
+uniform vec4 uBaseColorFactor;
+uniform float uMetallicFactor;
+uniform float uRoughnessFactor;
+uniform float uNormalScale;
+uniform float uOcclusionStrength;
+uniform vec3 uEmissiveFactor;
+// 0: opaque, 1: mask, 2: blend
+uniform int uAlphaMode;
+uniform float uAlphaCutoff;
+
+uniform bool uHasBaseColorMap;
+uniform bool uHasMetallicRoughnessMap;
+uniform bool uHasOcclusionMap;
+uniform bool uHasEmissiveMap;
+uniform sampler2D uBaseColorMap;
+// Roughness in G, metalness in B
+uniform sampler2D uMetallicRoughnessMap;
+uniform sampler2D uOcclusionMap;
+uniform sampler2D uEmissiveMap;
+
+#ifdef USE_ENVIRONMENT
+uniform samplerCube uIrradianceMap;
+uniform samplerCube uPrefilteredMap;
+uniform sampler2D uBrdfLut;
+#endif
+
+const float PI = 3.14159265359;
+
+float distributionGGX(float NdotH, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float d = NdotH * NdotH * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+float geometrySmith(float NdotV, float NdotL, float roughness) {
+    // k for direct lighting
+    float r = roughness + 1.0;
+    float k = r * r / 8.0;
+    return NdotV / (NdotV * (1.0 - k) + k) * NdotL / (NdotL * (1.0 - k) + k);
+}
+
+vec3 fresnelSchlick(float cosTheta, vec3 f0) {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+// Rough surfaces reflect less at grazing angles
+vec3 fresnelSchlickRoughness(float cosTheta, vec3 f0, float roughness) {
+    return f0 + (max(vec3(1.0 - roughness), f0) - f0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+vec3 shade(Surface surface, vec3 lightDirection, vec3 radiance) {
+    vec3 normal = surface.normal;
+    vec3 view = surface.view;
+    float roughness = surface.roughness;
+    float NdotL = max(dot(normal, lightDirection), 0.0);
+    if (NdotL == 0.0) {
+        return vec3(0.0);
+    }
+    vec3 halfVector = normalize(view + lightDirection);
+    float NdotV = max(dot(normal, view), 0.0001);
+    float NdotH = max(dot(normal, halfVector), 0.0);
+
+    vec3 fresnel = fresnelSchlick(max(dot(halfVector, view), 0.0), surface.specular);
+    vec3 specular = distributionGGX(NdotH, roughness) * geometrySmith(NdotV, NdotL, roughness)
+        * fresnel / (4.0 * NdotV * NdotL + 0.0001);
+    vec3 diffuse = (1.0 - fresnel) * (1.0 - surface.metallic) * surface.color / PI;
+    return (diffuse + specular) * radiance * NdotL;
+}
+
+void main() {
+    vec4 baseColor = uBaseColorFactor;
+    if (uHasBaseColorMap) {
+        baseColor *= texture(uBaseColorMap, vTexCoord);
+    }
+    if (uAlphaMode == 1 && baseColor.a < uAlphaCutoff) {
+        discard;
+    }
+
+    float metallic = uMetallicFactor;
+    float roughness = uRoughnessFactor;
+    if (uHasMetallicRoughnessMap) {
+        vec4 metallicRoughness = texture(uMetallicRoughnessMap, vTexCoord);
+        roughness *= metallicRoughness.g;
+        metallic *= metallicRoughness.b;
+    }
+    // Perfectly smooth surfaces make the highlights of point lights vanish
+    roughness = clamp(roughness, 0.04, 1.0);
+
+    vec3 albedo = baseColor.rgb;
+    vec3 f0 = mix(vec3(0.04), albedo, metallic);
+    vec3 normal = getNormal(uNormalScale);
+    vec3 view = normalize(uViewPosition.xyz - vWorldPos);
+
+    vec3 color = shadeLights(Surface(vWorldPos, normal, view, albedo, f0, metallic, roughness));
+
+    float occlusion = 1.0;
+    if (uHasOcclusionMap) {
+        occlusion = 1.0 + uOcclusionStrength * (texture(uOcclusionMap, vTexCoord).r - 1.0);
+    }
+
+#ifdef USE_ENVIRONMENT
+    float NdotV = max(dot(normal, view), 0.0);
+    vec3 fresnel = fresnelSchlickRoughness(NdotV, f0, roughness);
+    vec3 diffuse = (1.0 - fresnel) * (1.0 - metallic) * texture(uIrradianceMap, normal).rgb
+        * albedo;
+    vec3 prefiltered = textureLod(uPrefilteredMap, reflect(-view, normal),
+                                  roughness * MAX_REFLECTION_LOD).rgb;
+    vec2 brdf = texture(uBrdfLut, vec2(NdotV, roughness)).rg;
+    vec3 specular = prefiltered * (fresnel * brdf.x + brdf.y);
+    color += (diffuse + specular) * occlusion;
+#else
+    color += uAmbientLight.rgb * albedo * occlusion;
+#endif
+
+    vec3 emissive = uEmissiveFactor;
+    if (uHasEmissiveMap) {
+        emissive *= texture(uEmissiveMap, vTexCoord).rgb;
+    }
+    color += emissive;
+
+    FragColor = vec4(color, uAlphaMode == 2 ? baseColor.a : 1.0);
+}";
+
+// Standard PBR program, shared by the materials created with PbrMaterial::to_material. Reads the
+// lights from the Lights uniform block. With use_environment the ambient light comes from the
+// maps bound by Environment::bind, otherwise it is the ambient colour of the Lights
+pub fn new_program(use_environment: bool) -> Program {
+    let defines = match use_environment {
+        true => format!(
+            "#define USE_ENVIRONMENT\nconst float MAX_REFLECTION_LOD = {}.0;",
+            PREFILTERED_MAP_MIP_LEVELS - 1
+        ),
+        false => String::new(),
+    };
+
+    let program = light::new_lit_program(&defines, FRAGMENT_SHADER_SRC);
+
+    if use_environment {
+        program.set_uniform_1i(IRRADIANCE_MAP_UNIFORM, IRRADIANCE_MAP_SLOT as i32);
+        program.set_uniform_1i(PREFILTERED_MAP_UNIFORM, PREFILTERED_MAP_SLOT as i32);
+        program.set_uniform_1i(BRDF_LUT_UNIFORM, BRDF_LUT_SLOT as i32);
+    }
+    program
+}
+
+// Parameters of the standard PBR program, with the meaning of the glTF 2.0 metallic-roughness
+// material. The maps multiply the factors; colour maps should be sRGB textures
+#[derive(Clone)]
+pub struct PbrMaterial {
+    pub base_color: glm::Vec4,
+    pub base_color_map: Option<Rc<Texture>>,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub metallic_roughness_map: Option<Rc<Texture>>,
+    pub normal_map: Option<Rc<Texture>>,
+    pub normal_scale: f32,
+    pub occlusion_map: Option<Rc<Texture>>,
+    pub occlusion_strength: f32,
+    pub emissive: glm::Vec3,
+    pub emissive_map: Option<Rc<Texture>>,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+}
+
+impl Default for PbrMaterial {
+    // White dielectric
+    fn default() -> Self {
+        Self {
+            base_color: glm::vec4(1.0, 1.0, 1.0, 1.0),
+            base_color_map: None,
+            metallic: 0.0,
+            roughness: 0.5,
+            metallic_roughness_map: None,
+            normal_map: None,
+            normal_scale: 1.0,
+            occlusion_map: None,
+            occlusion_strength: 1.0,
+            emissive: glm::Vec3::zeros(),
+            emissive_map: None,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+        }
+    }
+}
+
+impl PbrMaterial {
+    pub fn from_gltf(material: &GltfMaterial) -> Self {
+        Self {
+            base_color: material.base_color_factor,
+            base_color_map: material.base_color_map.clone(),
+            metallic: material.metallic_factor,
+            roughness: material.roughness_factor,
+            metallic_roughness_map: material.metallic_roughness_map.clone(),
+            normal_map: material.normal_map.clone(),
+            normal_scale: material.normal_scale,
+            occlusion_map: material.occlusion_map.clone(),
+            occlusion_strength: material.occlusion_strength,
+            emissive: material.emissive_factor,
+            emissive_map: material.emissive_map.clone(),
+            alpha_mode: material.alpha_mode,
+            double_sided: material.double_sided,
+        }
+    }
+
+    // The textures must use the *_MAP_UNIFORM names and *_MAP_SLOT slots
+    pub fn to_material(&self, program: Rc<Program>) -> Material {
+        let mut material = Material::new(program);
+        material.set_vec4("uBaseColorFactor", self.base_color);
+        material.set_float("uMetallicFactor", self.metallic);
+        material.set_float("uRoughnessFactor", self.roughness);
+        material.set_float("uNormalScale", self.normal_scale);
+        material.set_float("uOcclusionStrength", self.occlusion_strength);
+        material.set_vec3("uEmissiveFactor", self.emissive);
+
+        let (alpha_mode, alpha_cutoff) = match self.alpha_mode {
+            AlphaMode::Opaque => (0, 0.0),
+            AlphaMode::Mask(cutoff) => (1, cutoff),
+            AlphaMode::Blend => (2, 0.0),
+        };
+        material.set_int("uAlphaMode", alpha_mode);
+        material.set_float("uAlphaCutoff", alpha_cutoff);
+
+        let maps = [
+            (
+                "uHasBaseColorMap",
+                &self.base_color_map,
+                BASE_COLOR_MAP_SLOT,
+            ),
+            (
+                "uHasMetallicRoughnessMap",
+                &self.metallic_roughness_map,
+                METALLIC_ROUGHNESS_MAP_SLOT,
+            ),
+            ("uHasNormalMap", &self.normal_map, NORMAL_MAP_SLOT),
+            ("uHasOcclusionMap", &self.occlusion_map, OCCLUSION_MAP_SLOT),
+            ("uHasEmissiveMap", &self.emissive_map, EMISSIVE_MAP_SLOT),
+        ];
+        for (has_map_uniform, map, slot) in maps {
+            material.set_int(has_map_uniform, map.is_some() as i32);
+            if let Some(texture) = map {
+                assert_eq!(texture.get_slot(), slot, "Wrong texture slot");
+                material.set_texture(texture.clone());
+            }
+        }
+
+        if self.alpha_mode == AlphaMode::Blend {
+            material.render_state = RenderState::transparent_3d();
+        }
+        if self.double_sided {
+            material.render_state.cull_face = CullFace::None;
+        }
+        material
+    }
+}
@@ -0,0 +1,145 @@
+use std::rc::Rc;
+
+use nalgebra_glm as glm;
+
+use crate::light;
+use crate::material::Material;
+use crate::obj_loader::ObjMaterial;
+use crate::program::Program;
+use crate::render_state::RenderState;
+use crate::texture::Texture;
+
+pub const DIFFUSE_MAP_UNIFORM: &str = "uDiffuseMap";
+pub const DIFFUSE_MAP_SLOT: u32 = 0;
+pub const SPECULAR_MAP_UNIFORM: &str = "uSpecularMap";
+pub const SPECULAR_MAP_SLOT: u32 = 1;
+pub const NORMAL_MAP_UNIFORM: &str = "uNormalMap";
+pub const NORMAL_MAP_SLOT: u32 = 2;
+
+// Appended to the shared lit fragment shader source, see light::new_lit_program
+const FRAGMENT_SHADER_SRC: &str = "
+out vec4 FragColor;
+
+uniform vec3 uAmbient;
+uniform vec3 uDiffuse;
+uniform vec3 uSpecular;
+uniform float uShininess;
+uniform float uOpacity;
+// Blinn-Phong (half vector) instead of Phong (reflection vector) specular highlights
+uniform bool uBlinn;
+
+uniform bool uHasDiffuseMap;
+uniform bool uHasSpecularMap;
+uniform sampler2D uDiffuseMap;
+uniform sampler2D uSpecularMap;
+
+vec3 shade(Surface surface, vec3 lightDirection, vec3 radiance) {
+    float diffuseFactor = max(dot(surface.normal, lightDirection), 0.0);
+    float specularFactor = 0.0;
+    if (diffuseFactor > 0.0) {
+        float cosAngle = uBlinn
+            ? dot(surface.normal, normalize(lightDirection + surface.view))
+            : dot(surface.view, reflect(-lightDirection, surface.normal));
+        specularFactor = pow(max(cosAngle, 0.0), uShininess);
+    }
+    return radiance * (surface.color * diffuseFactor + surface.specular * specularFactor);
+}
+
+void main() {
+    vec4 diffuseSample = uHasDiffuseMap ? texture(uDiffuseMap, vTexCoord) : vec4(1.0);
+    vec3 diffuse = uDiffuse * diffuseSample.rgb;
+    vec3 specular = uSpecular;
+    if (uHasSpecularMap) {
+        specular *= texture(uSpecularMap, vTexCoord).rgb;
+    }
+
+    // metallic and roughness are not used
+    Surface surface = Surface(vWorldPos, getNormal(1.0), normalize(uViewPosition.xyz - vWorldPos),
+                              diffuse, specular, 0.0, 1.0);
+    vec3 color = uAmbientLight.rgb * uAmbient * diffuse + shadeLights(surface);
+
+    FragColor = vec4(color, uOpacity * diffuseSample.a);
+}";
+
+// Standard lit program, shared by the materials created with PhongMaterial::to_material. Reads
+// the lights from the Lights uniform block, see Lights::update
+pub fn new_program() -> Program {
+    light::new_lit_program("", FRAGMENT_SHADER_SRC)
+}
+
+// Parameters of the standard lit program. The diffuse and specular maps multiply the colours.
+// Normal maps store tangent-space normals and need meshes with tangents
+#[derive(Clone)]
+pub struct PhongMaterial {
+    pub ambient: glm::Vec3,
+    pub diffuse: glm::Vec3,
+    pub specular: glm::Vec3,
+    pub shininess: f32,
+    // 1 is opaque. Translucent materials are alpha blended without depth writes
+    pub opacity: f32,
+    pub blinn: bool,
+    pub diffuse_map: Option<Rc<Texture>>,
+    pub specular_map: Option<Rc<Texture>>,
+    pub normal_map: Option<Rc<Texture>>,
+}
+
+impl Default for PhongMaterial {
+    fn default() -> Self {
+        Self {
+            ambient: glm::vec3(0.1, 0.1, 0.1),
+            diffuse: glm::vec3(0.8, 0.8, 0.8),
+            specular: glm::vec3(0.5, 0.5, 0.5),
+            shininess: 32.0,
+            opacity: 1.0,
+            blinn: true,
+            diffuse_map: None,
+            specular_map: None,
+            normal_map: None,
+        }
+    }
+}
+
+impl PhongMaterial {
+    pub fn from_obj(material: &ObjMaterial) -> Self {
+        Self {
+            ambient: material.ambient,
+            diffuse: material.diffuse,
+            specular: material.specular,
+            shininess: material.shininess,
+            opacity: material.opacity,
+            diffuse_map: material.diffuse_map.clone(),
+            specular_map: material.specular_map.clone(),
+            normal_map: material.normal_map.clone(),
+            ..Self::default()
+        }
+    }
+
+    // The textures must use the *_MAP_UNIFORM names and *_MAP_SLOT slots
+    pub fn to_material(&self, program: Rc<Program>) -> Material {
+        let mut material = Material::new(program);
+        material.set_vec3("uAmbient", self.ambient);
+        material.set_vec3("uDiffuse", self.diffuse);
+        material.set_vec3("uSpecular", self.specular);
+        material.set_float("uShininess", self.shininess);
+        material.set_float("uOpacity", self.opacity);
+        material.set_int("uBlinn", self.blinn as i32);
+
+        let maps = [
+            ("uHasDiffuseMap", &self.diffuse_map, DIFFUSE_MAP_SLOT),
+            ("uHasSpecularMap", &self.specular_map, SPECULAR_MAP_SLOT),
+            ("uHasNormalMap", &self.normal_map, NORMAL_MAP_SLOT),
+        ];
+        for (has_map_uniform, map, slot) in maps {
+            material.set_int(has_map_uniform, map.is_some() as i32);
+            if let Some(texture) = map {
+                assert_eq!(texture.get_slot(), slot, "Wrong texture slot");
+                material.set_texture(texture.clone());
+            }
+        }
+
+        if self.opacity < 1.0 {
+            material.render_state = RenderState::transparent_3d();
+        }
+        material
+    }
+}
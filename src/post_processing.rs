@@ -0,0 +1,493 @@
+use std::rc::Rc;
+
+use crate::framebuffer::{ColorAttachment, DepthStencilAttachment, Framebuffer, RenderTarget};
+use crate::program::Program;
+use crate::render_state::RenderState;
+use crate::renderer::Renderer;
+use crate::shader::{Shader, ShaderType};
+use crate::texture::{Texture, TextureFormat};
+use crate::vertex_array::VertexArray;
+use crate::vertex_buffer_layout::{VertexBufferLayout, VertexBufferLayoutType};
+
+// Every pass draws a triangle covering the whole target. The input of the pass is bound to
+// "uInput" (texture slot 0), so effect textures must use slots 1 and above
+const FULLSCREEN_VERTEX_SHADER_SRC: &str = "#version 330 core
+layout (location = 0) in vec2 aPos;
+
+out vec2 vTexCoord;
+
+void main() {
+    gl_Position = vec4(aPos, 0.0, 1.0);
+    vTexCoord = aPos * 0.5 + 0.5;
+}";
+
+const COPY_FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec2 vTexCoord;
+
+uniform sampler2D uInput;
+
+void main() {
+    FragColor = texture(uInput, vTexCoord);
+}";
+
+// ACES filmic curve (Krzysztof Narkowicz's fit)
+const TONEMAPPING_FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec2 vTexCoord;
+
+uniform sampler2D uInput;
+uniform float uExposure;
+uniform float uGamma;
+
+vec3 aces(vec3 x) {
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), 0.0, 1.0);
+}
+
+void main() {
+    vec4 hdr = texture(uInput, vTexCoord);
+    vec3 ldr = aces(hdr.rgb * uExposure);
+    FragColor = vec4(pow(ldr, vec3(1.0 / uGamma)), hdr.a);
+}";
+
+const FXAA_FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec2 vTexCoord;
+
+uniform sampler2D uInput;
+
+const float FXAA_REDUCE_MIN = 1.0 / 128.0;
+const float FXAA_REDUCE_MUL = 1.0 / 8.0;
+const float FXAA_SPAN_MAX = 8.0;
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(uInput, 0));
+    vec3 luma = vec3(0.299, 0.587, 0.114);
+
+    vec4 colorM = texture(uInput, vTexCoord);
+    float lumaNW = dot(texture(uInput, vTexCoord + vec2(-1.0, 1.0) * texel).rgb, luma);
+    float lumaNE = dot(texture(uInput, vTexCoord + vec2(1.0, 1.0) * texel).rgb, luma);
+    float lumaSW = dot(texture(uInput, vTexCoord + vec2(-1.0, -1.0) * texel).rgb, luma);
+    float lumaSE = dot(texture(uInput, vTexCoord + vec2(1.0, -1.0) * texel).rgb, luma);
+    float lumaM = dot(colorM.rgb, luma);
+
+    float lumaMin = min(lumaM, min(min(lumaNW, lumaNE), min(lumaSW, lumaSE)));
+    float lumaMax = max(lumaM, max(max(lumaNW, lumaNE), max(lumaSW, lumaSE)));
+
+    vec2 dir = vec2(
+        -((lumaNW + lumaNE) - (lumaSW + lumaSE)),
+        (lumaNW + lumaSW) - (lumaNE + lumaSE)
+    );
+    float dirReduce = max((lumaNW + lumaNE + lumaSW + lumaSE) * 0.25 * FXAA_REDUCE_MUL, FXAA_REDUCE_MIN);
+    float rcpDirMin = 1.0 / (min(abs(dir.x), abs(dir.y)) + dirReduce);
+    dir = clamp(dir * rcpDirMin, vec2(-FXAA_SPAN_MAX), vec2(FXAA_SPAN_MAX)) * texel;
+
+    vec3 rgbA = 0.5 * (
+        texture(uInput, vTexCoord + dir * (1.0 / 3.0 - 0.5)).rgb +
+        texture(uInput, vTexCoord + dir * (2.0 / 3.0 - 0.5)).rgb);
+    vec3 rgbB = rgbA * 0.5 + 0.25 * (
+        texture(uInput, vTexCoord + dir * -0.5).rgb +
+        texture(uInput, vTexCoord + dir * 0.5).rgb);
+
+    float lumaB = dot(rgbB, luma);
+    FragColor = vec4((lumaB < lumaMin || lumaB > lumaMax) ? rgbA : rgbB, colorM.a);
+}";
+
+// One direction of a separable 9-tap gaussian blur
+const BLUR_FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec2 vTexCoord;
+
+uniform sampler2D uInput;
+uniform vec2 uDirection;
+
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main() {
+    vec2 offset = uDirection / vec2(textureSize(uInput, 0));
+    vec4 result = texture(uInput, vTexCoord) * weights[0];
+    for (int i = 1; i < 5; i++) {
+        result += texture(uInput, vTexCoord + offset * float(i)) * weights[i];
+        result += texture(uInput, vTexCoord - offset * float(i)) * weights[i];
+    }
+    FragColor = result;
+}";
+
+const BLOOM_THRESHOLD_FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec2 vTexCoord;
+
+uniform sampler2D uInput;
+uniform float uThreshold;
+
+void main() {
+    vec3 color = texture(uInput, vTexCoord).rgb;
+    float brightness = max(color.r, max(color.g, color.b));
+    float contribution = max(brightness - uThreshold, 0.0) / max(brightness, 0.0001);
+    FragColor = vec4(color * contribution, 1.0);
+}";
+
+const BLOOM_COMBINE_FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec2 vTexCoord;
+
+uniform sampler2D uInput;
+uniform sampler2D uBloom;
+uniform float uIntensity;
+
+void main() {
+    vec4 color = texture(uInput, vTexCoord);
+    FragColor = vec4(color.rgb + texture(uBloom, vTexCoord).rgb * uIntensity, color.a);
+}";
+
+const VIGNETTE_FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec2 vTexCoord;
+
+uniform sampler2D uInput;
+uniform float uRadius;
+uniform float uSoftness;
+
+void main() {
+    vec4 color = texture(uInput, vTexCoord);
+    float distance = length(vTexCoord - vec2(0.5));
+    float vignette = smoothstep(uRadius, uRadius - uSoftness, distance);
+    FragColor = vec4(color.rgb * vignette, color.a);
+}";
+
+// The LUT is a strip of N slices of NxN pixels (for example, 256x16). Red grows to the right
+// within a slice, green from the top to the bottom of the image and blue with the slice index.
+// Texture::new flips images, so the top of the image is at v = 1
+const COLOR_GRADING_FRAGMENT_SHADER_SRC: &str = "#version 330 core
+out vec4 FragColor;
+
+in vec2 vTexCoord;
+
+uniform sampler2D uInput;
+uniform sampler2D uLut;
+
+void main() {
+    vec4 color = texture(uInput, vTexCoord);
+    vec3 c = clamp(color.rgb, 0.0, 1.0);
+    float size = float(textureSize(uLut, 0).y);
+
+    float blue = c.b * (size - 1.0);
+    float slice0 = floor(blue);
+    float slice1 = min(slice0 + 1.0, size - 1.0);
+
+    float x = (c.r * (size - 1.0) + 0.5) / (size * size);
+    float y = 1.0 - (c.g * (size - 1.0) + 0.5) / size;
+
+    vec3 graded0 = texture(uLut, vec2(x + slice0 / size, y)).rgb;
+    vec3 graded1 = texture(uLut, vec2(x + slice1 / size, y)).rgb;
+    FragColor = vec4(mix(graded0, graded1, blue - slice0), color.a);
+}";
+
+// Shared state available to the effects while the chain runs
+pub struct PostPass<'a> {
+    renderer: &'a Renderer,
+    fullscreen: &'a VertexArray,
+}
+
+impl PostPass<'_> {
+    // Draws a full-screen pass of the program, reading from input, into the target
+    pub fn draw<T: RenderTarget + ?Sized>(&self, program: &Program, input: &Texture, target: &T) {
+        self.renderer.render_to(target, || {
+            input.bind();
+            self.renderer.draw(self.fullscreen, program);
+            input.unbind();
+        });
+    }
+}
+
+pub trait PostEffect {
+    // Reads the input texture and writes the result into the target
+    fn apply(&self, pass: &PostPass, input: &Texture, target: &dyn RenderTarget);
+
+    // Called when the chain is resized, effects with their own targets must resize them
+    fn resize(&mut self, _width: u32, _height: u32) {}
+}
+
+// Intermediate target of the chain. HDR so tonemapping can be done by an effect
+fn new_target(width: u32, height: u32) -> Framebuffer {
+    Framebuffer::new(
+        width,
+        height,
+        &[ColorAttachment::new("uInput", TextureFormat::Rgba16F, 0)],
+        DepthStencilAttachment::None,
+    )
+}
+
+// A single triangle covering the screen avoids the diagonal seam of a quad. Its vertex
+// attribute 0 is the vec2 position in normalized device coordinates
+pub(crate) fn new_fullscreen_triangle() -> VertexArray {
+    let vertices = [-1.0f32, -1.0, 3.0, -1.0, -1.0, 3.0];
+    let indices = [0u32, 1, 2];
+    let layouts = VertexBufferLayout::new(VertexBufferLayoutType::F32, 2, false);
+    VertexArray::with_layout(&vertices, &indices, &layouts)
+}
+
+fn new_fullscreen_program(fragment_shader_src: &str) -> Program {
+    let vertex_shader = Shader::new(ShaderType::VertexShader, FULLSCREEN_VERTEX_SHADER_SRC);
+    let fragment_shader = Shader::new(ShaderType::FragmentShader, fragment_shader_src);
+    Program::new(&vertex_shader, &fragment_shader)
+}
+
+// Single full-screen pass. The fragment shader receives the previous result in
+// "uniform sampler2D uInput", if it uses it, and the texture coordinates in "in vec2 vTexCoord"
+pub struct ShaderEffect {
+    program: Program,
+}
+
+impl ShaderEffect {
+    pub fn new(fragment_shader_src: &str) -> Self {
+        let program = new_fullscreen_program(fragment_shader_src);
+        // Generated effects may ignore the input, the compiler then removes the uniform
+        if program.has_uniform("uInput") {
+            program.set_uniform_1i("uInput", 0);
+        }
+        Self { program }
+    }
+
+    // Maps HDR colours to the [0, 1] range. Use a gamma of 1.0 when the scene is not in linear
+    // space
+    pub fn tonemapping(exposure: f32, gamma: f32) -> Self {
+        let effect = Self::new(TONEMAPPING_FRAGMENT_SHADER_SRC);
+        effect.program.set_uniform_1f("uExposure", exposure);
+        effect.program.set_uniform_1f("uGamma", gamma);
+        effect
+    }
+
+    // Expects colours in the [0, 1] range, run it after tonemapping
+    pub fn fxaa() -> Self {
+        Self::new(FXAA_FRAGMENT_SHADER_SRC)
+    }
+
+    // Darkens the corners. Distances are measured from the centre in texture coordinates
+    pub fn vignette(radius: f32, softness: f32) -> Self {
+        let effect = Self::new(VIGNETTE_FRAGMENT_SHADER_SRC);
+        effect.program.set_uniform_1f("uRadius", radius);
+        effect.program.set_uniform_1f("uSoftness", softness);
+        effect
+    }
+
+    // The texture must be created with the "uLut" uniform name and a slot other than 0
+    pub fn color_grading(lut: Texture) -> Self {
+        assert_ne!(lut.get_slot(), 0);
+        let mut effect = Self::new(COLOR_GRADING_FRAGMENT_SHADER_SRC);
+        effect.program.add_texture2d(lut);
+        effect
+    }
+
+    // To set the uniforms and textures of user-defined effects
+    pub fn get_program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn get_program_mut(&mut self) -> &mut Program {
+        &mut self.program
+    }
+}
+
+impl PostEffect for ShaderEffect {
+    fn apply(&self, pass: &PostPass, input: &Texture, target: &dyn RenderTarget) {
+        pass.draw(&self.program, input, target);
+    }
+}
+
+// Separable gaussian blur. Each iteration runs a horizontal and a vertical pass
+pub struct GaussianBlur {
+    program: Program,
+    iterations: u32,
+    horizontal_target: Framebuffer,
+    vertical_target: Framebuffer,
+}
+
+impl GaussianBlur {
+    pub fn new(width: u32, height: u32, iterations: u32) -> Self {
+        assert!(iterations > 0);
+
+        let program = new_fullscreen_program(BLUR_FRAGMENT_SHADER_SRC);
+        program.set_uniform_1i("uInput", 0);
+
+        Self {
+            program,
+            iterations,
+            horizontal_target: new_target(width, height),
+            vertical_target: new_target(width, height),
+        }
+    }
+}
+
+impl PostEffect for GaussianBlur {
+    fn apply(&self, pass: &PostPass, input: &Texture, target: &dyn RenderTarget) {
+        let horizontal_result = self.horizontal_target.get_color_attachment(0);
+        let vertical_result = self.vertical_target.get_color_attachment(0);
+
+        for iteration in 0..self.iterations {
+            let source = match iteration {
+                0 => input,
+                _ => &vertical_result,
+            };
+
+            self.program.set_uniform_2f("uDirection", 1.0, 0.0);
+            pass.draw(&self.program, source, &self.horizontal_target);
+
+            self.program.set_uniform_2f("uDirection", 0.0, 1.0);
+            match iteration == self.iterations - 1 {
+                true => pass.draw(&self.program, &horizontal_result, target),
+                false => pass.draw(&self.program, &horizontal_result, &self.vertical_target),
+            }
+        }
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.horizontal_target.resize(width, height);
+        self.vertical_target.resize(width, height);
+    }
+}
+
+// Adds a blurred copy of the pixels brighter than the threshold. Run it before tonemapping
+pub struct Bloom {
+    threshold: ShaderEffect,
+    blur: GaussianBlur,
+    combine: ShaderEffect,
+    bright_target: Framebuffer,
+    blurred_target: Framebuffer,
+}
+
+impl Bloom {
+    pub fn new(width: u32, height: u32, threshold: f32, intensity: f32) -> Self {
+        let threshold_effect = ShaderEffect::new(BLOOM_THRESHOLD_FRAGMENT_SHADER_SRC);
+        threshold_effect
+            .program
+            .set_uniform_1f("uThreshold", threshold);
+
+        let blurred_target = Framebuffer::new(
+            width,
+            height,
+            &[ColorAttachment::new("uBloom", TextureFormat::Rgba16F, 1)],
+            DepthStencilAttachment::None,
+        );
+
+        let mut combine = ShaderEffect::new(BLOOM_COMBINE_FRAGMENT_SHADER_SRC);
+        combine.program.set_uniform_1f("uIntensity", intensity);
+        combine
+            .program
+            .add_texture2d(blurred_target.get_color_attachment(0));
+
+        Self {
+            threshold: threshold_effect,
+            blur: GaussianBlur::new(width, height, 4),
+            combine,
+            bright_target: new_target(width, height),
+            blurred_target,
+        }
+    }
+}
+
+impl PostEffect for Bloom {
+    fn apply(&self, pass: &PostPass, input: &Texture, target: &dyn RenderTarget) {
+        self.threshold.apply(pass, input, &self.bright_target);
+
+        let bright: Rc<Texture> = self.bright_target.get_color_attachment(0);
+        self.blur.apply(pass, &bright, &self.blurred_target);
+
+        self.combine.apply(pass, input, target);
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.blur.resize(width, height);
+        self.bright_target.resize(width, height);
+        self.blurred_target.resize(width, height);
+    }
+}
+
+// The scene is rendered into get_scene_target() and then each effect is applied in order. The
+// last effect writes to the final target, the others write to two textures used alternately
+pub struct PostProcessing {
+    scene_target: Framebuffer,
+    ping_pong_targets: [Framebuffer; 2],
+    effects: Vec<Box<dyn PostEffect>>,
+    copy: ShaderEffect,
+    fullscreen: VertexArray,
+}
+
+impl PostProcessing {
+    pub fn new(width: u32, height: u32) -> Self {
+        let scene_target = Framebuffer::new(
+            width,
+            height,
+            &[ColorAttachment::new("uInput", TextureFormat::Rgba16F, 0)],
+            DepthStencilAttachment::Renderbuffer(TextureFormat::Depth24Stencil8),
+        );
+
+        Self {
+            scene_target,
+            ping_pong_targets: [new_target(width, height), new_target(width, height)],
+            effects: Vec::new(),
+            copy: ShaderEffect::new(COPY_FRAGMENT_SHADER_SRC),
+            fullscreen: new_fullscreen_triangle(),
+        }
+    }
+
+    pub fn add_effect(&mut self, effect: impl PostEffect + 'static) {
+        self.effects.push(Box::new(effect));
+    }
+
+    // Render the scene here with Renderer::render_to
+    pub fn get_scene_target(&self) -> &Framebuffer {
+        &self.scene_target
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.scene_target.resize(width, height);
+        self.ping_pong_targets
+            .iter_mut()
+            .for_each(|target| target.resize(width, height));
+        self.effects
+            .iter_mut()
+            .for_each(|effect| effect.resize(width, height));
+    }
+
+    // Runs the chain and writes the result into the target, usually the DefaultFramebuffer
+    pub fn render<T: RenderTarget>(&self, renderer: &Renderer, target: &T) {
+        // Passes overwrite every pixel: no depth test, no blending
+        let render_state = renderer.get_render_state();
+        renderer.set_render_state(&RenderState::gl_initial());
+
+        let pass = PostPass {
+            renderer,
+            fullscreen: &self.fullscreen,
+        };
+
+        let mut input = self.scene_target.get_color_attachment(0);
+        if self.effects.is_empty() {
+            self.copy.apply(&pass, &input, target);
+        }
+
+        for (index, effect) in self.effects.iter().enumerate() {
+            if index == self.effects.len() - 1 {
+                effect.apply(&pass, &input, target);
+            } else {
+                let output = &self.ping_pong_targets[index % 2];
+                effect.apply(&pass, &input, output);
+                input = output.get_color_attachment(0);
+            }
+        }
+
+        renderer.set_render_state(&render_state);
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use nalgebra_glm as glm;
+
+use crate::mesh::{Mesh, MeshVertex};
+
+// Procedural meshes centered on the origin, with normals, texture coordinates and tangents.
+// Triangles are counter-clockwise seen from the outside
+
+#[derive(Default)]
+struct MeshBuilder {
+    vertices: Vec<MeshVertex>,
+    indices: Vec<u32>,
+}
+
+impl MeshBuilder {
+    fn vertex(&mut self, position: glm::Vec3, normal: glm::Vec3, tex_coords: glm::Vec2) -> u32 {
+        self.vertices.push(MeshVertex {
+            normal,
+            ..MeshVertex::new(position, tex_coords)
+        });
+        self.vertices.len() as u32 - 1
+    }
+
+    fn triangle(&mut self, a: u32, b: u32, c: u32) {
+        self.indices.extend([a, b, c]);
+    }
+
+    // Corners in counter-clockwise order
+    fn quad(&mut self, a: u32, b: u32, c: u32, d: u32) {
+        self.triangle(a, b, c);
+        self.triangle(a, c, d);
+    }
+
+    // Grid of (columns + 1) x (rows + 1) vertices, row by row, joined with quads
+    fn grid(&mut self, first: u32, columns: u32, rows: u32) {
+        for row in 0..rows {
+            for column in 0..columns {
+                let a = first + row * (columns + 1) + column;
+                let b = a + columns + 1;
+                self.quad(a, b, b + 1, a + 1);
+            }
+        }
+    }
+
+    // Surface of revolution around the Y axis. Each profile point is (angle from +Y, height
+    // offset, v texture coordinate) of a ring of the given radius
+    fn revolve(&mut self, radius: f32, segments: u32, profile: &[(f32, f32, f32)]) {
+        let first = self.vertices.len() as u32;
+        for &(phi, offset, v) in profile {
+            for segment in 0..=segments {
+                let u = segment as f32 / segments as f32;
+                let theta = u * TAU;
+                let normal = glm::vec3(phi.sin() * theta.sin(), phi.cos(), phi.sin() * theta.cos());
+                let position = normal * radius + glm::vec3(0.0, offset, 0.0);
+                self.vertex(position, normal, glm::vec2(u, v));
+            }
+        }
+
+        let rows = profile.len() as u32 - 1;
+        for row in 0..rows {
+            for segment in 0..segments {
+                let a = first + row * (segments + 1) + segment;
+                let b = a + segments + 1;
+                // Skip the degenerate triangles at the poles
+                if row != rows - 1 {
+                    self.triangle(a, b, b + 1);
+                }
+                if row != 0 {
+                    self.triangle(a, b + 1, a + 1);
+                }
+            }
+        }
+    }
+
+    // Disc facing up or down, at the given height
+    fn cap(&mut self, radius: f32, y: f32, segments: u32, up: bool) {
+        let normal = match up {
+            true => glm::Vec3::y(),
+            false => -glm::Vec3::y(),
+        };
+        let center = self.vertex(glm::vec3(0.0, y, 0.0), normal, glm::vec2(0.5, 0.5));
+        for segment in 0..=segments {
+            let theta = segment as f32 / segments as f32 * TAU;
+            let (sin, cos) = theta.sin_cos();
+            self.vertex(
+                glm::vec3(radius * sin, y, radius * cos),
+                normal,
+                glm::vec2(0.5 + 0.5 * sin, 0.5 + 0.5 * cos),
+            );
+        }
+        for segment in 0..segments {
+            let ring = center + 1 + segment;
+            match up {
+                true => self.triangle(center, ring, ring + 1),
+                false => self.triangle(center, ring + 1, ring),
+            }
+        }
+    }
+
+    fn build(self) -> Mesh {
+        let mut mesh = Mesh::new(self.vertices, self.indices);
+        mesh.compute_tangents();
+        mesh
+    }
+}
+
+// In the XY plane, facing +Z
+pub fn quad(width: f32, height: f32) -> Mesh {
+    let mut builder = MeshBuilder::default();
+    let (x, y) = (width / 2.0, height / 2.0);
+    let normal = glm::Vec3::z();
+    let a = builder.vertex(glm::vec3(-x, -y, 0.0), normal, glm::vec2(0.0, 0.0));
+    let b = builder.vertex(glm::vec3(x, -y, 0.0), normal, glm::vec2(1.0, 0.0));
+    let c = builder.vertex(glm::vec3(x, y, 0.0), normal, glm::vec2(1.0, 1.0));
+    let d = builder.vertex(glm::vec3(-x, y, 0.0), normal, glm::vec2(0.0, 1.0));
+    builder.quad(a, b, c, d);
+    builder.build()
+}
+
+// In the XZ plane, facing +Y, with the given number of quads along each side
+pub fn plane(width: f32, depth: f32, subdivisions_x: u32, subdivisions_z: u32) -> Mesh {
+    assert!(subdivisions_x > 0 && subdivisions_z > 0);
+
+    let mut builder = MeshBuilder::default();
+    for row in 0..=subdivisions_z {
+        for column in 0..=subdivisions_x {
+            let u = column as f32 / subdivisions_x as f32;
+            let v = row as f32 / subdivisions_z as f32;
+            builder.vertex(
+                glm::vec3((u - 0.5) * width, 0.0, (v - 0.5) * depth),
+                glm::Vec3::y(),
+                glm::vec2(u, 1.0 - v),
+            );
+        }
+    }
+    builder.grid(0, subdivisions_x, subdivisions_z);
+    builder.build()
+}
+
+// Each face has its own vertices for flat normals and a full texture
+pub fn cube(size: f32) -> Mesh {
+    // (normal, u axis, v axis) with u x v = normal
+    let faces = [
+        (glm::Vec3::x(), -glm::Vec3::z(), glm::Vec3::y()),
+        (-glm::Vec3::x(), glm::Vec3::z(), glm::Vec3::y()),
+        (glm::Vec3::y(), glm::Vec3::x(), -glm::Vec3::z()),
+        (-glm::Vec3::y(), glm::Vec3::x(), glm::Vec3::z()),
+        (glm::Vec3::z(), glm::Vec3::x(), glm::Vec3::y()),
+        (-glm::Vec3::z(), -glm::Vec3::x(), glm::Vec3::y()),
+    ];
+
+    let mut builder = MeshBuilder::default();
+    let half = size / 2.0;
+    for (normal, u_axis, v_axis) in faces {
+        let corners = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].map(|(u, v)| {
+            let position = (normal + u_axis * (u * 2.0 - 1.0) + v_axis * (v * 2.0 - 1.0)) * half;
+            builder.vertex(position, normal, glm::vec2(u, v))
+        });
+        builder.quad(corners[0], corners[1], corners[2], corners[3]);
+    }
+    builder.build()
+}
+
+// segments around the Y axis, rings from pole to pole
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> Mesh {
+    assert!(segments >= 3 && rings >= 2);
+
+    let profile: Vec<(f32, f32, f32)> = (0..=rings)
+        .map(|ring| {
+            let t = ring as f32 / rings as f32;
+            (t * PI, 0.0, 1.0 - t)
+        })
+        .collect();
+
+    let mut builder = MeshBuilder::default();
+    builder.revolve(radius, segments, &profile);
+    builder.build()
+}
+
+// Subdivided icosahedron, the triangles have about the same size everywhere. 0 subdivisions give
+// 20 triangles, each one multiplies them by 4
+pub fn icosphere(radius: f32, subdivisions: u32) -> Mesh {
+    let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+    let mut positions: Vec<glm::Vec3> = [
+        (-1.0, t, 0.0),
+        (1.0, t, 0.0),
+        (-1.0, -t, 0.0),
+        (1.0, -t, 0.0),
+        (0.0, -1.0, t),
+        (0.0, 1.0, t),
+        (0.0, -1.0, -t),
+        (0.0, 1.0, -t),
+        (t, 0.0, -1.0),
+        (t, 0.0, 1.0),
+        (-t, 0.0, -1.0),
+        (-t, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|&(x, y, z)| glm::vec3(x, y, z).normalize())
+    .collect();
+
+    #[rustfmt::skip]
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        // Edges are shared by two triangles, create their midpoint once
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                positions.push(((positions[a as usize] + positions[b as usize]) / 2.0).normalize());
+                positions.len() as u32 - 1
+            })
+        };
+
+        triangles = triangles
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    // Spherical texture coordinates, u = 0.5 at +Z, the seam is at -Z
+    let tex_coords = |p: &glm::Vec3| {
+        glm::vec2(
+            0.5 + f32::atan2(p.x, p.z) / TAU,
+            0.5 + p.y.clamp(-1.0, 1.0).asin() / PI,
+        )
+    };
+
+    let mut builder = MeshBuilder::default();
+    for position in &positions {
+        builder.vertex(position * radius, *position, tex_coords(position));
+    }
+
+    // Triangles crossing the texture seam get u close to 0 on one side and close to 1 on the
+    // other. Duplicate their vertices on the low side with u + 1
+    let mut seam_vertices: HashMap<u32, u32> = HashMap::new();
+    for triangle in triangles {
+        let us = triangle.map(|index| builder.vertices[index as usize].tex_coords.x);
+        let crosses_seam = us.iter().fold(0.0f32, |a, &b| a.max(b))
+            - us.iter().fold(1.0f32, |a, &b| a.min(b))
+            > 0.5;
+
+        let [a, b, c] = triangle.map(|index| {
+            if !crosses_seam || builder.vertices[index as usize].tex_coords.x >= 0.5 {
+                return index;
+            }
+            *seam_vertices.entry(index).or_insert_with(|| {
+                let mut vertex = builder.vertices[index as usize];
+                vertex.tex_coords.x += 1.0;
+                builder.vertices.push(vertex);
+                builder.vertices.len() as u32 - 1
+            })
+        });
+        builder.triangle(a, b, c);
+    }
+    builder.build()
+}
+
+// Along the Y axis, with caps
+pub fn cylinder(radius: f32, height: f32, segments: u32) -> Mesh {
+    assert!(segments >= 3);
+
+    let mut builder = MeshBuilder::default();
+    let half = height / 2.0;
+    // Top row first, so that the grid faces outwards
+    for y in [half, -half] {
+        for segment in 0..=segments {
+            let u = segment as f32 / segments as f32;
+            let (sin, cos) = (u * TAU).sin_cos();
+            let normal = glm::vec3(sin, 0.0, cos);
+            builder.vertex(
+                normal * radius + glm::vec3(0.0, y, 0.0),
+                normal,
+                glm::vec2(u, (y + half) / height),
+            );
+        }
+    }
+    builder.grid(0, segments, 1);
+
+    builder.cap(radius, half, segments, true);
+    builder.cap(radius, -half, segments, false);
+    builder.build()
+}
+
+// Along the Y axis, the tip at the top, with a base cap
+pub fn cone(radius: f32, height: f32, segments: u32) -> Mesh {
+    assert!(segments >= 3);
+
+    let mut builder = MeshBuilder::default();
+    let half = height / 2.0;
+    let normal_at =
+        |theta: f32| glm::vec3(theta.sin() * height, radius, theta.cos() * height).normalize();
+
+    for segment in 0..segments {
+        let u0 = segment as f32 / segments as f32;
+        let u1 = (segment + 1) as f32 / segments as f32;
+        let (theta0, theta1) = (u0 * TAU, u1 * TAU);
+        let base = |theta: f32| glm::vec3(theta.sin() * radius, -half, theta.cos() * radius);
+
+        let a = builder.vertex(base(theta0), normal_at(theta0), glm::vec2(u0, 0.0));
+        let b = builder.vertex(base(theta1), normal_at(theta1), glm::vec2(u1, 0.0));
+        // One tip vertex per triangle, with the normal of the middle of the side
+        let middle = (theta0 + theta1) / 2.0;
+        let tip = builder.vertex(
+            glm::vec3(0.0, half, 0.0),
+            normal_at(middle),
+            glm::vec2((u0 + u1) / 2.0, 1.0),
+        );
+        builder.triangle(a, b, tip);
+    }
+
+    builder.cap(radius, -half, segments, false);
+    builder.build()
+}
+
+// Around the Y axis. major_radius: from the center to the middle of the tube, minor_radius:
+// radius of the tube
+pub fn torus(
+    major_radius: f32,
+    minor_radius: f32,
+    major_segments: u32,
+    minor_segments: u32,
+) -> Mesh {
+    assert!(major_segments >= 3 && minor_segments >= 3);
+
+    let mut builder = MeshBuilder::default();
+    for i in 0..=major_segments {
+        let u = i as f32 / major_segments as f32;
+        let (sin_theta, cos_theta) = (u * TAU).sin_cos();
+        let radial = glm::vec3(sin_theta, 0.0, cos_theta);
+
+        for j in 0..=minor_segments {
+            let v = j as f32 / minor_segments as f32;
+            let (sin_phi, cos_phi) = (v * TAU).sin_cos();
+            let normal = radial * cos_phi + glm::Vec3::y() * sin_phi;
+            builder.vertex(
+                radial * major_radius + normal * minor_radius,
+                normal,
+                glm::vec2(u, v),
+            );
+        }
+    }
+    // Rows go around the Y axis, columns around the tube
+    builder.grid(0, minor_segments, major_segments);
+    builder.build()
+}
+
+// Cylinder with hemispheres at the ends, along the Y axis. height: length of the cylinder part,
+// the total height is height + 2 * radius. rings: per hemisphere
+pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> Mesh {
+    assert!(segments >= 3 && rings >= 1);
+
+    let half = height / 2.0;
+    let total_height = height + 2.0 * radius;
+    let v_at = |phi: f32, offset: f32| 0.5 + (radius * phi.cos() + offset) / total_height;
+
+    // The two equator rings are the ends of the cylinder part
+    let profile: Vec<(f32, f32, f32)> = (0..=rings)
+        .map(|ring| (ring as f32 / rings as f32 * FRAC_PI_2, half))
+        .chain((0..=rings).map(|ring| (FRAC_PI_2 + ring as f32 / rings as f32 * FRAC_PI_2, -half)))
+        .map(|(phi, offset)| (phi, offset, v_at(phi, offset)))
+        .collect();
+
+    let mut builder = MeshBuilder::default();
+    builder.revolve(radius, segments, &profile);
+    builder.build()
+}
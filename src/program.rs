@@ -3,23 +3,102 @@ use crate::texture::Texture;
 
 use gl;
 use nalgebra_glm as glm;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::ffi::CString;
+use std::rc::Rc;
+
+// Vertex attribute declared by the vertex shader
+pub struct ProgramAttribute {
+    pub name: String,
+    // First location, matrices and arrays use the next ones too
+    pub location: u32,
+    // For example, gl::FLOAT_VEC3 or gl::INT
+    pub attribute_type: u32,
+    // Number of elements of an array, 1 otherwise
+    pub size: u32,
+    // Constant value used when the vertex array doesn't provide the attribute. Integer
+    // attributes read it converted to integers, matrices read the identity
+    pub default_value: [f32; 4],
+}
+
+impl ProgramAttribute {
+    pub fn is_matrix(&self) -> bool {
+        self.get_num_columns() > 1
+    }
+
+    // Each column of a matrix takes one location
+    fn get_num_columns(&self) -> u32 {
+        match self.attribute_type {
+            gl::FLOAT_MAT2 | gl::FLOAT_MAT2x3 | gl::FLOAT_MAT2x4 => 2,
+            gl::FLOAT_MAT3 | gl::FLOAT_MAT3x2 | gl::FLOAT_MAT3x4 => 3,
+            gl::FLOAT_MAT4 | gl::FLOAT_MAT4x2 | gl::FLOAT_MAT4x3 => 4,
+            _ => 1,
+        }
+    }
+
+    pub fn get_locations(&self) -> std::ops::Range<u32> {
+        self.location..self.location + self.get_num_columns() * self.size
+    }
+
+    // Sets the constant value read at one of the attribute locations. Integer attributes need
+    // the integer functions, glVertexAttrib4f leaves their value undefined
+    fn set_constant_value(&self, location: u32) {
+        let [v0, v1, v2, v3] = match self.is_matrix() {
+            true => {
+                let column = (location - self.location) % self.get_num_columns();
+                let mut value = [0.0; 4];
+                value[column as usize] = 1.0;
+                value
+            }
+            false => self.default_value,
+        };
+
+        unsafe {
+            match self.attribute_type {
+                gl::INT | gl::INT_VEC2 | gl::INT_VEC3 | gl::INT_VEC4 => {
+                    gl::VertexAttribI4i(location, v0 as i32, v1 as i32, v2 as i32, v3 as i32)
+                }
+                gl::UNSIGNED_INT
+                | gl::UNSIGNED_INT_VEC2
+                | gl::UNSIGNED_INT_VEC3
+                | gl::UNSIGNED_INT_VEC4 => {
+                    gl::VertexAttribI4ui(location, v0 as u32, v1 as u32, v2 as u32, v3 as u32)
+                }
+                _ => gl::VertexAttrib4f(location, v0, v1, v2, v3),
+            }
+        }
+    }
+}
 
 pub struct Program {
     id: u32,
-    textures: Vec<Texture>,
+    // Shared because textures can also be owned by a framebuffer
+    textures: Vec<Rc<Texture>>,
+    attributes: Vec<ProgramAttribute>,
+    // Filled on first use, the locations don't change once the program is linked
+    uniform_locations: RefCell<HashMap<String, Option<i32>>>,
 }
 
 impl Program {
     pub fn new(vertex_shader: &Shader, fragment_shader: &Shader) -> Self {
+        Self::link(&[vertex_shader, fragment_shader])
+    }
+
+    pub fn new_compute(compute_shader: &Shader) -> Self {
+        Self::link(&[compute_shader])
+    }
+
+    fn link(shaders: &[&Shader]) -> Self {
         let id = unsafe { gl::CreateProgram() };
         if id == 0 {
             panic!("Error creating shader program object");
         }
 
         unsafe {
-            gl::AttachShader(id, vertex_shader.id);
-            gl::AttachShader(id, fragment_shader.id);
+            shaders
+                .iter()
+                .for_each(|shader| gl::AttachShader(id, shader.id));
             gl::LinkProgram(id);
         }
 
@@ -40,9 +119,53 @@ impl Program {
         Self {
             id,
             textures: Vec::new(),
+            attributes: Self::query_attributes(id),
+            uniform_locations: RefCell::new(HashMap::new()),
         }
     }
 
+    fn query_attributes(id: u32) -> Vec<ProgramAttribute> {
+        let mut num_attributes = 0;
+        unsafe { gl::GetProgramiv(id, gl::ACTIVE_ATTRIBUTES, &mut num_attributes) };
+
+        let mut attributes = Vec::new();
+        for index in 0..num_attributes as u32 {
+            let mut name: [u8; 256] = [0; 256];
+            let mut name_length = 0;
+            let mut size = 0;
+            let mut attribute_type = 0;
+            unsafe {
+                gl::GetActiveAttrib(
+                    id,
+                    index,
+                    name.len() as i32,
+                    &mut name_length,
+                    &mut size,
+                    &mut attribute_type,
+                    name.as_mut_ptr().cast(),
+                );
+            }
+
+            let name = String::from_utf8_lossy(&name[..name_length as usize]).into_owned();
+            let c_name =
+                CString::new(name.as_str()).expect("Error creating CString from attribute name");
+            let location = unsafe { gl::GetAttribLocation(id, c_name.as_ptr()) };
+
+            // Built-in attributes like gl_VertexID don't have a location
+            if location != -1 {
+                attributes.push(ProgramAttribute {
+                    name,
+                    location: location as u32,
+                    attribute_type,
+                    size: size as u32,
+                    default_value: [0.0, 0.0, 0.0, 1.0],
+                });
+            }
+        }
+
+        attributes
+    }
+
     pub fn bind(&self) {
         unsafe { gl::UseProgram(self.id) };
     }
@@ -51,7 +174,59 @@ impl Program {
         unsafe { gl::UseProgram(0) };
     }
 
-    pub fn add_texture2d(&mut self, texture: Texture) {
+    // Runs the compute shader. Use buffer::memory_barrier() before reading its results
+    pub fn dispatch_compute(&self, num_groups_x: u32, num_groups_y: u32, num_groups_z: u32) {
+        self.bind();
+        self.bind_textures();
+        unsafe { gl::DispatchCompute(num_groups_x, num_groups_y, num_groups_z) };
+        self.unbind_textures();
+        self.unbind();
+    }
+
+    pub fn get_attributes(&self) -> &Vec<ProgramAttribute> {
+        &self.attributes
+    }
+
+    pub fn get_attribute_location(&self, attribute_name: &str) -> Option<u32> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.name == attribute_name)
+            .map(|attribute| attribute.location)
+    }
+
+    pub fn set_attribute_default_4f(
+        &mut self,
+        attribute_name: &str,
+        v0: f32,
+        v1: f32,
+        v2: f32,
+        v3: f32,
+    ) {
+        let attribute = self
+            .attributes
+            .iter_mut()
+            .find(|attribute| attribute.name == attribute_name)
+            .expect("Attribute not found in program");
+        assert!(
+            !attribute.is_matrix(),
+            "Matrix attribute {attribute_name} always defaults to the identity"
+        );
+        attribute.default_value = [v0, v1, v2, v3];
+    }
+
+    // Attributes declared by the shader but missing in the vertex array read a constant value.
+    // Constant attribute values are context state, so they must be set before every draw
+    pub fn set_missing_attributes(&self, provided_locations: &[u32]) {
+        for attribute in &self.attributes {
+            attribute
+                .get_locations()
+                .filter(|location| !provided_locations.contains(location))
+                .for_each(|location| attribute.set_constant_value(location));
+        }
+    }
+
+    pub fn add_texture2d(&mut self, texture: impl Into<Rc<Texture>>) {
+        let texture = texture.into();
         let c_uniform_name = CString::new(texture.get_uniform_name())
             .expect("Error creating CString from texture uniform name");
 
@@ -76,29 +251,98 @@ impl Program {
         self.textures.iter().for_each(|texture| texture.unbind());
     }
 
-    pub fn set_uniform_4f(&self, uniform_name: &str, v0: f32, v1: f32, v2: f32, v3: f32) {
+    pub fn has_uniform(&self, uniform_name: &str) -> bool {
+        self.get_uniform_location(uniform_name).is_some()
+    }
+
+    // None if the uniform is not declared, or not used and removed by the compiler
+    pub(crate) fn get_uniform_location(&self, uniform_name: &str) -> Option<i32> {
+        if let Some(location) = self.uniform_locations.borrow().get(uniform_name) {
+            return *location;
+        }
+
         let c_uniform_name =
             CString::new(uniform_name).expect("Error creating CString from uniform name");
+        let location = match unsafe { gl::GetUniformLocation(self.id, c_uniform_name.as_ptr()) } {
+            -1 => None,
+            location => Some(location),
+        };
+        self.uniform_locations
+            .borrow_mut()
+            .insert(String::from(uniform_name), location);
+        location
+    }
 
-        self.bind();
+    // Binding point of a uniform block, the "layout (binding = N)" of OpenGL 4.2 shaders
+    pub fn set_uniform_block_binding(&self, block_name: &str, binding: u32) {
+        let c_block_name =
+            CString::new(block_name).expect("Error creating CString from uniform block name");
         unsafe {
-            let uniform_location = gl::GetUniformLocation(self.id, c_uniform_name.as_ptr());
-            assert_ne!(uniform_location, -1);
-            gl::Uniform4f(uniform_location, v0, v1, v2, v3);
+            let block_index = gl::GetUniformBlockIndex(self.id, c_block_name.as_ptr());
+            assert_ne!(block_index, gl::INVALID_INDEX, "Uniform block not found");
+            gl::UniformBlockBinding(self.id, block_index, binding);
         }
-        self.unbind();
+    }
+
+    pub(crate) fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn set_uniform_1i(&self, uniform_name: &str, v0: i32) {
+        self.set_uniform(uniform_name, |location| unsafe {
+            gl::Uniform1i(location, v0)
+        });
+    }
+
+    pub fn set_uniform_1f(&self, uniform_name: &str, v0: f32) {
+        self.set_uniform(uniform_name, |location| unsafe {
+            gl::Uniform1f(location, v0)
+        });
+    }
+
+    pub fn set_uniform_2f(&self, uniform_name: &str, v0: f32, v1: f32) {
+        self.set_uniform(uniform_name, |location| unsafe {
+            gl::Uniform2f(location, v0, v1)
+        });
+    }
+
+    pub fn set_uniform_3f(&self, uniform_name: &str, v0: f32, v1: f32, v2: f32) {
+        self.set_uniform(uniform_name, |location| unsafe {
+            gl::Uniform3f(location, v0, v1, v2)
+        });
+    }
+
+    pub fn set_uniform_4f(&self, uniform_name: &str, v0: f32, v1: f32, v2: f32, v3: f32) {
+        self.set_uniform(uniform_name, |location| unsafe {
+            gl::Uniform4f(location, v0, v1, v2, v3)
+        });
     }
 
     pub fn set_uniform_mat4(&self, uniform_name: &str, mat4: &glm::Mat4) {
-        let c_uniform_name =
-            CString::new(uniform_name).expect("Error creating CString from uniform name");
+        self.set_uniform(uniform_name, |location| unsafe {
+            gl::UniformMatrix4fv(location, 1, gl::FALSE, mat4.as_ptr())
+        });
+    }
+
+    // uniform_name: name of the array, without [0]
+    pub fn set_uniform_mat4_array(&self, uniform_name: &str, mat4s: &[glm::Mat4]) {
+        self.set_uniform(uniform_name, |location| unsafe {
+            gl::UniformMatrix4fv(
+                location,
+                mat4s.len() as i32,
+                gl::FALSE,
+                mat4s.as_ptr() as *const f32,
+            )
+        });
+    }
+
+    fn set_uniform(&self, uniform_name: &str, set: impl FnOnce(i32)) {
+        let uniform_location = self
+            .get_uniform_location(uniform_name)
+            .unwrap_or_else(|| panic!("Uniform {uniform_name} not found in program"));
 
         self.bind();
-        unsafe {
-            let uniform_location = gl::GetUniformLocation(self.id, c_uniform_name.as_ptr());
-            assert_ne!(uniform_location, -1);
-            gl::UniformMatrix4fv(uniform_location, 1, gl::FALSE, mat4.as_ptr());
-        }
+        set(uniform_location);
         self.unbind();
     }
 }
@@ -0,0 +1,149 @@
+use std::collections::VecDeque;
+
+use gl;
+use image::{imageops, RgbaImage};
+
+use crate::buffer::{Buffer, BufferTarget, BufferUsage};
+use crate::framebuffer::RenderTarget;
+
+// Reads the colour attachment as RGBA8 into pixels: width * height * 4 bytes, or an offset
+// into the bound pixel pack buffer. Multisampled targets can't be read, resolve them first.
+// Float attachments are clamped to [0, 1]
+fn read_into<T: RenderTarget + ?Sized>(target: &T, attachment: u32, pixels: *mut std::ffi::c_void) {
+    assert_eq!(
+        target.get_samples(),
+        0,
+        "Multisampled targets can't be read, resolve them first"
+    );
+
+    let read_buffer = match target.get_framebuffer_id() {
+        // Read before swapping the buffers, the back buffer is undefined afterwards
+        0 => gl::BACK,
+        _ => gl::COLOR_ATTACHMENT0 + attachment,
+    };
+    let (width, height) = target.get_size();
+
+    let mut previous_alignment = 0;
+    unsafe {
+        gl::GetIntegerv(gl::PACK_ALIGNMENT, &mut previous_alignment);
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, target.get_framebuffer_id());
+        gl::ReadBuffer(read_buffer);
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+
+        gl::ReadPixels(
+            0,
+            0,
+            width as i32,
+            height as i32,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels,
+        );
+
+        gl::PixelStorei(gl::PACK_ALIGNMENT, previous_alignment);
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+    }
+}
+
+// OpenGL rows start at the bottom, images at the top. Same flip as Texture::new, reversed
+fn to_image(width: u32, height: u32, pixels: Vec<u8>) -> RgbaImage {
+    let mut image = RgbaImage::from_raw(width, height, pixels).expect("Invalid pixel data size");
+    imageops::flip_vertical_in_place(&mut image);
+    image
+}
+
+// Reads a colour attachment of the target (ignored for the DefaultFramebuffer). Stalls until
+// the GPU finishes rendering, use AsyncReadback to read every frame
+pub fn read_pixels<T: RenderTarget + ?Sized>(target: &T, attachment: u32) -> RgbaImage {
+    let (width, height) = target.get_size();
+
+    let mut pixels = vec![0u8; (width * height * 4) as usize];
+    read_into(target, attachment, pixels.as_mut_ptr().cast());
+
+    to_image(width, height, pixels)
+}
+
+pub fn save_screenshot<T: RenderTarget + ?Sized>(target: &T, path: &str) -> image::ImageResult<()> {
+    read_pixels(target, 0).save(path)
+}
+
+struct PendingRead {
+    buffer: Buffer<u8>,
+    fence: gl::types::GLsync,
+    width: u32,
+    height: u32,
+}
+
+// Reads pixels into pixel buffer objects without waiting for the GPU. Request a read every frame
+// and collect the images a few frames later, once the copies have finished
+#[derive(Default)]
+pub struct AsyncReadback {
+    pending: VecDeque<PendingRead>,
+    // Buffers of received reads, reused by the next requests
+    free_buffers: Vec<Buffer<u8>>,
+}
+
+impl AsyncReadback {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn request<T: RenderTarget + ?Sized>(&mut self, target: &T, attachment: u32) {
+        let (width, height) = target.get_size();
+        let size = (width * height * 4) as usize;
+
+        let buffer = match self.free_buffers.iter().position(|b| b.len() == size) {
+            Some(index) => self.free_buffers.swap_remove(index),
+            None => Buffer::zeroed(BufferTarget::PixelPack, size, BufferUsage::StreamRead),
+        };
+
+        buffer.bind();
+        // With a pixel pack buffer bound the pointer is an offset into the buffer
+        read_into(target, attachment, std::ptr::null_mut());
+        let fence = unsafe { gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0) };
+        buffer.unbind();
+
+        self.pending.push_back(PendingRead {
+            buffer,
+            fence,
+            width,
+            height,
+        });
+    }
+
+    // Returns the oldest requested image if the GPU has finished copying it
+    pub fn try_receive(&mut self) -> Option<RgbaImage> {
+        let oldest = self.pending.front()?;
+        let status = unsafe { gl::ClientWaitSync(oldest.fence, 0, 0) };
+        match status {
+            gl::ALREADY_SIGNALED | gl::CONDITION_SATISFIED => self.receive(),
+            _ => None,
+        }
+    }
+
+    // Waits for the oldest requested image. Useful to flush the pending reads before exiting
+    pub fn receive(&mut self) -> Option<RgbaImage> {
+        let read = self.pending.pop_front()?;
+        unsafe {
+            gl::ClientWaitSync(read.fence, gl::SYNC_FLUSH_COMMANDS_BIT, u64::MAX);
+            gl::DeleteSync(read.fence);
+        }
+
+        let pixels = read.buffer.map_read(|pixels| pixels.to_vec());
+        self.free_buffers.push(read.buffer);
+
+        Some(to_image(read.width, read.height, pixels))
+    }
+
+    pub fn get_num_pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+impl Drop for AsyncReadback {
+    fn drop(&mut self) {
+        self.pending
+            .iter()
+            .for_each(|read| unsafe { gl::DeleteSync(read.fence) });
+    }
+}
@@ -0,0 +1,480 @@
+use gl;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareFunc {
+    Never = gl::NEVER,
+    Less = gl::LESS,
+    Equal = gl::EQUAL,
+    LessEqual = gl::LEQUAL,
+    Greater = gl::GREATER,
+    NotEqual = gl::NOTEQUAL,
+    GreaterEqual = gl::GEQUAL,
+    Always = gl::ALWAYS,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthState {
+    pub test: bool,
+    pub write: bool,
+    pub func: CompareFunc,
+}
+
+impl DepthState {
+    pub fn disabled() -> Self {
+        Self {
+            test: false,
+            write: true,
+            func: CompareFunc::Less,
+        }
+    }
+
+    // Usual configuration for opaque 3D geometry
+    pub fn less() -> Self {
+        Self {
+            test: true,
+            write: true,
+            func: CompareFunc::Less,
+        }
+    }
+
+    // Test against the depth buffer without writing to it, for example, for transparent geometry
+    pub fn read_only() -> Self {
+        Self {
+            test: true,
+            write: false,
+            func: CompareFunc::LessEqual,
+        }
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StencilOp {
+    Keep = gl::KEEP,
+    Zero = gl::ZERO,
+    Replace = gl::REPLACE,
+    Increment = gl::INCR,
+    IncrementWrap = gl::INCR_WRAP,
+    Decrement = gl::DECR,
+    DecrementWrap = gl::DECR_WRAP,
+    Invert = gl::INVERT,
+}
+
+// Applied to both front and back faces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StencilState {
+    pub test: bool,
+    pub func: CompareFunc,
+    pub reference: i32,
+    pub read_mask: u32,
+    pub write_mask: u32,
+    pub stencil_fail: StencilOp,
+    pub depth_fail: StencilOp,
+    pub pass: StencilOp,
+}
+
+impl StencilState {
+    pub fn disabled() -> Self {
+        Self {
+            test: false,
+            func: CompareFunc::Always,
+            reference: 0,
+            read_mask: u32::MAX,
+            write_mask: u32::MAX,
+            stencil_fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            pass: StencilOp::Keep,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CullFace {
+    None,
+    Front,
+    Back,
+    FrontAndBack,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontFace {
+    CounterClockwise = gl::CCW,
+    Clockwise = gl::CW,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolygonMode {
+    Fill = gl::FILL,
+    Line = gl::LINE,
+    Point = gl::POINT,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendEquation {
+    Add = gl::FUNC_ADD,
+    Subtract = gl::FUNC_SUBTRACT,
+    ReverseSubtract = gl::FUNC_REVERSE_SUBTRACT,
+    Min = gl::MIN,
+    Max = gl::MAX,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendFactor {
+    Zero = gl::ZERO,
+    One = gl::ONE,
+    SrcColor = gl::SRC_COLOR,
+    OneMinusSrcColor = gl::ONE_MINUS_SRC_COLOR,
+    DstColor = gl::DST_COLOR,
+    OneMinusDstColor = gl::ONE_MINUS_DST_COLOR,
+    SrcAlpha = gl::SRC_ALPHA,
+    OneMinusSrcAlpha = gl::ONE_MINUS_SRC_ALPHA,
+    DstAlpha = gl::DST_ALPHA,
+    OneMinusDstAlpha = gl::ONE_MINUS_DST_ALPHA,
+    ConstantColor = gl::CONSTANT_COLOR,
+    OneMinusConstantColor = gl::ONE_MINUS_CONSTANT_COLOR,
+    ConstantAlpha = gl::CONSTANT_ALPHA,
+    OneMinusConstantAlpha = gl::ONE_MINUS_CONSTANT_ALPHA,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlendState {
+    pub enabled: bool,
+    pub color_equation: BlendEquation,
+    pub alpha_equation: BlendEquation,
+    pub src_color: BlendFactor,
+    pub dst_color: BlendFactor,
+    pub src_alpha: BlendFactor,
+    pub dst_alpha: BlendFactor,
+}
+
+impl BlendState {
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::factors(BlendFactor::One, BlendFactor::Zero)
+        }
+    }
+
+    // Colours with straight (non-premultiplied) alpha
+    pub fn alpha() -> Self {
+        Self::factors(BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha)
+    }
+
+    // Colours already multiplied by their alpha
+    pub fn premultiplied_alpha() -> Self {
+        Self::factors(BlendFactor::One, BlendFactor::OneMinusSrcAlpha)
+    }
+
+    // Light accumulation, particles...
+    pub fn additive() -> Self {
+        Self::factors(BlendFactor::One, BlendFactor::One)
+    }
+
+    fn factors(src: BlendFactor, dst: BlendFactor) -> Self {
+        Self {
+            enabled: true,
+            color_equation: BlendEquation::Add,
+            alpha_equation: BlendEquation::Add,
+            src_color: src,
+            dst_color: dst,
+            src_alpha: src,
+            dst_alpha: dst,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    // Width / height, for projection matrices
+    pub fn get_aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height.max(1) as f32
+    }
+
+    // Splits the rectangle in columns x rows cells of the same size, row by row from the top
+    // left. Useful for split-screen layouts
+    pub fn split(&self, columns: i32, rows: i32) -> Vec<Rect> {
+        assert!(columns > 0 && rows > 0);
+
+        let width = self.width / columns;
+        let height = self.height / rows;
+        (0..rows)
+            .flat_map(|row| {
+                (0..columns).map(move |column| {
+                    // OpenGL y goes up, the first row is the top one
+                    Rect::new(
+                        self.x + column * width,
+                        self.y + (rows - 1 - row) * height,
+                        width,
+                        height,
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+// Fixed-function configuration used by draw calls. Default matches the state set up by
+// Renderer::default(): alpha blending and no depth test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderState {
+    pub depth: DepthState,
+    pub stencil: StencilState,
+    pub cull_face: CullFace,
+    pub front_face: FrontFace,
+    pub polygon_mode: PolygonMode,
+    pub blend: BlendState,
+    // Red, green, blue and alpha
+    pub color_mask: [bool; 4],
+    // None disables the scissor test
+    pub scissor: Option<Rect>,
+}
+
+impl Default for RenderState {
+    fn default() -> Self {
+        Self {
+            blend: BlendState::alpha(),
+            ..Self::gl_initial()
+        }
+    }
+}
+
+impl RenderState {
+    // Opaque 3D geometry: depth test and back-face culling
+    pub fn opaque_3d() -> Self {
+        Self {
+            depth: DepthState::less(),
+            cull_face: CullFace::Back,
+            blend: BlendState::disabled(),
+            ..Self::gl_initial()
+        }
+    }
+
+    // Transparent 3D geometry, drawn back to front after the opaque one: depth test without depth
+    // writes and alpha blending
+    pub fn transparent_3d() -> Self {
+        Self {
+            depth: DepthState::read_only(),
+            blend: BlendState::alpha(),
+            ..Self::opaque_3d()
+        }
+    }
+
+    // State of a newly created OpenGL context
+    pub(crate) fn gl_initial() -> Self {
+        Self {
+            depth: DepthState::disabled(),
+            stencil: StencilState::disabled(),
+            cull_face: CullFace::None,
+            front_face: FrontFace::CounterClockwise,
+            polygon_mode: PolygonMode::Fill,
+            blend: BlendState::disabled(),
+            color_mask: [true; 4],
+            scissor: None,
+        }
+    }
+
+    // Emits only the OpenGL calls needed to go from the current state to self
+    pub(crate) fn apply(&self, current: &RenderState) {
+        self.apply_depth(&current.depth);
+        self.apply_stencil(&current.stencil);
+        self.apply_rasterizer(current);
+        self.apply_blend(&current.blend);
+
+        if self.color_mask != current.color_mask {
+            let [r, g, b, a] = self.color_mask.map(|mask| mask as u8);
+            unsafe { gl::ColorMask(r, g, b, a) };
+        }
+
+        if self.scissor != current.scissor {
+            set_capability(gl::SCISSOR_TEST, self.scissor.is_some());
+            if let Some(rect) = self.scissor {
+                unsafe { gl::Scissor(rect.x, rect.y, rect.width, rect.height) };
+            }
+        }
+    }
+
+    fn apply_depth(&self, current: &DepthState) {
+        let depth = &self.depth;
+        if depth.test != current.test {
+            set_capability(gl::DEPTH_TEST, depth.test);
+        }
+        if depth.write != current.write {
+            unsafe { gl::DepthMask(depth.write as u8) };
+        }
+        if depth.func != current.func {
+            unsafe { gl::DepthFunc(depth.func as u32) };
+        }
+    }
+
+    fn apply_stencil(&self, current: &StencilState) {
+        let stencil = &self.stencil;
+        if stencil.test != current.test {
+            set_capability(gl::STENCIL_TEST, stencil.test);
+        }
+        if (stencil.func, stencil.reference, stencil.read_mask)
+            != (current.func, current.reference, current.read_mask)
+        {
+            unsafe { gl::StencilFunc(stencil.func as u32, stencil.reference, stencil.read_mask) };
+        }
+        if stencil.write_mask != current.write_mask {
+            unsafe { gl::StencilMask(stencil.write_mask) };
+        }
+        if (stencil.stencil_fail, stencil.depth_fail, stencil.pass)
+            != (current.stencil_fail, current.depth_fail, current.pass)
+        {
+            unsafe {
+                gl::StencilOp(
+                    stencil.stencil_fail as u32,
+                    stencil.depth_fail as u32,
+                    stencil.pass as u32,
+                )
+            };
+        }
+    }
+
+    fn apply_rasterizer(&self, current: &RenderState) {
+        if self.cull_face != current.cull_face {
+            let face = match self.cull_face {
+                CullFace::None => None,
+                CullFace::Front => Some(gl::FRONT),
+                CullFace::Back => Some(gl::BACK),
+                CullFace::FrontAndBack => Some(gl::FRONT_AND_BACK),
+            };
+            set_capability(gl::CULL_FACE, face.is_some());
+            if let Some(face) = face {
+                unsafe { gl::CullFace(face) };
+            }
+        }
+        if self.front_face != current.front_face {
+            unsafe { gl::FrontFace(self.front_face as u32) };
+        }
+        if self.polygon_mode != current.polygon_mode {
+            // Core profiles only accept GL_FRONT_AND_BACK
+            unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, self.polygon_mode as u32) };
+        }
+    }
+
+    fn apply_blend(&self, current: &BlendState) {
+        let blend = &self.blend;
+        if blend.enabled != current.enabled {
+            set_capability(gl::BLEND, blend.enabled);
+        }
+        if (blend.color_equation, blend.alpha_equation)
+            != (current.color_equation, current.alpha_equation)
+        {
+            unsafe {
+                gl::BlendEquationSeparate(blend.color_equation as u32, blend.alpha_equation as u32)
+            };
+        }
+        if (
+            blend.src_color,
+            blend.dst_color,
+            blend.src_alpha,
+            blend.dst_alpha,
+        ) != (
+            current.src_color,
+            current.dst_color,
+            current.src_alpha,
+            current.dst_alpha,
+        ) {
+            unsafe {
+                gl::BlendFuncSeparate(
+                    blend.src_color as u32,
+                    blend.dst_color as u32,
+                    blend.src_alpha as u32,
+                    blend.dst_alpha as u32,
+                )
+            };
+        }
+    }
+}
+
+fn set_capability(capability: u32, enabled: bool) {
+    unsafe {
+        match enabled {
+            true => gl::Enable(capability),
+            false => gl::Disable(capability),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_single_cell() {
+        let rect = Rect::new(10, 20, 300, 200);
+        assert_eq!(rect.split(1, 1), vec![rect]);
+    }
+
+    #[test]
+    fn split_columns() {
+        let rect = Rect::new(0, 0, 800, 600);
+        assert_eq!(
+            rect.split(2, 1),
+            vec![Rect::new(0, 0, 400, 600), Rect::new(400, 0, 400, 600)]
+        );
+    }
+
+    #[test]
+    fn split_rows_from_the_top() {
+        let rect = Rect::new(0, 0, 800, 600);
+        assert_eq!(
+            rect.split(1, 2),
+            vec![Rect::new(0, 300, 800, 300), Rect::new(0, 0, 800, 300)]
+        );
+    }
+
+    #[test]
+    fn split_grid_with_offset() {
+        let rect = Rect::new(100, 50, 200, 100);
+        assert_eq!(
+            rect.split(2, 2),
+            vec![
+                Rect::new(100, 100, 100, 50),
+                Rect::new(200, 100, 100, 50),
+                Rect::new(100, 50, 100, 50),
+                Rect::new(200, 50, 100, 50),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_rounds_cells_down() {
+        // The leftover pixels are not covered
+        let cells = Rect::new(0, 0, 101, 51).split(2, 2);
+        assert!(cells
+            .iter()
+            .all(|cell| cell.width == 50 && cell.height == 25));
+        assert_eq!(cells[1].x, 50);
+        assert_eq!(cells[0].y, 25);
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_zero_columns() {
+        Rect::new(0, 0, 100, 100).split(0, 1);
+    }
+}
@@ -0,0 +1,81 @@
+use std::cell::Cell;
+
+use gl;
+
+use crate::texture::TextureFormat;
+
+// Framebuffer attachment that can't be sampled. Cheaper than a texture when the content is only
+// needed while rendering, like most depth and stencil buffers
+pub struct Renderbuffer {
+    id: u32,
+    format: TextureFormat,
+    // 0 for single sample renderbuffers
+    samples: u32,
+    size: Cell<(u32, u32)>,
+}
+
+impl Renderbuffer {
+    pub fn new(width: u32, height: u32, format: TextureFormat) -> Self {
+        Self::new_multisampled(width, height, format, 0)
+    }
+
+    pub fn new_multisampled(width: u32, height: u32, format: TextureFormat, samples: u32) -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenRenderbuffers(1, &mut id);
+            assert_ne!(id, 0);
+        }
+
+        let renderbuffer = Self {
+            id,
+            format,
+            samples,
+            size: Cell::new((0, 0)),
+        };
+        renderbuffer.resize(width, height);
+        renderbuffer
+    }
+
+    // Reallocates the renderbuffer storage. The content is lost
+    pub fn resize(&self, width: u32, height: u32) {
+        unsafe {
+            gl::BindRenderbuffer(gl::RENDERBUFFER, self.id);
+            gl::RenderbufferStorageMultisample(
+                gl::RENDERBUFFER,
+                self.samples as i32,
+                self.format as u32,
+                width as i32,
+                height as i32,
+            );
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+        }
+
+        self.size.set((width, height));
+    }
+
+    pub(crate) fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn get_format(&self) -> TextureFormat {
+        self.format
+    }
+
+    pub fn get_samples(&self) -> u32 {
+        self.samples
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.size.get().0
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.size.get().1
+    }
+}
+
+impl Drop for Renderbuffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteRenderbuffers(1, &self.id) };
+    }
+}
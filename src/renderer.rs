@@ -1,44 +1,491 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
 use gl;
+use nalgebra_glm as glm;
 
-use crate::{program::Program, vertex_array::VertexArray};
+use crate::{
+    animation::MAX_JOINTS,
+    buffer::{Buffer, BufferTarget},
+    camera::Camera,
+    clear_values::{AttachmentClearValue, ClearValues},
+    draw_command::{DrawElementsIndirectCommand, DrawRange},
+    framebuffer::RenderTarget,
+    material::Material,
+    mesh::{Mesh, Submesh},
+    program::Program,
+    render_state::{DepthState, Rect, RenderState, StencilState},
+    scene::{NodeId, Scene},
+    vertex_array::VertexArray,
+};
 
-pub struct Renderer {}
+pub struct Renderer {
+    // OpenGL (major, minor) version of the context
+    gl_version: (i32, i32),
+    // Last state sent to OpenGL, used to skip redundant calls
+    render_state: RefCell<RenderState>,
+}
 
 impl Default for Renderer {
     fn default() -> Self {
-        Renderer::enable_blending();
-        Self {}
+        let renderer = Self {
+            gl_version: Renderer::query_gl_version(),
+            render_state: RefCell::new(RenderState::gl_initial()),
+        };
+        renderer.set_render_state(&RenderState::default());
+        renderer
     }
 }
 
 impl Renderer {
-    fn enable_blending() {
+    pub fn set_render_state(&self, render_state: &RenderState) {
+        let mut current = self.render_state.borrow_mut();
+        render_state.apply(&current);
+        *current = *render_state;
+    }
+
+    pub fn get_render_state(&self) -> RenderState {
+        *self.render_state.borrow()
+    }
+
+    // None disables the scissor test
+    pub fn set_scissor(&self, scissor: Option<Rect>) {
+        self.set_render_state(&RenderState {
+            scissor,
+            ..self.get_render_state()
+        });
+    }
+
+    pub fn set_viewport(&self, viewport: Rect) {
+        unsafe { gl::Viewport(viewport.x, viewport.y, viewport.width, viewport.height) };
+    }
+
+    // Queried from OpenGL, the window changes it when the framebuffer is resized
+    pub fn get_viewport(&self) -> Rect {
+        let mut viewport = [0; 4];
+        unsafe { gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr()) };
+        let [x, y, width, height] = viewport;
+        Rect::new(x, y, width, height)
+    }
+
+    // Runs the render callback with the viewport and the scissor rectangle set to the region,
+    // so that clears don't affect the rest of the target. For split-screen and
+    // picture-in-picture. The previous viewport and scissor are restored afterwards
+    pub fn render_to_region(&self, region: Rect, render: impl FnOnce()) {
+        let previous_viewport = self.get_viewport();
+        let previous_scissor = self.get_render_state().scissor;
+
+        self.set_viewport(region);
+        self.set_scissor(Some(region));
+
+        render();
+
+        self.set_viewport(previous_viewport);
+        self.set_scissor(previous_scissor);
+    }
+
+    fn query_gl_version() -> (i32, i32) {
+        let mut major = 0;
+        let mut minor = 0;
         unsafe {
-            gl::Enable(gl::BLEND);
-            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+            gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
         }
+        (major, minor)
+    }
+
+    // glMultiDrawElementsIndirect is core since OpenGL 4.3
+    pub fn supports_indirect_draw(&self) -> bool {
+        self.gl_version >= (4, 3)
     }
 
     pub fn clear(&self) {
         unsafe { gl::Clear(gl::COLOR_BUFFER_BIT) };
     }
 
+    pub fn clear_with(&self, values: &ClearValues) {
+        let mut mask = 0;
+
+        self.with_write_masks(|| unsafe {
+            if let Some([r, g, b, a]) = values.color {
+                gl::ClearColor(r, g, b, a);
+                mask |= gl::COLOR_BUFFER_BIT;
+            }
+            if let Some(depth) = values.depth {
+                gl::ClearDepth(depth as f64);
+                mask |= gl::DEPTH_BUFFER_BIT;
+            }
+            if let Some(stencil) = values.stencil {
+                gl::ClearStencil(stencil);
+                mask |= gl::STENCIL_BUFFER_BIT;
+            }
+            if mask != 0 {
+                gl::Clear(mask);
+            }
+        });
+    }
+
+    // Clears the colour attachment bound to the draw_buffer slot (as set by glDrawBuffers) of the
+    // current framebuffer
+    pub fn clear_color_attachment(&self, draw_buffer: u32, value: AttachmentClearValue) {
+        self.with_write_masks(|| unsafe {
+            match value {
+                AttachmentClearValue::Float(color) => {
+                    gl::ClearBufferfv(gl::COLOR, draw_buffer as i32, color.as_ptr())
+                }
+                AttachmentClearValue::Int(color) => {
+                    gl::ClearBufferiv(gl::COLOR, draw_buffer as i32, color.as_ptr())
+                }
+                AttachmentClearValue::Uint(color) => {
+                    gl::ClearBufferuiv(gl::COLOR, draw_buffer as i32, color.as_ptr())
+                }
+            }
+        });
+    }
+
+    pub fn clear_depth_stencil_attachment(&self, depth: f32, stencil: i32) {
+        self.with_write_masks(|| unsafe {
+            gl::ClearBufferfi(gl::DEPTH_STENCIL, 0, depth, stencil)
+        });
+    }
+
+    // Clearing respects the colour, depth and stencil write masks. Enable them while clearing so
+    // the result doesn't depend on the last render state
+    fn with_write_masks(&self, clear: impl FnOnce()) {
+        let render_state = self.get_render_state();
+        self.set_render_state(&RenderState {
+            depth: DepthState {
+                write: true,
+                ..render_state.depth
+            },
+            stencil: StencilState {
+                write_mask: u32::MAX,
+                ..render_state.stencil
+            },
+            color_mask: [true; 4],
+            ..render_state
+        });
+
+        clear();
+
+        self.set_render_state(&render_state);
+    }
+
+    // Runs the render callback with the framebuffer as render target and a viewport covering it.
+    // The previous framebuffer (usually the window) and viewport are restored afterwards
+    pub fn render_to<T: RenderTarget + ?Sized>(&self, target: &T, render: impl FnOnce()) {
+        let (width, height) = target.get_size();
+        let mut previous_framebuffer = 0;
+        let previous_viewport = self.get_viewport();
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous_framebuffer);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target.get_framebuffer_id());
+        }
+        self.set_viewport(Rect::new(0, 0, width as i32, height as i32));
+
+        render();
+
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, previous_framebuffer as u32) };
+        self.set_viewport(previous_viewport);
+    }
+
     pub fn draw(&self, vao: &VertexArray, program: &Program) {
-        let num_indices = vao.get_num_indices_to_draw();
+        let range = DrawRange::new(0, vao.get_num_indices_to_draw(), 0);
+        self.draw_range(vao, program, range);
+    }
 
-        program.bind();
-        program.bind_textures();
+    // Draws every node with a mesh, after updating the changed world matrices. The opaque draws
+    // are sorted by material so that those sharing a program and textures are consecutive, then
+    // the blended ones are drawn back to front, by the distance of their mesh bounding sphere
+    pub fn draw_scene(&self, scene: &Scene, camera: &Camera) {
+        scene.update_transforms();
+
+        let mut nodes = Vec::new();
+        scene.traverse(|id, _| nodes.push(id));
+
+        let mut draws = Vec::new();
+        for (id, node) in nodes.into_iter().map(|id| (id, scene.get_node(id))) {
+            if let Some(mesh) = &node.mesh {
+                for submesh in mesh.get_submeshes() {
+                    let material = node
+                        .materials
+                        .get(submesh.material_slot)
+                        .unwrap_or_else(|| {
+                            panic!(
+                                "Node {} has no material for slot {}",
+                                node.name, submesh.material_slot
+                            )
+                        });
+                    let sort_key = material.get_sort_key();
+                    let distance = match sort_key.is_blended() {
+                        true => {
+                            let center = mesh.get_bounding_sphere().center;
+                            let world_center = node.get_world_matrix() * center.push(1.0);
+                            glm::distance2(&world_center.xyz(), &camera.position)
+                        }
+                        false => 0.0,
+                    };
+                    draws.push((sort_key, distance, id, node, mesh, submesh, material));
+                }
+            }
+        }
+        draws.sort_by(|a, b| match (a.0.is_blended(), b.0.is_blended()) {
+            (true, true) => b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)),
+            _ => a.0.cmp(&b.0),
+        });
+
+        let view_projection = camera.view_projection();
+        let mut current_program = None;
+        for (_, _, id, node, mesh, submesh, material) in draws {
+            let program = material.get_program();
+            if current_program != Some(program.get_id()) {
+                program.set_uniform_mat4("uViewProjection", &view_projection);
+                current_program = Some(program.get_id());
+            }
+            program.set_uniform_mat4("uModel", &node.get_world_matrix());
+            Self::set_skin_uniforms(scene, id, program);
+            self.draw_submesh_with_material(mesh, submesh, material);
+        }
+    }
+
+    // Draws every mesh of the scene with the same program, for example, for depth-only passes.
+    // Sets uModel and uploads the uniforms and textures of the materials that the program
+    // declares, such as the alpha mask of the PBR materials. Blended materials are skipped. The
+    // other uniforms are up to the caller
+    pub fn draw_scene_with_program(&self, scene: &Scene, program: &Program) {
+        scene.update_transforms();
+
+        let mut nodes = Vec::new();
+        scene.traverse(|id, _| nodes.push(id));
+
+        for (id, node) in nodes.into_iter().map(|id| (id, scene.get_node(id))) {
+            if let Some(mesh) = &node.mesh {
+                program.set_uniform_mat4("uModel", &node.get_world_matrix());
+                Self::set_skin_uniforms(scene, id, program);
+                for submesh in mesh.get_submeshes() {
+                    match node.materials.get(submesh.material_slot) {
+                        Some(material) if material.render_state.blend.enabled => {}
+                        Some(material) => {
+                            Self::reset_alpha_mask(program);
+                            material.bind_to(program);
+                            self.draw_submesh(mesh, submesh, program);
+                            material.unbind_from(program);
+                        }
+                        None => {
+                            Self::reset_alpha_mask(program);
+                            self.draw_submesh(mesh, submesh, program);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Materials only upload the uniforms they set, so the alpha mask of a PBR material would
+    // otherwise stay set for the next submeshes drawn with the program
+    fn reset_alpha_mask(program: &Program) {
+        if program.has_uniform("uAlphaMode") {
+            program.set_uniform_1i("uAlphaMode", 0);
+        }
+        if program.has_uniform("uHasBaseColorMap") {
+            program.set_uniform_1i("uHasBaseColorMap", 0);
+        }
+    }
+
+    // For the programs with the skinning uniforms, see animation::skinning_shader_src. They stay
+    // set after the draw, so they are reset for the nodes without skin
+    fn set_skin_uniforms(scene: &Scene, id: NodeId, program: &Program) {
+        if !program.has_uniform("uSkinned") {
+            return;
+        }
+
+        match &scene.get_node(id).skin {
+            Some(skin) => {
+                let joint_matrices = skin.get_joint_matrices(scene, id);
+                assert!(
+                    joint_matrices.len() <= MAX_JOINTS,
+                    "Skin {} has {} joints, the maximum is {MAX_JOINTS}",
+                    skin.name,
+                    joint_matrices.len()
+                );
+                program.set_uniform_1i("uSkinned", 1);
+                program.set_uniform_mat4_array("uJointMatrices", &joint_matrices);
+            }
+            None => program.set_uniform_1i("uSkinned", 0),
+        }
+    }
+
+    // Sets the render state of the material, which stays set after the draw
+    pub fn draw_with_material(&self, vao: &VertexArray, material: &Material) {
+        let range = DrawRange::new(0, vao.get_num_indices_to_draw(), 0);
+        self.draw_range_with_material(vao, material, range);
+    }
+
+    // Draws each submesh with the material of its slot
+    pub fn draw_mesh_with_materials(&self, mesh: &Mesh, materials: &[Rc<Material>]) {
+        mesh.get_submeshes().iter().for_each(|submesh| {
+            let material = materials
+                .get(submesh.material_slot)
+                .expect("No material for the submesh slot");
+            self.draw_submesh_with_material(mesh, submesh, material);
+        });
+    }
+
+    pub fn draw_submesh_with_material(&self, mesh: &Mesh, submesh: &Submesh, material: &Material) {
+        self.draw_range_with_material(mesh.get_vertex_array(), material, submesh.get_range());
+    }
+
+    pub fn draw_range_with_material(
+        &self,
+        vao: &VertexArray,
+        material: &Material,
+        range: DrawRange,
+    ) {
+        Self::check_range(vao, &range);
+
+        self.set_render_state(&material.render_state);
+        material.bind();
+        material
+            .get_program()
+            .set_missing_attributes(vao.get_attribute_locations());
         vao.bind();
+        Self::draw_elements(&range);
+        vao.unbind();
+        material.unbind();
+    }
+
+    // Draws every submesh with the same program
+    pub fn draw_mesh(&self, mesh: &Mesh, program: &Program) {
+        mesh.get_submeshes()
+            .iter()
+            .for_each(|submesh| self.draw_submesh(mesh, submesh, program));
+    }
+
+    pub fn draw_submesh(&self, mesh: &Mesh, submesh: &Submesh, program: &Program) {
+        self.draw_range(mesh.get_vertex_array(), program, submesh.get_range());
+    }
+
+    pub fn draw_range(&self, vao: &VertexArray, program: &Program, range: DrawRange) {
+        Self::check_range(vao, &range);
+
+        Self::bind(vao, program);
+        Self::draw_elements(&range);
+        Self::unbind(vao, program);
+    }
 
+    fn draw_elements(range: &DrawRange) {
         unsafe {
-            gl::DrawElements(
+            gl::DrawElementsBaseVertex(
+                gl::TRIANGLES,
+                range.num_indices as i32,
+                gl::UNSIGNED_INT,
+                Self::index_offset(range.first_index),
+                range.base_vertex,
+            )
+        };
+    }
+
+    // Draws several ranges of the same vertex array with a single call. Works on OpenGL 3.3
+    // contexts; commands with more than one instance fall back to one instanced draw each.
+    // base_instance needs OpenGL 4.2 and is ignored, as in the fallback
+    pub fn multi_draw(
+        &self,
+        vao: &VertexArray,
+        program: &Program,
+        commands: &[DrawElementsIndirectCommand],
+    ) {
+        let ranges: Vec<DrawRange> = commands
+            .iter()
+            .map(|command| DrawRange::new(command.first_index, command.count, command.base_vertex))
+            .collect();
+        ranges
+            .iter()
+            .for_each(|range| Self::check_range(vao, range));
+
+        let single_instance = commands.iter().all(|command| command.instance_count == 1);
+
+        Self::bind(vao, program);
+        if single_instance {
+            let counts: Vec<i32> = ranges.iter().map(|r| r.num_indices as i32).collect();
+            let offsets: Vec<*const _> = ranges
+                .iter()
+                .map(|r| Self::index_offset(r.first_index))
+                .collect();
+            let base_vertices: Vec<i32> = ranges.iter().map(|r| r.base_vertex).collect();
+
+            unsafe {
+                gl::MultiDrawElementsBaseVertex(
+                    gl::TRIANGLES,
+                    counts.as_ptr(),
+                    gl::UNSIGNED_INT,
+                    offsets.as_ptr(),
+                    commands.len() as i32,
+                    base_vertices.as_ptr(),
+                )
+            };
+        } else {
+            // gl_InstanceID starts at 0 for each draw, base_instance can't be emulated
+            for (command, range) in commands.iter().zip(&ranges) {
+                unsafe {
+                    gl::DrawElementsInstancedBaseVertex(
+                        gl::TRIANGLES,
+                        range.num_indices as i32,
+                        gl::UNSIGNED_INT,
+                        Self::index_offset(range.first_index),
+                        command.instance_count as i32,
+                        range.base_vertex,
+                    )
+                };
+            }
+        }
+        Self::unbind(vao, program);
+    }
+
+    // Draws the commands stored in a GPU buffer, for example, generated by a compute shader.
+    // Requires OpenGL 4.3; the commands can't be validated on the CPU
+    pub fn draw_indirect(
+        &self,
+        vao: &VertexArray,
+        program: &Program,
+        commands: &Buffer<DrawElementsIndirectCommand>,
+    ) {
+        assert!(
+            self.supports_indirect_draw(),
+            "Indirect draws require OpenGL 4.3, use multi_draw instead"
+        );
+
+        Self::bind(vao, program);
+        commands.bind_to(BufferTarget::DrawIndirect);
+        unsafe {
+            gl::MultiDrawElementsIndirect(
                 gl::TRIANGLES,
-                num_indices as i32,
                 gl::UNSIGNED_INT,
                 std::ptr::null(),
+                commands.len() as i32,
+                0,
             )
         };
+        unsafe { gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, 0) };
+        Self::unbind(vao, program);
+    }
+
+    fn check_range(vao: &VertexArray, range: &DrawRange) {
+        range.check(vao.get_indices(), vao.get_num_vertices());
+    }
+
+    // Offset, in bytes, of the first index in the element buffer
+    fn index_offset(first_index: u32) -> *const std::ffi::c_void {
+        (first_index as usize * std::mem::size_of::<u32>()) as *const _
+    }
+
+    fn bind(vao: &VertexArray, program: &Program) {
+        program.bind();
+        program.bind_textures();
+        program.set_missing_attributes(vao.get_attribute_locations());
+        vao.bind();
+    }
 
+    fn unbind(vao: &VertexArray, program: &Program) {
         vao.unbind();
         program.unbind_textures();
         program.unbind();
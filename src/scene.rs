@@ -0,0 +1,197 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use nalgebra_glm as glm;
+
+use crate::animation::Skin;
+use crate::material::Material;
+use crate::mesh::Mesh;
+use crate::transform::Transform;
+
+// Index of a node in its Scene
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+pub struct SceneNode {
+    pub name: String,
+    pub transform: Transform,
+    // Nodes without mesh only group and move their children
+    pub mesh: Option<Rc<Mesh>>,
+    // Indexed by the material slot of each submesh. Their programs must declare the uModel and
+    // uViewProjection mat4 uniforms
+    pub materials: Vec<Rc<Material>>,
+    // Joints animating the mesh, which must have skin vertices
+    pub skin: Option<Rc<Skin>>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    world_matrix: Cell<glm::Mat4>,
+    // Set when the transform may have changed since the world matrix was computed
+    dirty: Cell<bool>,
+}
+
+impl SceneNode {
+    pub fn new(name: &str, transform: Transform) -> Self {
+        Self {
+            name: String::from(name),
+            transform,
+            mesh: None,
+            materials: Vec::new(),
+            skin: None,
+            parent: None,
+            children: Vec::new(),
+            world_matrix: Cell::new(glm::Mat4::identity()),
+            dirty: Cell::new(true),
+        }
+    }
+
+    pub fn new_mesh(
+        name: &str,
+        transform: Transform,
+        mesh: Rc<Mesh>,
+        materials: Vec<Rc<Material>>,
+    ) -> Self {
+        Self {
+            mesh: Some(mesh),
+            materials,
+            ..Self::new(name, transform)
+        }
+    }
+
+    pub fn get_parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn get_children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    // Local to world space. Up to date after Scene::update_transforms
+    pub fn get_world_matrix(&self) -> glm::Mat4 {
+        self.world_matrix.get()
+    }
+}
+
+// Hierarchy of nodes, each one positioned relative to its parent. Nodes are never removed, a
+// NodeId stays valid for the lifetime of the scene
+#[derive(Default)]
+pub struct Scene {
+    nodes: Vec<SceneNode>,
+    roots: Vec<NodeId>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_node(&mut self, node: SceneNode) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(node);
+        self.roots.push(id);
+        id
+    }
+
+    pub fn add_child(&mut self, parent: NodeId, node: SceneNode) -> NodeId {
+        let id = self.add_node(node);
+        self.set_parent(id, Some(parent));
+        id
+    }
+
+    // Moves the node, with its children, under another parent. None makes it a root. The local
+    // transform is kept, so the node moves with its new parent
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        if let Some(parent) = parent {
+            assert!(
+                !self.is_ancestor(id, parent),
+                "Node {id:?} can't be a child of its descendant {parent:?}"
+            );
+        }
+
+        match self.nodes[id.0].parent {
+            Some(previous) => self.nodes[previous.0].children.retain(|&child| child != id),
+            None => self.roots.retain(|&root| root != id),
+        }
+        match parent {
+            Some(parent) => self.nodes[parent.0].children.push(id),
+            None => self.roots.push(id),
+        }
+
+        let node = &mut self.nodes[id.0];
+        node.parent = parent;
+        node.dirty.set(true);
+    }
+
+    fn is_ancestor(&self, ancestor: NodeId, id: NodeId) -> bool {
+        let mut current = Some(id);
+        while let Some(node) = current {
+            if node == ancestor {
+                return true;
+            }
+            current = self.nodes[node.0].parent;
+        }
+        false
+    }
+
+    pub fn get_node(&self, id: NodeId) -> &SceneNode {
+        &self.nodes[id.0]
+    }
+
+    // Marks the node as changed, its world matrix and the ones of its descendants are
+    // recomputed by the next update_transforms
+    pub fn get_node_mut(&mut self, id: NodeId) -> &mut SceneNode {
+        let node = &mut self.nodes[id.0];
+        node.dirty.set(true);
+        node
+    }
+
+    pub fn get_transform_mut(&mut self, id: NodeId) -> &mut Transform {
+        &mut self.get_node_mut(id).transform
+    }
+
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes
+            .iter()
+            .position(|node| node.name == name)
+            .map(NodeId)
+    }
+
+    pub fn get_roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    pub fn get_num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    // Recomputes the world matrices of the changed nodes and their descendants
+    pub fn update_transforms(&self) {
+        self.roots
+            .iter()
+            .for_each(|&root| self.update_node(root, &glm::Mat4::identity(), false));
+    }
+
+    fn update_node(&self, id: NodeId, parent_world: &glm::Mat4, parent_changed: bool) {
+        let node = &self.nodes[id.0];
+        let changed = parent_changed || node.dirty.get();
+        if changed {
+            node.world_matrix
+                .set(parent_world * node.transform.get_matrix());
+            node.dirty.set(false);
+        }
+
+        let world = node.world_matrix.get();
+        node.children
+            .iter()
+            .for_each(|&child| self.update_node(child, &world, changed));
+    }
+
+    // Depth-first, parents before their children
+    pub fn traverse(&self, mut visit: impl FnMut(NodeId, &SceneNode)) {
+        let mut stack: Vec<NodeId> = self.roots.iter().rev().copied().collect();
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id.0];
+            visit(id, node);
+            stack.extend(node.children.iter().rev());
+        }
+    }
+}
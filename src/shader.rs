@@ -7,6 +7,8 @@ use gl;
 pub enum ShaderType {
     VertexShader = gl::VERTEX_SHADER,
     FragmentShader = gl::FRAGMENT_SHADER,
+    // Requires an OpenGL 4.3 context
+    ComputeShader = gl::COMPUTE_SHADER,
 }
 
 pub struct Shader {
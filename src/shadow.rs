@@ -0,0 +1,500 @@
+use std::rc::Rc;
+
+use nalgebra_glm as glm;
+
+use crate::animation::skinning_shader_src;
+use crate::bounding_volume::Aabb;
+use crate::camera::{Camera, Projection};
+use crate::clear_values::ClearValues;
+use crate::cubemap::{Cubemap, CubemapFaceTarget, NUM_FACES};
+use crate::framebuffer::{ColorAttachment, DepthStencilAttachment, Framebuffer};
+use crate::light::{self, Lights, ShadowData, MAX_SHADOW_CASCADES};
+use crate::program::Program;
+use crate::render_state::{DepthState, Rect, RenderState};
+use crate::renderer::Renderer;
+use crate::scene::Scene;
+use crate::shader::{Shader, ShaderType};
+use crate::texture::{Texture, TextureFormat};
+
+// Texture units and sampler names of the maps bound by ShadowMaps::bind, after the environment
+// maps. The lit programs point their samplers to them
+pub const CASCADE_SHADOW_MAP_UNIFORM: &str = "uCascadeShadowMap";
+pub const CASCADE_SHADOW_MAP_SLOT: u32 = 8;
+pub const POINT_SHADOW_MAP_UNIFORM: &str = "uPointShadowMap";
+pub const POINT_SHADOW_MAP_SLOT: u32 = 9;
+pub const SPOT_SHADOW_MAP_UNIFORM: &str = "uSpotShadowMap";
+pub const SPOT_SHADOW_MAP_SLOT: u32 = 10;
+
+// Near plane of the point and spot light projections
+const LIGHT_NEAR_PLANE: f32 = 0.05;
+
+// Largest ShadowMaps::pcf_radius, the directional and spot light kernels take (2r+1)^2 samples
+pub const MAX_PCF_RADIUS: u32 = 3;
+
+// Skinned like the lit programs, so that the shadows follow the animation
+fn depth_vertex_shader_src() -> String {
+    format!(
+        "#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 2) in vec2 aTexCoord;
+
+out vec3 vWorldPos;
+out vec2 vTexCoord;
+
+uniform mat4 uLightViewProjection;
+uniform mat4 uModel;
+
+{}
+void main() {{
+    vec4 worldPos = uModel * getSkinMatrix() * vec4(aPos, 1.0);
+    gl_Position = uLightViewProjection * worldPos;
+    vWorldPos = worldPos.xyz;
+    vTexCoord = aTexCoord;
+}}",
+        skinning_shader_src()
+    )
+}
+
+// Alpha mask of the PBR materials, uploaded by Renderer::draw_scene_with_program from the
+// material of each draw, so that cut-out surfaces cast cut-out shadows
+const ALPHA_MASK_SHADER_SRC: &str = "
+in vec2 vTexCoord;
+
+uniform int uAlphaMode;
+uniform float uAlphaCutoff;
+uniform vec4 uBaseColorFactor;
+uniform bool uHasBaseColorMap;
+uniform sampler2D uBaseColorMap;
+
+void alphaMask() {
+    if (uAlphaMode != 1) {
+        return;
+    }
+    float alpha = uBaseColorFactor.a;
+    if (uHasBaseColorMap) {
+        alpha *= texture(uBaseColorMap, vTexCoord).a;
+    }
+    if (alpha < uAlphaCutoff) {
+        discard;
+    }
+}
+";
+
+// Directional lights keep the depth of the orthographic projection
+const DEPTH_FRAGMENT_SHADER_SRC: &str = "
+void main() {
+    alphaMask();
+}";
+
+// Point and spot lights store the distance to the light divided by the far plane, the same
+// value for every face of a cubemap
+const DISTANCE_FRAGMENT_SHADER_SRC: &str = "
+in vec3 vWorldPos;
+
+// xyz: light position, w: far plane
+uniform vec4 uLightPosition;
+
+void main() {
+    alphaMask();
+    gl_FragDepth = distance(vWorldPos, uLightPosition.xyz) / uLightPosition.w;
+}";
+
+// Directions and up vectors of the cubemap faces, in the GL_TEXTURE_CUBE_MAP_POSITIVE_X + index
+// order
+const CUBEMAP_FACES: [([f32; 3], [f32; 3]); NUM_FACES as usize] = [
+    ([1.0, 0.0, 0.0], [0.0, -1.0, 0.0]),
+    ([-1.0, 0.0, 0.0], [0.0, -1.0, 0.0]),
+    ([0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+    ([0.0, -1.0, 0.0], [0.0, 0.0, -1.0]),
+    ([0.0, 0.0, 1.0], [0.0, -1.0, 0.0]),
+    ([0.0, 0.0, -1.0], [0.0, -1.0, 0.0]),
+];
+
+fn new_depth_program(fragment_shader_src: &str) -> Program {
+    let vertex_shader = Shader::new(ShaderType::VertexShader, &depth_vertex_shader_src());
+    let fragment_shader = Shader::new(
+        ShaderType::FragmentShader,
+        &format!("#version 330 core\n{ALPHA_MASK_SHADER_SRC}{fragment_shader_src}"),
+    );
+    Program::new(&vertex_shader, &fragment_shader)
+}
+
+fn new_shadow_map_target(uniform_name: &str, width: u32, height: u32, slot: u32) -> Framebuffer {
+    let depth = ColorAttachment::new(uniform_name, TextureFormat::DepthComponent32F, slot);
+    let framebuffer = Framebuffer::new(width, height, &[], DepthStencilAttachment::Texture(depth));
+    framebuffer
+        .get_depth_texture()
+        .unwrap()
+        .set_depth_compare(true);
+    framebuffer
+}
+
+// Any vector that isn't parallel to the direction
+fn get_up_vector(direction: &glm::Vec3) -> glm::Vec3 {
+    match direction.normalize().y.abs() > 0.99 {
+        true => glm::Vec3::z(),
+        false => glm::Vec3::y(),
+    }
+}
+
+// From clip space to texture coordinates and depth, all from 0 to 1
+fn get_bias_matrix() -> glm::Mat4 {
+    glm::translation(&glm::vec3(0.5, 0.5, 0.5)) * glm::scaling(&glm::vec3(0.5, 0.5, 0.5))
+}
+
+// World bounds of the meshes, None without meshes. The world matrices must be up to date
+fn get_scene_bounds(scene: &Scene) -> Option<Aabb> {
+    let mut bounds: Option<Aabb> = None;
+    scene.traverse(|_, node| {
+        if let Some(mesh) = &node.mesh {
+            let aabb = mesh.get_aabb().transform(&node.get_world_matrix());
+            bounds = Some(match bounds {
+                Some(bounds) => bounds.union(&aabb),
+                None => aabb,
+            });
+        }
+    });
+    bounds
+}
+
+// Shadow maps of one directional light (cascaded), one point light (cubemap) and one spot light,
+// picked by their index in Lights. The other lights never cast shadows. The lit programs (phong,
+// pbr) darken these lights with percentage-closer filtering.
+// Each frame: render (depth passes, before drawing the scene), Lights::update, bind, draw the
+// scene and unbind
+pub struct ShadowMaps {
+    pub directional_light: Option<usize>,
+    pub point_light: Option<usize>,
+    pub spot_light: Option<usize>,
+    // View distance covered by the cascades, and far plane of the point and spot light shadows
+    pub max_distance: f32,
+    // Cascade splits from uniform (0) to logarithmic (1). Logarithmic splits give more
+    // resolution close to the camera
+    pub split_lambda: f32,
+    // Subtracted from the depth of the surface, in shadow map depth units (0 to 1)
+    pub depth_bias: f32,
+    // Offset of the surface along its normal, in world units
+    pub normal_bias: f32,
+    // Half size of the PCF kernel in texels, 0 for a single hardware-filtered comparison. At most
+    // MAX_PCF_RADIUS
+    pub pcf_radius: u32,
+    resolution: u32,
+    num_cascades: u32,
+    // The cascades side by side, each one resolution x resolution
+    cascade_target: Framebuffer,
+    point_map: Cubemap,
+    point_target: CubemapFaceTarget,
+    spot_target: Framebuffer,
+    depth_program: Program,
+    distance_program: Program,
+}
+
+impl ShadowMaps {
+    // resolution: size of each cascade, cubemap face and spot light map
+    pub fn new(resolution: u32, num_cascades: u32) -> Self {
+        assert!((1..=MAX_SHADOW_CASCADES as u32).contains(&num_cascades));
+
+        let point_map = Cubemap::new_empty(
+            POINT_SHADOW_MAP_UNIFORM,
+            resolution,
+            TextureFormat::DepthComponent32F,
+            POINT_SHADOW_MAP_SLOT,
+            1,
+        );
+        point_map.set_depth_compare(true);
+
+        Self {
+            directional_light: None,
+            point_light: None,
+            spot_light: None,
+            max_distance: 50.0,
+            split_lambda: 0.75,
+            depth_bias: 0.001,
+            normal_bias: 0.02,
+            pcf_radius: 1,
+            resolution,
+            num_cascades,
+            cascade_target: new_shadow_map_target(
+                CASCADE_SHADOW_MAP_UNIFORM,
+                resolution * num_cascades,
+                resolution,
+                CASCADE_SHADOW_MAP_SLOT,
+            ),
+            point_map,
+            point_target: CubemapFaceTarget::new(resolution),
+            spot_target: new_shadow_map_target(
+                SPOT_SHADOW_MAP_UNIFORM,
+                resolution,
+                resolution,
+                SPOT_SHADOW_MAP_SLOT,
+            ),
+            depth_program: new_depth_program(DEPTH_FRAGMENT_SHADER_SRC),
+            distance_program: new_depth_program(DISTANCE_FRAGMENT_SHADER_SRC),
+        }
+    }
+
+    pub fn get_resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    pub fn get_num_cascades(&self) -> u32 {
+        self.num_cascades
+    }
+
+    pub fn get_cascade_shadow_map(&self) -> Rc<Texture> {
+        self.cascade_target.get_depth_texture().unwrap()
+    }
+
+    pub fn get_point_shadow_map(&self) -> &Cubemap {
+        &self.point_map
+    }
+
+    pub fn get_spot_shadow_map(&self) -> Rc<Texture> {
+        self.spot_target.get_depth_texture().unwrap()
+    }
+
+    // Renders the depth of the scene from the shadow-casting lights and stores what the lit
+    // shaders need in the lights, uploaded by the next Lights::update. Every mesh casts
+    // shadows except the blended ones, see Renderer::draw_scene_with_program
+    pub fn render(&self, renderer: &Renderer, scene: &Scene, camera: &Camera, lights: &mut Lights) {
+        assert!(
+            self.pcf_radius <= MAX_PCF_RADIUS,
+            "PCF radius {} above the maximum of {MAX_PCF_RADIUS}",
+            self.pcf_radius
+        );
+        scene.update_transforms();
+        let scene_bounds = get_scene_bounds(scene);
+
+        let previous_render_state = renderer.get_render_state();
+        renderer.set_render_state(&RenderState {
+            depth: DepthState::less(),
+            ..RenderState::gl_initial()
+        });
+
+        let mut data = ShadowData::none();
+        data.params = [
+            self.depth_bias,
+            self.normal_bias,
+            self.pcf_radius as f32,
+            0.0,
+        ];
+        data.view_direction = light::vec4(&camera.get_forward(), 0.0);
+
+        if let Some(index) = self.directional_light {
+            let light = lights
+                .directional_lights
+                .get(index)
+                .expect("No directional light at the shadow index");
+            let direction = light.direction.normalize();
+
+            let splits = self.get_cascade_splits(camera);
+            let mut start = Self::get_near_far(camera).0;
+            renderer.render_to(&self.cascade_target, || {
+                renderer.clear_with(&ClearValues::depth(1.0));
+                for (cascade, &end) in splits.iter().enumerate() {
+                    let view_projection =
+                        self.fit_cascade(camera, &direction, start, end, scene_bounds.as_ref());
+                    start = end;
+
+                    let tile = Rect::new(
+                        (cascade as u32 * self.resolution) as i32,
+                        0,
+                        self.resolution as i32,
+                        self.resolution as i32,
+                    );
+                    renderer.render_to_region(tile, || {
+                        self.depth_program
+                            .set_uniform_mat4("uLightViewProjection", &view_projection);
+                        renderer.draw_scene_with_program(scene, &self.depth_program);
+                    });
+
+                    // Scale and offset the texture coordinates into the tile of the cascade
+                    let num_cascades = self.num_cascades as f32;
+                    let tile_matrix =
+                        glm::translation(&glm::vec3(cascade as f32 / num_cascades, 0.0, 0.0))
+                            * glm::scaling(&glm::vec3(1.0 / num_cascades, 1.0, 1.0));
+                    data.cascade_matrices[cascade] =
+                        tile_matrix * get_bias_matrix() * view_projection;
+                    data.cascade_splits[cascade] = end;
+                }
+            });
+
+            data.shadow_lights[0] = index as i32;
+            data.shadow_lights[3] = self.num_cascades as i32;
+        }
+
+        if let Some(index) = self.point_light {
+            let light = lights
+                .point_lights
+                .get(index)
+                .expect("No point light at the shadow index");
+            let projection = glm::perspective(
+                1.0,
+                std::f32::consts::FRAC_PI_2,
+                LIGHT_NEAR_PLANE,
+                self.max_distance,
+            );
+
+            let light_position = light::vec4(&light.position, self.max_distance);
+            self.distance_program.set_uniform_4f(
+                "uLightPosition",
+                light_position[0],
+                light_position[1],
+                light_position[2],
+                light_position[3],
+            );
+            for (face, (direction, up)) in CUBEMAP_FACES.iter().enumerate() {
+                let view = glm::look_at(
+                    &light.position,
+                    &(light.position + glm::Vec3::from(*direction)),
+                    &glm::Vec3::from(*up),
+                );
+                renderer.render_to(&self.point_target, || {
+                    self.point_target.attach(&self.point_map, face as u32, 0);
+                    renderer.clear_with(&ClearValues::depth(1.0));
+                    self.distance_program
+                        .set_uniform_mat4("uLightViewProjection", &(projection * view));
+                    renderer.draw_scene_with_program(scene, &self.distance_program);
+                });
+            }
+
+            data.shadow_lights[1] = index as i32;
+            data.point_position = light_position;
+        }
+
+        if let Some(index) = self.spot_light {
+            let light = lights
+                .spot_lights
+                .get(index)
+                .expect("No spot light at the shadow index");
+            let fov = (2.0 * light.outer_angle).min(std::f32::consts::PI - 0.01);
+            let projection = glm::perspective(1.0, fov, LIGHT_NEAR_PLANE, self.max_distance);
+            let view = glm::look_at(
+                &light.position,
+                &(light.position + light.direction),
+                &get_up_vector(&light.direction),
+            );
+            let view_projection = projection * view;
+
+            let light_position = light::vec4(&light.position, self.max_distance);
+            renderer.render_to(&self.spot_target, || {
+                renderer.clear_with(&ClearValues::depth(1.0));
+                self.distance_program.set_uniform_4f(
+                    "uLightPosition",
+                    light_position[0],
+                    light_position[1],
+                    light_position[2],
+                    light_position[3],
+                );
+                self.distance_program
+                    .set_uniform_mat4("uLightViewProjection", &view_projection);
+                renderer.draw_scene_with_program(scene, &self.distance_program);
+            });
+
+            data.shadow_lights[2] = index as i32;
+            data.spot_position = light_position;
+            data.spot_matrix = get_bias_matrix() * view_projection;
+        }
+
+        renderer.set_render_state(&previous_render_state);
+        lights.shadows = data;
+    }
+
+    pub fn bind(&self) {
+        self.get_cascade_shadow_map().bind();
+        self.point_map.bind();
+        self.get_spot_shadow_map().bind();
+    }
+
+    pub fn unbind(&self) {
+        self.get_cascade_shadow_map().unbind();
+        self.point_map.unbind();
+        self.get_spot_shadow_map().unbind();
+    }
+
+    fn get_near_far(camera: &Camera) -> (f32, f32) {
+        match camera.projection {
+            Projection::Perspective { near, far, .. } => (near, far),
+            Projection::Orthographic { near, far, .. } => (near, far),
+        }
+    }
+
+    // View depth where each cascade ends, the last one at max_distance
+    fn get_cascade_splits(&self, camera: &Camera) -> Vec<f32> {
+        let (near, far) = Self::get_near_far(camera);
+        let far = far.min(near + self.max_distance);
+
+        (1..=self.num_cascades)
+            .map(|cascade| {
+                let fraction = cascade as f32 / self.num_cascades as f32;
+                let uniform = near + (far - near) * fraction;
+                // Orthographic cameras can have a negative near plane
+                let logarithmic = match near > 0.0 {
+                    true => near * (far / near).powf(fraction),
+                    false => uniform,
+                };
+                glm::lerp_scalar(uniform, logarithmic, self.split_lambda)
+            })
+            .collect()
+    }
+
+    // Orthographic view projection of the light that covers the part of the camera frustum
+    // between the view depths, and the whole scene towards the light so that every caster is
+    // drawn
+    fn fit_cascade(
+        &self,
+        camera: &Camera,
+        direction: &glm::Vec3,
+        start: f32,
+        end: f32,
+        scene_bounds: Option<&Aabb>,
+    ) -> glm::Mat4 {
+        let (near, far) = Self::get_near_far(camera);
+        let inverse_view_projection = glm::inverse(&camera.view_projection());
+        let unproject = |x: f32, y: f32, z: f32| {
+            let point = inverse_view_projection * glm::vec4(x, y, z, 1.0);
+            point.xyz() / point.w
+        };
+
+        // The edges of the frustum go from the near to the far plane, and the view depth is
+        // linear along them
+        let mut corners = Vec::new();
+        for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+            let near_corner = unproject(x, y, -1.0);
+            let far_corner = unproject(x, y, 1.0);
+            for depth in [start, end] {
+                let t = (depth - near) / (far - near);
+                corners.push(glm::lerp(&near_corner, &far_corner, t));
+            }
+        }
+
+        // A bounding sphere keeps the size of the projection constant when the camera rotates
+        let center = corners.iter().sum::<glm::Vec3>() / corners.len() as f32;
+        let radius = corners
+            .iter()
+            .map(|corner| glm::distance(corner, &center))
+            .fold(0.0, f32::max);
+        let radius = (radius * 16.0).ceil() / 16.0;
+
+        // Snapping the center to the texels avoids shimmering edges when the camera moves
+        let rotation = glm::look_at(&glm::Vec3::zeros(), direction, &get_up_vector(direction));
+        let texel_size = 2.0 * radius / self.resolution as f32;
+        let light_center = (rotation * center.push(1.0)).xyz();
+        let snapped_center = glm::vec3(
+            (light_center.x / texel_size).floor() * texel_size,
+            (light_center.y / texel_size).floor() * texel_size,
+            light_center.z,
+        );
+        let view = glm::translation(&-snapped_center) * rotation;
+
+        // The light looks along -Z
+        let (mut z_near, mut z_far) = (-radius, radius);
+        if let Some(bounds) = scene_bounds {
+            for corner in bounds.transform(&view).get_corners() {
+                z_near = z_near.min(-corner.z);
+                z_far = z_far.max(-corner.z);
+            }
+        }
+        glm::ortho(-radius, radius, -radius, radius, z_near, z_far) * view
+    }
+}
@@ -1,11 +1,106 @@
+use std::cell::Cell;
+
 use gl;
 use image;
 use image::imageops;
 
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFormat {
+    Rgba8 = gl::RGBA8,
+    // Colour textures authored in sRGB (base colour, emissive), converted to linear when sampled
+    Srgb8Alpha8 = gl::SRGB8_ALPHA8,
+    Rgba16F = gl::RGBA16F,
+    Rgba32F = gl::RGBA32F,
+    R32F = gl::R32F,
+    R32Ui = gl::R32UI,
+    DepthComponent24 = gl::DEPTH_COMPONENT24,
+    DepthComponent32F = gl::DEPTH_COMPONENT32F,
+    Depth24Stencil8 = gl::DEPTH24_STENCIL8,
+}
+
+impl TextureFormat {
+    // Format and type of the pixel data passed to glTexImage2D
+    pub(crate) fn pixel_format(&self) -> (u32, u32) {
+        match self {
+            TextureFormat::Rgba8 | TextureFormat::Srgb8Alpha8 => (gl::RGBA, gl::UNSIGNED_BYTE),
+            TextureFormat::Rgba16F | TextureFormat::Rgba32F => (gl::RGBA, gl::FLOAT),
+            TextureFormat::R32F => (gl::RED, gl::FLOAT),
+            TextureFormat::R32Ui => (gl::RED_INTEGER, gl::UNSIGNED_INT),
+            TextureFormat::DepthComponent24 | TextureFormat::DepthComponent32F => {
+                (gl::DEPTH_COMPONENT, gl::FLOAT)
+            }
+            TextureFormat::Depth24Stencil8 => (gl::DEPTH_STENCIL, gl::UNSIGNED_INT_24_8),
+        }
+    }
+
+    pub fn is_depth(&self) -> bool {
+        matches!(
+            self,
+            TextureFormat::DepthComponent24
+                | TextureFormat::DepthComponent32F
+                | TextureFormat::Depth24Stencil8
+        )
+    }
+
+    pub fn has_stencil(&self) -> bool {
+        *self == TextureFormat::Depth24Stencil8
+    }
+
+    pub fn is_integer(&self) -> bool {
+        *self == TextureFormat::R32Ui
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFilter {
+    Nearest = gl::NEAREST,
+    Linear = gl::LINEAR,
+    // Minification only
+    NearestMipmapNearest = gl::NEAREST_MIPMAP_NEAREST,
+    LinearMipmapNearest = gl::LINEAR_MIPMAP_NEAREST,
+    NearestMipmapLinear = gl::NEAREST_MIPMAP_LINEAR,
+    LinearMipmapLinear = gl::LINEAR_MIPMAP_LINEAR,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureWrap {
+    Repeat = gl::REPEAT,
+    MirroredRepeat = gl::MIRRORED_REPEAT,
+    ClampToEdge = gl::CLAMP_TO_EDGE,
+    ClampToBorder = gl::CLAMP_TO_BORDER,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureSampler {
+    pub min_filter: TextureFilter,
+    pub mag_filter: TextureFilter,
+    pub wrap_s: TextureWrap,
+    pub wrap_t: TextureWrap,
+}
+
+impl Default for TextureSampler {
+    // Trilinear filtering, repeated
+    fn default() -> Self {
+        Self {
+            min_filter: TextureFilter::LinearMipmapLinear,
+            mag_filter: TextureFilter::Linear,
+            wrap_s: TextureWrap::Repeat,
+            wrap_t: TextureWrap::Repeat,
+        }
+    }
+}
+
 pub struct Texture {
     id: u32,
     uniform_name: String,
     slot: u32,
+    format: TextureFormat,
+    // (width, height). Cell because textures attached to a framebuffer are shared and resized
+    // with it
+    size: Cell<(u32, u32)>,
 }
 
 impl Texture {
@@ -17,6 +112,7 @@ impl Texture {
 
         let mut img = image::open(path).unwrap();
         imageops::flip_vertical_in_place(&mut img);
+        let size = (img.width(), img.height());
 
         unsafe {
             gl::GenTextures(1, &mut id);
@@ -56,9 +152,220 @@ impl Texture {
             id,
             uniform_name: String::from(uniform_name),
             slot,
+            format: TextureFormat::Rgba8,
+            size: Cell::new(size),
+        }
+    }
+
+    // Uploads the rows top first, without the flip of Texture::new: the texture coordinate v = 0
+    // is the top of the image, as in glTF. Mipmaps are always generated
+    pub fn from_image(
+        uniform_name: &str,
+        image: &image::RgbaImage,
+        format: TextureFormat,
+        slot: u32,
+        sampler: &TextureSampler,
+    ) -> Self {
+        assert!(
+            matches!(format, TextureFormat::Rgba8 | TextureFormat::Srgb8Alpha8),
+            "Images can only be uploaded to 8 bit RGBA textures"
+        );
+
+        let id = Self::gen_texture(slot, sampler);
+        unsafe {
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                format as i32,
+                image.width() as i32,
+                image.height() as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                image.as_raw().as_ptr() as *const _,
+            );
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        Self {
+            id,
+            uniform_name: String::from(uniform_name),
+            slot,
+            format,
+            size: Cell::new(image.dimensions()),
         }
     }
 
+    // Floating point version of from_image, for HDR images like equirectangular environment maps.
+    // Without mipmaps: the min filter of the sampler can't use them
+    pub fn from_hdr_image(
+        uniform_name: &str,
+        image: &image::Rgba32FImage,
+        slot: u32,
+        sampler: &TextureSampler,
+    ) -> Self {
+        assert!(
+            matches!(
+                sampler.min_filter,
+                TextureFilter::Nearest | TextureFilter::Linear
+            ),
+            "HDR textures have no mipmaps"
+        );
+
+        let id = Self::gen_texture(slot, sampler);
+        unsafe {
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                TextureFormat::Rgba32F as i32,
+                image.width() as i32,
+                image.height() as i32,
+                0,
+                gl::RGBA,
+                gl::FLOAT,
+                image.as_raw().as_ptr() as *const _,
+            );
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        Self {
+            id,
+            uniform_name: String::from(uniform_name),
+            slot,
+            format: TextureFormat::Rgba32F,
+            size: Cell::new(image.dimensions()),
+        }
+    }
+
+    // Creates a texture with the parameters of the sampler, left bound for the upload
+    fn gen_texture(slot: u32, sampler: &TextureSampler) -> u32 {
+        let mut id = 0;
+
+        let valid_slot_range = 0..31;
+        assert!(valid_slot_range.contains(&slot));
+
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            assert_ne!(id, 0);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, sampler.wrap_s as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, sampler.wrap_t as i32);
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MIN_FILTER,
+                sampler.min_filter as i32,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MAG_FILTER,
+                sampler.mag_filter as i32,
+            );
+        }
+        id
+    }
+
+    // Texture without content, for example, to render into it with a framebuffer
+    pub fn new_empty(
+        uniform_name: &str,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        slot: u32,
+    ) -> Self {
+        let mut id = 0;
+
+        let valid_slot_range = 0..31;
+        assert!(valid_slot_range.contains(&slot));
+
+        // Integer textures can't be filtered
+        let filter = match format.is_integer() {
+            true => gl::NEAREST,
+            false => gl::LINEAR,
+        };
+
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            assert_ne!(id, 0);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, filter as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, filter as i32);
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        let texture = Self {
+            id,
+            uniform_name: String::from(uniform_name),
+            slot,
+            format,
+            size: Cell::new((0, 0)),
+        };
+        texture.resize(width, height);
+        texture
+    }
+
+    // Reallocates the texture storage. The content is lost
+    pub fn resize(&self, width: u32, height: u32) {
+        let (pixel_format, pixel_type) = self.format.pixel_format();
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                self.format as i32,
+                width as i32,
+                height as i32,
+                0,
+                pixel_format,
+                pixel_type,
+                std::ptr::null(),
+            );
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        self.size.set((width, height));
+    }
+
+    // Depth textures only. When enabled, shadow samplers ("uniform sampler2DShadow") return the
+    // result of comparing the reference value with the stored depth, filtered between texels
+    pub fn set_depth_compare(&self, enabled: bool) {
+        assert!(self.format.is_depth());
+        let mode = match enabled {
+            true => gl::COMPARE_REF_TO_TEXTURE,
+            false => gl::NONE,
+        };
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_COMPARE_MODE, mode as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_COMPARE_FUNC, gl::LEQUAL as i32);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+
+    pub(crate) fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn get_format(&self) -> TextureFormat {
+        self.format
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.size.get().0
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.size.get().1
+    }
+
     pub fn get_uniform_name(&self) -> &str {
         self.uniform_name.as_str()
     }
@@ -74,7 +381,10 @@ impl Texture {
     }
 
     pub fn unbind(&self) {
-        unsafe { gl::BindTexture(gl::TEXTURE_2D, 0) };
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + self.slot);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
     }
 }
 
@@ -0,0 +1,91 @@
+use std::cell::Cell;
+
+use nalgebra_glm as glm;
+
+// Translation, rotation and scale, applied in the order scale, rotate, translate. The matrix is
+// cached and only recomputed after a change
+#[derive(Debug, Clone)]
+pub struct Transform {
+    translation: glm::Vec3,
+    rotation: glm::Quat,
+    scale: glm::Vec3,
+    matrix: Cell<glm::Mat4>,
+    dirty: Cell<bool>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new(
+            glm::Vec3::zeros(),
+            glm::quat_identity(),
+            glm::vec3(1.0, 1.0, 1.0),
+        )
+    }
+}
+
+impl Transform {
+    pub fn new(translation: glm::Vec3, rotation: glm::Quat, scale: glm::Vec3) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+            matrix: Cell::new(glm::Mat4::identity()),
+            dirty: Cell::new(true),
+        }
+    }
+
+    pub fn from_translation(translation: glm::Vec3) -> Self {
+        Self {
+            translation,
+            ..Self::default()
+        }
+    }
+
+    pub fn get_translation(&self) -> glm::Vec3 {
+        self.translation
+    }
+
+    pub fn get_rotation(&self) -> glm::Quat {
+        self.rotation
+    }
+
+    pub fn get_scale(&self) -> glm::Vec3 {
+        self.scale
+    }
+
+    pub fn set_translation(&mut self, translation: glm::Vec3) {
+        self.translation = translation;
+        self.dirty.set(true);
+    }
+
+    pub fn set_rotation(&mut self, rotation: glm::Quat) {
+        self.rotation = glm::quat_normalize(&rotation);
+        self.dirty.set(true);
+    }
+
+    pub fn set_scale(&mut self, scale: glm::Vec3) {
+        self.scale = scale;
+        self.dirty.set(true);
+    }
+
+    pub fn translate(&mut self, offset: &glm::Vec3) {
+        self.set_translation(self.translation + offset);
+    }
+
+    // Rotates around an axis of the parent space, in radians
+    pub fn rotate(&mut self, angle: f32, axis: &glm::Vec3) {
+        self.set_rotation(glm::quat_angle_axis(angle, axis) * self.rotation);
+    }
+
+    // Local to parent space
+    pub fn get_matrix(&self) -> glm::Mat4 {
+        if self.dirty.get() {
+            let matrix = glm::translation(&self.translation)
+                * glm::quat_to_mat4(&self.rotation)
+                * glm::scaling(&self.scale);
+            self.matrix.set(matrix);
+            self.dirty.set(false);
+        }
+        self.matrix.get()
+    }
+}
@@ -0,0 +1,83 @@
+use bytemuck::Pod;
+use nalgebra_glm as glm;
+
+use crate::vertex_buffer_layout::{AttributeLocation, VertexBufferLayout, VertexBufferLayoutType};
+
+// Types that can be used as a field of a vertex struct
+pub trait VertexAttribute {
+    const LAYOUT_TYPE: VertexBufferLayoutType;
+    const COUNT: u32;
+}
+
+macro_rules! impl_vertex_attribute {
+    ($type:ty, $layout_type:ident, $count:expr) => {
+        impl VertexAttribute for $type {
+            const LAYOUT_TYPE: VertexBufferLayoutType = VertexBufferLayoutType::$layout_type;
+            const COUNT: u32 = $count;
+        }
+    };
+}
+
+impl_vertex_attribute!(f32, F32, 1);
+impl_vertex_attribute!([f32; 2], F32, 2);
+impl_vertex_attribute!([f32; 3], F32, 3);
+impl_vertex_attribute!([f32; 4], F32, 4);
+impl_vertex_attribute!(glm::Vec2, F32, 2);
+impl_vertex_attribute!(glm::Vec3, F32, 3);
+impl_vertex_attribute!(glm::Vec4, F32, 4);
+impl_vertex_attribute!(u32, U32, 1);
+impl_vertex_attribute!([u32; 2], U32, 2);
+impl_vertex_attribute!([u32; 3], U32, 3);
+impl_vertex_attribute!([u32; 4], U32, 4);
+impl_vertex_attribute!(glm::UVec2, U32, 2);
+impl_vertex_attribute!(glm::UVec3, U32, 3);
+impl_vertex_attribute!(glm::UVec4, U32, 4);
+
+// A #[repr(C)] struct describing a single vertex. Derive bytemuck::Pod and bytemuck::Zeroable
+// and implement it with impl_vertex!. Padding is not allowed by Pod, add explicit padding fields
+// instead; they are skipped if not listed in impl_vertex!
+pub trait Vertex: Pod {
+    fn layout() -> VertexBufferLayout;
+}
+
+// The field accessor is only used to infer the type of the field
+pub fn add_vertex_attribute<V, T: VertexAttribute>(
+    layout: &mut VertexBufferLayout,
+    offset: usize,
+    location: AttributeLocation,
+    _field: fn(&V) -> &T,
+) {
+    layout.add_with_offset(T::LAYOUT_TYPE, T::COUNT, false, offset as u32, location);
+}
+
+// Implements Vertex for a #[repr(C)] struct. Fields are added to the layout in the given order,
+// at the location of their position in the list unless one is given with "= N":
+//
+// impl_vertex!(MyVertex { position, tex_coords });
+// impl_vertex!(SkinVertex { joints = 4, weights = 5 });
+#[macro_export]
+macro_rules! impl_vertex {
+    (@location) => {
+        $crate::vertex_buffer_layout::AttributeLocation::Auto
+    };
+    (@location $location:expr) => {
+        $crate::vertex_buffer_layout::AttributeLocation::Index($location)
+    };
+    ($name:ty { $($field:ident $(= $location:expr)?),+ $(,)? }) => {
+        impl $crate::vertex::Vertex for $name {
+            fn layout() -> $crate::vertex_buffer_layout::VertexBufferLayout {
+                let mut layout = $crate::vertex_buffer_layout::VertexBufferLayout::default();
+                $(
+                    $crate::vertex::add_vertex_attribute(
+                        &mut layout,
+                        ::std::mem::offset_of!($name, $field),
+                        $crate::impl_vertex!(@location $($location)?),
+                        |vertex: &$name| &vertex.$field,
+                    );
+                )+
+                layout.set_stride(::std::mem::size_of::<$name>() as u32);
+                layout
+            }
+        }
+    };
+}
@@ -1,16 +1,54 @@
+use bytemuck::Pod;
 use gl;
 
 use crate::element_buffer::ElementBuffer;
+use crate::program::Program;
+use crate::vertex::Vertex;
 use crate::vertex_buffer::VertexBuffer;
-use crate::vertex_buffer_layout::VertexBufferLayout;
+use crate::vertex_buffer_layout::{AttributeLocation, VertexBufferLayout};
 
 pub struct VertexArray {
     id: u32,
     num_indices: u32,
+    num_vertices: u32,
+    // CPU copy of the element buffer, to check the draw ranges
+    indices: Vec<u32>,
+    // Attribute locations with an enabled vertex attribute array
+    attribute_locations: Vec<u32>,
 }
 
 impl VertexArray {
-    pub fn new<T: Sized>(vertices: &[T], indices: &[u32], layouts: &VertexBufferLayout) -> Self {
+    // The layout is taken from the vertex struct, see impl_vertex!
+    pub fn new<V: Vertex>(vertices: &[V], indices: &[u32]) -> Self {
+        Self::with_program(vertices, indices, &V::layout(), None)
+    }
+
+    // For vertex data without a vertex struct, for example, a flat [f32] array
+    pub fn with_layout<T: Pod>(
+        vertices: &[T],
+        indices: &[u32],
+        layouts: &VertexBufferLayout,
+    ) -> Self {
+        Self::with_program(vertices, indices, layouts, None)
+    }
+
+    // Required when the layout uses named attributes. Attributes not used by the program are
+    // skipped
+    pub fn new_for_program<T: Pod>(
+        vertices: &[T],
+        indices: &[u32],
+        layouts: &VertexBufferLayout,
+        program: &Program,
+    ) -> Self {
+        Self::with_program(vertices, indices, layouts, Some(program))
+    }
+
+    fn with_program<T: Pod>(
+        vertices: &[T],
+        indices: &[u32],
+        layouts: &VertexBufferLayout,
+        program: Option<&Program>,
+    ) -> Self {
         let mut id = 0;
         unsafe {
             gl::GenVertexArrays(1, &mut id);
@@ -18,25 +56,93 @@ impl VertexArray {
             gl::BindVertexArray(id);
         }
 
-        let ebo = ElementBuffer::new(&indices);
+        let ebo = ElementBuffer::new(indices);
         let num_indices = ebo.get_num_indices();
         ebo.bind();
 
-        let vbo = VertexBuffer::new(&vertices);
+        let vbo = VertexBuffer::new(vertices);
+        let num_vertices = Self::count_vertices(&vbo, layouts);
+        Self::check_indices(indices, num_vertices);
         vbo.bind();
-        Self::add_layouts(&layouts);
+        let attribute_locations = Self::add_layouts(layouts, program);
 
         vbo.unbind();
         unsafe { gl::BindVertexArray(0) };
 
-        Self { id, num_indices }
+        Self {
+            id,
+            num_indices,
+            num_vertices,
+            indices: indices.to_vec(),
+            attribute_locations,
+        }
+    }
+
+    // Adds the attributes of a second vertex buffer, for example, the skinning data of a mesh.
+    // It must have one element per vertex, at locations not used by the other buffers
+    pub fn add_vertex_buffer<V: Vertex>(&mut self, vertices: &[V]) {
+        assert_eq!(
+            vertices.len() as u32,
+            self.num_vertices,
+            "The vertex buffer doesn't have one element per vertex"
+        );
+
+        let layouts = V::layout();
+        unsafe { gl::BindVertexArray(self.id) };
+        let vbo = VertexBuffer::new(vertices);
+        vbo.bind();
+        let attribute_locations = Self::add_layouts(&layouts, None);
+        for location in attribute_locations {
+            assert!(
+                !self.attribute_locations.contains(&location),
+                "Attribute location {location} is already used"
+            );
+            self.attribute_locations.push(location);
+        }
+        vbo.unbind();
+        unsafe { gl::BindVertexArray(0) };
+    }
+
+    // The size of the vertex data must be a multiple of the layout stride, otherwise the layout
+    // doesn't describe the data
+    fn count_vertices<T: Pod>(vbo: &VertexBuffer<T>, layouts: &VertexBufferLayout) -> u32 {
+        let size = vbo.get_num_elements() * std::mem::size_of::<T>() as u32;
+        let stride = layouts.get_stride();
+        assert_ne!(stride, 0);
+        assert_eq!(
+            size % stride,
+            0,
+            "Vertex data size ({size} bytes) is not a multiple of the layout stride ({stride} bytes)"
+        );
+        size / stride
+    }
+
+    fn check_indices(indices: &[u32], num_vertices: u32) {
+        if let Some(&max_index) = indices.iter().max() {
+            assert!(
+                max_index < num_vertices,
+                "Index {max_index} out of range, the vertex array has {num_vertices} vertices"
+            );
+        }
     }
 
-    fn add_layouts(layouts: &VertexBufferLayout) {
-        let mut location = 0;
+    fn add_layouts(layouts: &VertexBufferLayout, program: Option<&Program>) -> Vec<u32> {
+        let mut attribute_locations = Vec::new();
         let stride = layouts.get_stride();
 
-        for layout in layouts.get_layouts() {
+        for (index, layout) in layouts.get_layouts().iter().enumerate() {
+            let location = match &layout.location {
+                AttributeLocation::Auto => index as u32,
+                AttributeLocation::Index(location) => *location,
+                AttributeLocation::Name(name) => {
+                    let program = program.expect("Named attributes require a program");
+                    match program.get_attribute_location(name) {
+                        Some(location) => location,
+                        None => continue,
+                    }
+                }
+            };
+
             let normalized = match layout.normalized {
                 true => gl::TRUE,
                 false => gl::FALSE,
@@ -54,8 +160,22 @@ impl VertexArray {
                 gl::EnableVertexAttribArray(location);
             }
 
-            location += 1;
+            attribute_locations.push(location);
         }
+
+        attribute_locations
+    }
+
+    pub fn get_num_vertices(&self) -> u32 {
+        self.num_vertices
+    }
+
+    pub fn get_indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    pub fn get_attribute_locations(&self) -> &[u32] {
+        &self.attribute_locations
     }
 
     pub fn get_num_indices_to_draw(&self) -> u32 {
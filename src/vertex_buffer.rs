@@ -1,43 +1,29 @@
-use gl;
+use bytemuck::Pod;
 
-pub struct VertexBuffer {
-    id: u32,
-}
-
-impl VertexBuffer {
-    pub fn new<T: Sized>(vertices: &[T]) -> Self {
-        assert_ne!(vertices.len(), 0);
+use crate::buffer::{Buffer, BufferTarget};
 
-        let mut id = 0;
-        let size = vertices.len() * std::mem::size_of_val(&vertices[0]);
+pub struct VertexBuffer<T: Pod> {
+    buffer: Buffer<T>,
+}
 
-        unsafe {
-            gl::GenBuffers(1, &mut id);
-            assert_ne!(id, 0);
-            gl::BindBuffer(gl::ARRAY_BUFFER, id);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                size as isize,
-                vertices.as_ptr().cast(),
-                gl::STATIC_DRAW,
-            );
-            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+impl<T: Pod> VertexBuffer<T> {
+    pub fn new(vertices: &[T]) -> Self {
+        Self {
+            buffer: Buffer::new(BufferTarget::Array, vertices),
         }
+    }
 
-        Self { id }
+    // Number of elements of type T. When T is a scalar (for example, a flat [f32] array) this is
+    // not the number of vertices
+    pub fn get_num_elements(&self) -> u32 {
+        self.buffer.len() as u32
     }
 
     pub fn bind(&self) {
-        unsafe { gl::BindBuffer(gl::ARRAY_BUFFER, self.id) };
+        self.buffer.bind();
     }
 
     pub fn unbind(&self) {
-        unsafe { gl::BindBuffer(gl::ARRAY_BUFFER, 0) };
-    }
-}
-
-impl Drop for VertexBuffer {
-    fn drop(&mut self) {
-        unsafe { gl::DeleteBuffers(1, &mut self.id) };
+        self.buffer.unbind();
     }
 }
@@ -7,11 +7,24 @@ pub enum VertexBufferLayoutType {
     U32 = gl::UNSIGNED_INT,
 }
 
+// Where the attribute is bound in the vertex shader
+#[derive(Debug, Clone, Default)]
+pub enum AttributeLocation {
+    // Position of the element in the layout, starting at 0
+    #[default]
+    Auto,
+    // Matches "layout (location = N)" in the shader
+    Index(u32),
+    // Resolved against the program with glGetAttribLocation
+    Name(String),
+}
+
 pub struct VertexBufferLayoutElement {
     pub layout_type: VertexBufferLayoutType,
     pub count: u32,
     pub normalized: bool,
     pub offset: u32,
+    pub location: AttributeLocation,
 }
 
 impl VertexBufferLayoutElement {
@@ -37,17 +50,56 @@ impl VertexBufferLayout {
     }
 
     pub fn add(&mut self, layout_type: VertexBufferLayoutType, count: u32, normalized: bool) {
+        self.add_with_location(layout_type, count, normalized, AttributeLocation::Auto);
+    }
+
+    pub fn add_with_location(
+        &mut self,
+        layout_type: VertexBufferLayoutType,
+        count: u32,
+        normalized: bool,
+        location: AttributeLocation,
+    ) {
         let layout_element = VertexBufferLayoutElement {
             layout_type,
             count,
             normalized,
             offset: self.stride,
+            location,
         };
 
         self.stride += count * layout_element.size_of_layout_type();
         self.layouts.push(layout_element);
     }
 
+    // Used when the vertex data comes from a struct: the offset of each field and the stride are
+    // taken from the struct itself so padding is respected
+    pub fn add_with_offset(
+        &mut self,
+        layout_type: VertexBufferLayoutType,
+        count: u32,
+        normalized: bool,
+        offset: u32,
+        location: AttributeLocation,
+    ) {
+        let layout_element = VertexBufferLayoutElement {
+            layout_type,
+            count,
+            normalized,
+            offset,
+            location,
+        };
+
+        let end = offset + count * layout_element.size_of_layout_type();
+        self.stride = self.stride.max(end);
+        self.layouts.push(layout_element);
+    }
+
+    pub fn set_stride(&mut self, stride: u32) {
+        assert!(stride >= self.stride);
+        self.stride = stride;
+    }
+
     pub fn get_stride(&self) -> u32 {
         self.stride
     }
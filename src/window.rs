@@ -1,35 +1,163 @@
-use gl;
-use glfw::{fail_on_errors, Context};
+use std::cell::Cell;
+use std::rc::Rc;
+
+use glfw::Context;
+
+use crate::application::Application;
+use crate::event::{Event, Key, MouseButton};
+use crate::frame_clock::FrameClock;
+use crate::input_state::InputState;
+use crate::render_state::Rect;
+use crate::window_builder::WindowBuilder;
 
 pub struct Window {
     pub glfw: glfw::Glfw,
     pub window: glfw::PWindow,
+    pub events: glfw::GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input: InputState,
+    clock: FrameClock,
+    // Updated by the framebuffer size callback, which also runs for loops that call
+    // glfw.poll_events() directly instead of run()
+    framebuffer_size: Rc<Cell<(u32, u32)>>,
 }
 
 impl Window {
     pub fn new(width: u32, height: u32, title: &str) -> Self {
-        let mut glfw = glfw::init(glfw::fail_on_errors!()).unwrap();
-        glfw.window_hint(glfw::WindowHint::ContextVersionMajor(3));
-        glfw.window_hint(glfw::WindowHint::ContextVersionMinor(3));
-        glfw.window_hint(glfw::WindowHint::OpenGlProfile(
-            glfw::OpenGlProfileHint::Core,
-        ));
-
-        let (mut window, _) = glfw
-            .create_window(width, height, title, glfw::WindowMode::Windowed)
-            .expect("Failed to create window");
-
-        window.make_current();
-        window.set_framebuffer_size_callback(|_, width, height| unsafe {
-            gl::Viewport(0, 0, width, height)
+        WindowBuilder::new(width, height, title).build()
+    }
+
+    // Anti-aliased window with the given number of samples per pixel (for example, 4). 0 disables
+    // multisampling
+    pub fn new_multisampled(width: u32, height: u32, title: &str, samples: u32) -> Self {
+        WindowBuilder::new(width, height, title)
+            .samples(samples)
+            .build()
+    }
+
+    pub(crate) fn from_glfw(
+        glfw: glfw::Glfw,
+        mut window: glfw::PWindow,
+        events: glfw::GlfwReceiver<(f64, glfw::WindowEvent)>,
+    ) -> Self {
+        let (width, height) = window.get_framebuffer_size();
+        let framebuffer_size = Rc::new(Cell::new((width as u32, height as u32)));
+
+        let size = framebuffer_size.clone();
+        window.set_framebuffer_size_callback(move |_, width, height| {
+            unsafe { gl::Viewport(0, 0, width, height) };
+            size.set((width as u32, height as u32));
         });
 
-        gl::load_with(|ptr| window.get_proc_address(ptr) as *const _);
+        Self {
+            glfw,
+            window,
+            events,
+            input: InputState::default(),
+            clock: FrameClock::default(),
+            framebuffer_size,
+        }
+    }
+
+    // Runs the application until the window is closed: events, fixed updates, update, render,
+    // swap buffers
+    pub fn run(&mut self, app: &mut impl Application) {
+        // Don't count the setup before run() as the first frame
+        self.clock.reset();
+
+        while !self.window.should_close() {
+            for event in self.poll_events() {
+                if let Event::Resize { width, height } = event {
+                    app.on_resize(self, width, height);
+                }
+                app.on_event(self, &event);
+            }
+
+            let dt = self.clock.tick();
+            while self.clock.step_fixed() {
+                app.fixed_update(self, self.clock.get_fixed_timestep() as f32);
+            }
+            app.update(self, dt as f32);
+
+            app.render(self);
+
+            self.window.swap_buffers();
+            self.clock.limit_frame_rate();
+        }
+    }
+
+    // Polls the pending events and updates the input state. Called by run(), use it when writing
+    // your own loop
+    pub fn poll_events(&mut self) -> Vec<Event> {
+        self.input.end_frame();
+        self.glfw.poll_events();
+
+        let events: Vec<Event> = glfw::flush_messages(&self.events)
+            .filter_map(|(_, event)| Event::from_glfw(event))
+            .collect();
+        events
+            .iter()
+            .for_each(|event| self.input.handle_event(event));
+
+        events
+    }
+
+    pub fn get_input(&self) -> &InputState {
+        &self.input
+    }
+
+    pub fn is_key_down(&self, key: Key) -> bool {
+        self.input.is_key_down(key)
+    }
+
+    pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.input.is_mouse_button_down(button)
+    }
+
+    pub fn close(&mut self) {
+        self.window.set_should_close(true);
+    }
+
+    // Seconds since the window was created
+    pub fn get_time(&self) -> f64 {
+        self.glfw.get_time()
+    }
+
+    pub fn get_clock(&self) -> &FrameClock {
+        &self.clock
+    }
+
+    pub fn get_clock_mut(&mut self) -> &mut FrameClock {
+        &mut self.clock
+    }
+
+    // Size in pixels, use it for viewports and framebuffers. On HiDPI displays it is bigger than
+    // the window size
+    pub fn get_framebuffer_size(&self) -> (u32, u32) {
+        self.framebuffer_size.get()
+    }
+
+    // Rectangle covering the whole framebuffer, the viewport set after a resize
+    pub fn get_viewport(&self) -> Rect {
+        let (width, height) = self.get_framebuffer_size();
+        Rect::new(0, 0, width as i32, height as i32)
+    }
+
+    pub fn get_aspect_ratio(&self) -> f32 {
+        self.get_viewport().get_aspect_ratio()
+    }
 
-        Self { glfw, window }
+    // Framebuffer pixels per window unit, for example, (2.0, 2.0) on a Retina display
+    pub fn get_framebuffer_scale(&self) -> (f32, f32) {
+        let (framebuffer_width, framebuffer_height) = self.window.get_framebuffer_size();
+        let (width, height) = self.window.get_size();
+        (
+            framebuffer_width as f32 / width.max(1) as f32,
+            framebuffer_height as f32 / height.max(1) as f32,
+        )
     }
 
-    pub fn get_time(&self) -> f32 {
-        self.glfw.get_time() as f32
+    // Scale requested by the OS for the content (text, UI...) of the window
+    pub fn get_content_scale(&self) -> (f32, f32) {
+        self.window.get_content_scale()
     }
 }
@@ -0,0 +1,253 @@
+use std::ffi::CStr;
+
+use gl;
+use glfw::{fail_on_errors, Context};
+
+use crate::window::Window;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenGlProfile {
+    Core,
+    Compatibility,
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fullscreen {
+    PrimaryMonitor,
+    // Index in the list of connected monitors
+    Monitor(usize),
+}
+
+pub struct WindowBuilder {
+    width: u32,
+    height: u32,
+    title: String,
+    gl_version: (u32, u32),
+    profile: OpenGlProfile,
+    debug_context: bool,
+    swap_interval: glfw::SwapInterval,
+    fullscreen: Option<Fullscreen>,
+    borderless: bool,
+    resizable: bool,
+    position: Option<(i32, i32)>,
+    depth_bits: Option<u32>,
+    stencil_bits: Option<u32>,
+    samples: u32,
+}
+
+impl WindowBuilder {
+    // Defaults: OpenGL 3.3 core, no vsync, resizable, windowed
+    pub fn new(width: u32, height: u32, title: &str) -> Self {
+        Self {
+            width,
+            height,
+            title: String::from(title),
+            gl_version: (3, 3),
+            profile: OpenGlProfile::Core,
+            debug_context: false,
+            swap_interval: glfw::SwapInterval::None,
+            fullscreen: None,
+            borderless: false,
+            resizable: true,
+            position: None,
+            depth_bits: Some(24),
+            stencil_bits: Some(8),
+            samples: 0,
+        }
+    }
+
+    pub fn gl_version(mut self, major: u32, minor: u32) -> Self {
+        self.gl_version = (major, minor);
+        self
+    }
+
+    pub fn profile(mut self, profile: OpenGlProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    // Driver messages are printed to stderr. Requires an OpenGL 4.3 context, a warning is printed
+    // otherwise
+    pub fn debug_context(mut self, debug_context: bool) -> Self {
+        self.debug_context = debug_context;
+        self
+    }
+
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.swap_interval = match vsync {
+            true => glfw::SwapInterval::Sync(1),
+            false => glfw::SwapInterval::None,
+        };
+        self
+    }
+
+    pub fn swap_interval(mut self, swap_interval: glfw::SwapInterval) -> Self {
+        self.swap_interval = swap_interval;
+        self
+    }
+
+    // The window takes the current video mode of the monitor, width and height are ignored
+    pub fn fullscreen(mut self, fullscreen: Fullscreen) -> Self {
+        self.fullscreen = Some(fullscreen);
+        self
+    }
+
+    pub fn borderless(mut self, borderless: bool) -> Self {
+        self.borderless = borderless;
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    pub fn depth_bits(mut self, depth_bits: Option<u32>) -> Self {
+        self.depth_bits = depth_bits;
+        self
+    }
+
+    pub fn stencil_bits(mut self, stencil_bits: Option<u32>) -> Self {
+        self.stencil_bits = stencil_bits;
+        self
+    }
+
+    // Samples per pixel (for example, 4). 0 disables multisampling
+    pub fn samples(mut self, samples: u32) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn build(self) -> Window {
+        let mut glfw = glfw::init(glfw::fail_on_errors!()).unwrap();
+        self.set_window_hints(&mut glfw);
+
+        let (mut window, events) = match self.fullscreen {
+            None => glfw.create_window(
+                self.width,
+                self.height,
+                &self.title,
+                glfw::WindowMode::Windowed,
+            ),
+            Some(fullscreen) => glfw.with_connected_monitors(|glfw, monitors| {
+                let monitor = match fullscreen {
+                    // The primary monitor is always the first one
+                    Fullscreen::PrimaryMonitor => monitors.first(),
+                    Fullscreen::Monitor(index) => monitors.get(index),
+                }
+                .expect("Monitor not found");
+
+                let video_mode = monitor.get_video_mode().expect("Error getting video mode");
+                glfw.window_hint(glfw::WindowHint::RefreshRate(Some(video_mode.refresh_rate)));
+                glfw.create_window(
+                    video_mode.width,
+                    video_mode.height,
+                    &self.title,
+                    glfw::WindowMode::FullScreen(monitor),
+                )
+            }),
+        }
+        .expect("Failed to create window");
+
+        if let (Some((x, y)), None) = (self.position, self.fullscreen) {
+            window.set_pos(x, y);
+        }
+
+        window.set_key_polling(true);
+        window.set_char_polling(true);
+        window.set_mouse_button_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        window.set_drag_and_drop_polling(true);
+        window.set_focus_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_close_polling(true);
+
+        window.make_current();
+        glfw.set_swap_interval(self.swap_interval);
+
+        gl::load_with(|ptr| window.get_proc_address(ptr) as *const _);
+
+        if self.samples > 0 {
+            unsafe { gl::Enable(gl::MULTISAMPLE) };
+        }
+
+        if self.debug_context {
+            Self::enable_debug_output();
+        }
+
+        Window::from_glfw(glfw, window, events)
+    }
+
+    // The driver may create a newer context than requested, so the version of the context is
+    // checked rather than the requested one
+    fn enable_debug_output() {
+        let (mut major, mut minor) = (0, 0);
+        unsafe {
+            gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+            gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+        }
+        if (major, minor) < (4, 3) {
+            eprintln!("Debug output requires OpenGL 4.3, the context is {major}.{minor}");
+            return;
+        }
+
+        unsafe {
+            gl::Enable(gl::DEBUG_OUTPUT);
+            gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+            gl::DebugMessageCallback(Some(debug_message_callback), std::ptr::null());
+        }
+    }
+
+    fn set_window_hints(&self, glfw: &mut glfw::Glfw) {
+        let (major, minor) = self.gl_version;
+        glfw.window_hint(glfw::WindowHint::ContextVersion(major, minor));
+        glfw.window_hint(glfw::WindowHint::OpenGlProfile(match self.profile {
+            OpenGlProfile::Core => glfw::OpenGlProfileHint::Core,
+            OpenGlProfile::Compatibility => glfw::OpenGlProfileHint::Compat,
+            OpenGlProfile::Any => glfw::OpenGlProfileHint::Any,
+        }));
+        // Required by macOS to get a core profile
+        if self.profile == OpenGlProfile::Core {
+            glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+        }
+        glfw.window_hint(glfw::WindowHint::OpenGlDebugContext(self.debug_context));
+
+        glfw.window_hint(glfw::WindowHint::Resizable(self.resizable));
+        glfw.window_hint(glfw::WindowHint::Decorated(!self.borderless));
+        glfw.window_hint(glfw::WindowHint::DepthBits(self.depth_bits));
+        glfw.window_hint(glfw::WindowHint::StencilBits(self.stencil_bits));
+        if self.samples > 0 {
+            glfw.window_hint(glfw::WindowHint::Samples(Some(self.samples)));
+        }
+
+        // HiDPI: scale the window with the monitor and use a full resolution framebuffer
+        glfw.window_hint(glfw::WindowHint::ScaleToMonitor(true));
+        glfw.window_hint(glfw::WindowHint::CocoaRetinaFramebuffer(true));
+    }
+}
+
+extern "system" fn debug_message_callback(
+    _source: u32,
+    _message_type: u32,
+    id: u32,
+    severity: u32,
+    _length: i32,
+    message: *const gl::types::GLchar,
+    _user_param: *mut std::ffi::c_void,
+) {
+    let severity = match severity {
+        gl::DEBUG_SEVERITY_HIGH => "high",
+        gl::DEBUG_SEVERITY_MEDIUM => "medium",
+        gl::DEBUG_SEVERITY_LOW => "low",
+        _ => "notification",
+    };
+    let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+    eprintln!("OpenGL [{severity}] ({id}): {message}");
+}